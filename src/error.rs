@@ -0,0 +1,167 @@
+//! Defines the error type returned when reading XLIFF documents.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::str::Utf8Error;
+
+/// Location of a problem within the XLIFF document being read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    /// Line number, starting at 1.
+    pub line: usize,
+    /// Column number within the line, starting at 1 and counted in bytes.
+    pub column: usize,
+    /// Byte offset from the start of the document.
+    pub offset: usize,
+}
+
+impl Position {
+    /// Creates a position from a byte offset and the offsets of all line breaks preceding it.
+    pub(crate) fn locate(offset: usize, line_breaks: &[usize]) -> Self {
+        let preceding = line_breaks.iter().take_while(|&&b| b < offset).count();
+        let line_start = match preceding {
+            0 => 0,
+            n => line_breaks[n - 1] + 1,
+        };
+
+        Position {
+            line: preceding + 1,
+            column: offset - line_start + 1,
+            offset,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {} (byte {})",
+            self.line, self.column, self.offset
+        )
+    }
+}
+
+/// Errors which can occur while reading an XLIFF document.
+#[derive(Debug)]
+pub enum XliffError {
+    /// The underlying source could not be read.
+    Io(io::Error),
+    /// The document is not well-formed XML.
+    Xml {
+        /// The error reported by the XML parser.
+        source: quick_xml::Error,
+        /// Where the error was detected.
+        position: Position,
+    },
+    /// The document contains text or attribute values which are not valid UTF-8.
+    InvalidUtf8 {
+        /// The conversion error.
+        source: Utf8Error,
+        /// Where the invalid value was found.
+        position: Position,
+    },
+    /// The document is well-formed XML but violates the structure required by XLIFF,
+    /// for example a `<trans-unit>` outside of a `<file>` element.
+    Structure {
+        /// Description of the violation.
+        message: String,
+        /// Where the violation was found.
+        position: Position,
+    },
+}
+
+impl XliffError {
+    /// Returns the location of the error within the document, if it is known.
+    pub fn position(&self) -> Option<&Position> {
+        match self {
+            XliffError::Io(_) => None,
+            XliffError::Xml { position, .. } => Some(position),
+            XliffError::InvalidUtf8 { position, .. } => Some(position),
+            XliffError::Structure { position, .. } => Some(position),
+        }
+    }
+
+    /// Wraps an XML parser error reported at the given byte offset.
+    pub(crate) fn xml(source: quick_xml::Error, offset: usize) -> Self {
+        let position = Position {
+            offset,
+            ..Position::default()
+        };
+        match source {
+            quick_xml::Error::Io(e) => XliffError::Io(e),
+            quick_xml::Error::Utf8(source) => XliffError::InvalidUtf8 { source, position },
+            source => XliffError::Xml { source, position },
+        }
+    }
+
+    /// Wraps an invalid UTF-8 value found at the given byte offset.
+    pub(crate) fn utf8(source: Utf8Error, offset: usize) -> Self {
+        XliffError::InvalidUtf8 {
+            source,
+            position: Position {
+                offset,
+                ..Position::default()
+            },
+        }
+    }
+
+    /// Creates a structural error reported at the given byte offset.
+    pub(crate) fn structure<S: Into<String>>(message: S, offset: usize) -> Self {
+        XliffError::Structure {
+            message: message.into(),
+            position: Position {
+                offset,
+                ..Position::default()
+            },
+        }
+    }
+
+    /// Resolves the line and column of the error from the byte offset it was reported at.
+    pub(crate) fn located(mut self, line_breaks: &[usize]) -> Self {
+        match &mut self {
+            XliffError::Io(_) => (),
+            XliffError::Xml { position, .. }
+            | XliffError::InvalidUtf8 { position, .. }
+            | XliffError::Structure { position, .. } => {
+                *position = Position::locate(position.offset, line_breaks);
+            }
+        }
+        self
+    }
+}
+
+impl fmt::Display for XliffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            XliffError::Io(e) => write!(f, "I/O error: {}", e),
+            XliffError::Xml { source, position } => {
+                write!(f, "malformed XML at {}: {}", position, source)
+            }
+            XliffError::InvalidUtf8 { source, position } => {
+                write!(f, "invalid UTF-8 at {}: {}", position, source)
+            }
+            XliffError::Structure { message, position } => {
+                write!(f, "invalid XLIFF structure at {}: {}", position, message)
+            }
+        }
+    }
+}
+
+impl Error for XliffError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            XliffError::Io(e) => Some(e),
+            XliffError::Xml { source, .. } => Some(source),
+            XliffError::InvalidUtf8 { source, .. } => Some(source),
+            XliffError::Structure { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for XliffError {
+    fn from(error: io::Error) -> Self {
+        XliffError::Io(error)
+    }
+}
@@ -13,11 +13,11 @@
 //! use std::env;
 //! use xliff::t::T;
 //!
-//! let translations = T::load("./en.xliff");
+//! let translations = T::load("./en.xliff").expect("failed to load translations");
 //!
 //!    match translations.t_source(None, "Some text") {
 //!        None => println!("translation not found"),
-//!        Some(unit) => println!("> {}", unit.target_text().unwrap_or(&String::new())),
+//!        Some(unit) => println!("> {}", unit.target_text().unwrap_or_default()),
 //!    }
 //!```
 //!
@@ -27,11 +27,11 @@
 //! use std::env;
 //! use xliff::t::T;
 //!
-//! let translations = T::load("./en.xliff");
+//! let translations = T::load("./en.xliff").expect("failed to load translations");
 //!
 //!    match translations.t(None, "fIC-hX-uRv.text") {
 //!        None => println!("translation not found"),
-//!        Some(unit) => println!("> {}", unit.target_text().unwrap_or(&String::new())),
+//!        Some(unit) => println!("> {}", unit.target_text().unwrap_or_default()),
 //!    }
 //!```
 //!
@@ -45,6 +45,9 @@
 #![deny(missing_docs)]
 #![recursion_limit = "1024"]
 
+pub mod error;
+pub mod merge;
+mod readers;
 pub mod store;
 pub mod t;
 pub mod writers;
@@ -0,0 +1,347 @@
+//! Rebuilds the translated original document of a `<file>` from its skeleton.
+//!
+//! The `<skl>` element of a `<header>` holds the original document with the text of each
+//! translation unit replaced by a placeholder. XLIFF 1.2 leaves the format of the skeleton to
+//! the tool which extracted the document, and each tool uses its own, so the syntax of the
+//! placeholders is given by `Placeholders`. `OKAPI_PLACEHOLDERS` reads the reference markers
+//! written by the filters of the Okapi Framework, such as `[#$tu1]`, and a callback can be
+//! provided for other tools. `merge_file` and `merge_skeleton` use `DEFAULT_PLACEHOLDERS`, the
+//! identifier of the `<trans-unit>` enclosed in `%%%`, for example `%%%greeting%%%`.
+//! Merging replaces each placeholder with the text of the `<target>` of the unit, or of its
+//! `<source>` when the unit has not been translated.
+//!
+//! The text is escaped as required by the `datatype` of the file, or of the unit when it has its
+//! own. Plain text is written as is, while XML and HTML documents have their markup characters
+//! escaped. Formats such as C strings or Java properties would need their quotes, backslashes
+//! and line breaks escaped, so they are not supported and merging them is an error. Supported
+//! data types are listed in `PLAINTEXT_DATA_TYPES` and `MARKUP_DATA_TYPES`.
+//!
+//! The native code held by `<ph>`, `<bpt>`, `<ept>` and `<it>` is written back as is. Units
+//! holding `<x/>`, `<bx/>` or `<ex/>`, or `<g>` within markup, cannot be merged as the native
+//! code they stand for is not part of the document.
+//!
+//! # Example
+//! ```rust,no_run
+//! use xliff::merge;
+//! use xliff::t::T;
+//!
+//! let translations = T::load("./strings.xliff").expect("failed to load translations");
+//! let file = &translations.store.groups[0];
+//! let original = merge::merge_file(file).expect("failed to merge translations");
+//! let original = merge::merge_file_with(file, merge::OKAPI_PLACEHOLDERS)
+//!     .expect("failed to merge translations");
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::str::Utf8Error;
+
+use crate::store::{BinFile, ContentPart, InlineKind, TranslationFile, Unit};
+
+/// The marker written before and after the unit identifier of a placeholder.
+pub const PLACEHOLDER_MARKER: &str = "%%%";
+
+/// Placeholders made of the unit identifier enclosed in `PLACEHOLDER_MARKER`.
+pub const DEFAULT_PLACEHOLDERS: Placeholders<'static> = Placeholders::Delimited {
+    start: PLACEHOLDER_MARKER,
+    end: PLACEHOLDER_MARKER,
+};
+
+/// The reference markers written in skeletons by the Okapi Framework, such as `[#$tu1]`.
+pub const OKAPI_PLACEHOLDERS: Placeholders<'static> = Placeholders::Delimited {
+    start: "[#$",
+    end: "]",
+};
+
+/// The `datatype` values of the documents whose text is merged without escaping.
+pub const PLAINTEXT_DATA_TYPES: &[&str] = &["plaintext"];
+
+/// The `datatype` values of the documents whose text is merged with the characters `&`, `<`,
+/// `>`, `"` and `'` escaped.
+pub const MARKUP_DATA_TYPES: &[&str] = &["xml", "html", "xhtml"];
+
+/// The syntax of the placeholders of a skeleton.
+#[derive(Clone, Copy)]
+pub enum Placeholders<'a> {
+    /// The identifier of the unit enclosed between two markers.
+    Delimited {
+        /// The marker written before the identifier.
+        start: &'a str,
+        /// The marker written after the identifier.
+        end: &'a str,
+    },
+    /// A callback finding the next placeholder in the provided rest of the skeleton. It returns
+    /// the byte range of the placeholder within the text along with the identifier of the unit,
+    /// or `None` when there is no placeholder left.
+    Custom(&'a FindPlaceholder),
+}
+
+/// A callback finding the next placeholder of a skeleton, see `Placeholders::Custom`.
+pub type FindPlaceholder = dyn Fn(&str) -> Option<(Range<usize>, String)>;
+
+/// Errors which can occur while merging translations into a skeleton.
+#[derive(Debug)]
+pub enum MergeError {
+    /// The `<header>` of the file has no `<skl>` element.
+    MissingSkeleton,
+    /// The skeleton is an `<external-file>`, it must be loaded and provided to `merge_skeleton`.
+    ExternalSkeleton {
+        /// The location of the skeleton.
+        href: String,
+    },
+    /// The file does not hold a plain text document.
+    UnsupportedDataType(String),
+    /// The skeleton is not valid UTF-8 text.
+    InvalidUtf8(Utf8Error),
+    /// A placeholder is not closed by a second marker.
+    UnclosedPlaceholder {
+        /// Byte offset of the placeholder within the skeleton.
+        offset: usize,
+    },
+    /// A placeholder found by a `Placeholders::Custom` callback is empty or is not placed
+    /// within the text provided to the callback.
+    InvalidPlaceholder {
+        /// Byte offset within the skeleton of the text provided to the callback.
+        offset: usize,
+    },
+    /// A placeholder refers to a translation unit which is not part of the file.
+    UnknownUnit(String),
+    /// The translation unit holds an inline code whose native code is not part of the document,
+    /// such as `<x/>`.
+    MissingNativeCode(String),
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MergeError::MissingSkeleton => write!(f, "the file has no skeleton"),
+            MergeError::ExternalSkeleton { href } => {
+                write!(
+                    f,
+                    "the skeleton is stored outside of the document: {}",
+                    href
+                )
+            }
+            MergeError::UnsupportedDataType(data_type) => {
+                write!(f, "cannot merge a skeleton of datatype {}", data_type)
+            }
+            MergeError::InvalidUtf8(e) => write!(f, "the skeleton is not valid UTF-8: {}", e),
+            MergeError::UnclosedPlaceholder { offset } => {
+                write!(f, "unclosed placeholder at byte {} of the skeleton", offset)
+            }
+            MergeError::InvalidPlaceholder { offset } => {
+                write!(
+                    f,
+                    "invalid placeholder after byte {} of the skeleton",
+                    offset
+                )
+            }
+            MergeError::MissingNativeCode(id) => {
+                write!(
+                    f,
+                    "the translation unit {} holds inline codes without their native code",
+                    id
+                )
+            }
+            MergeError::UnknownUnit(id) => {
+                write!(
+                    f,
+                    "the skeleton refers to an unknown translation unit {}",
+                    id
+                )
+            }
+        }
+    }
+}
+
+impl Error for MergeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MergeError::InvalidUtf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Rebuilds the translated original document from the `<skl>` held in the file, using the
+/// `DEFAULT_PLACEHOLDERS`.
+///
+/// # Errors
+/// Returns an error if the file has no internal skeleton, if its skeleton contains an invalid
+/// placeholder, or if the text of a unit cannot be written in the document. See `merge_file_with`.
+pub fn merge_file(file: &TranslationFile) -> Result<String, MergeError> {
+    merge_file_with(file, DEFAULT_PLACEHOLDERS)
+}
+
+/// Rebuilds the translated original document from the `<skl>` held in the file, finding the
+/// placeholders of the skeleton with the given syntax.
+///
+/// # Errors
+/// Returns an error if the file has no internal skeleton, if its skeleton contains an invalid
+/// placeholder, or if the text of a unit cannot be written in the document because its data type
+/// is not supported or it holds inline codes without their native code.
+pub fn merge_file_with(
+    file: &TranslationFile,
+    placeholders: Placeholders,
+) -> Result<String, MergeError> {
+    let skeleton = match file.header.as_ref().and_then(|h| h.skeleton.as_ref()) {
+        None => return Err(MergeError::MissingSkeleton),
+        Some(BinFile::External { href }) => {
+            return Err(MergeError::ExternalSkeleton { href: href.clone() })
+        }
+        Some(BinFile::Internal { data, .. }) => data,
+    };
+    let skeleton = std::str::from_utf8(skeleton).map_err(MergeError::InvalidUtf8)?;
+    merge_skeleton_with(file, skeleton, placeholders)
+}
+
+/// Rebuilds the translated original document from the provided skeleton, such as the contents
+/// of the `<external-file>` referred to by the `<skl>` of the file, using the
+/// `DEFAULT_PLACEHOLDERS`.
+///
+/// # Errors
+/// See `merge_skeleton_with`.
+pub fn merge_skeleton(file: &TranslationFile, skeleton: &str) -> Result<String, MergeError> {
+    merge_skeleton_with(file, skeleton, DEFAULT_PLACEHOLDERS)
+}
+
+/// Rebuilds the translated original document from the provided skeleton, finding its
+/// placeholders with the given syntax.
+///
+/// # Errors
+/// Returns an error if the skeleton contains an invalid placeholder, or if the text of a unit
+/// cannot be written in the document because its data type is not supported or it holds inline
+/// codes without their native code.
+pub fn merge_skeleton_with(
+    file: &TranslationFile,
+    skeleton: &str,
+    placeholders: Placeholders,
+) -> Result<String, MergeError> {
+    escaping(&file.data_type)?;
+
+    let units = file.units();
+    let mut merged = String::with_capacity(skeleton.len());
+    let mut rest = skeleton;
+    while let Some((range, id)) =
+        next_placeholder(rest, placeholders).map_err(|e| shift(e, skeleton.len() - rest.len()))?
+    {
+        merged.push_str(&rest[..range.start]);
+        match units.iter().find(|unit| unit.id == id) {
+            None => return Err(MergeError::UnknownUnit(id)),
+            Some(unit) => merged.push_str(&merged_text(unit, &file.data_type)?),
+        }
+        rest = &rest[range.end..];
+    }
+    merged.push_str(rest);
+    Ok(merged)
+}
+
+/// Finds the next placeholder of the text, returning its byte range and the unit identifier.
+fn next_placeholder(
+    text: &str,
+    placeholders: Placeholders,
+) -> Result<Option<(Range<usize>, String)>, MergeError> {
+    match placeholders {
+        Placeholders::Delimited { start, end } => {
+            let placeholder_start = match text.find(start) {
+                None => return Ok(None),
+                Some(placeholder_start) => placeholder_start,
+            };
+            let id_start = placeholder_start + start.len();
+            match text[id_start..].find(end) {
+                None => Err(MergeError::UnclosedPlaceholder {
+                    offset: placeholder_start,
+                }),
+                Some(len) => Ok(Some((
+                    placeholder_start..id_start + len + end.len(),
+                    String::from(&text[id_start..id_start + len]),
+                ))),
+            }
+        }
+        Placeholders::Custom(find) => match find(text) {
+            None => Ok(None),
+            Some((range, id)) if !range.is_empty() && text.get(range.clone()).is_some() => {
+                Ok(Some((range, id)))
+            }
+            Some(_) => Err(MergeError::InvalidPlaceholder { offset: 0 }),
+        },
+    }
+}
+
+/// Makes the offset of an error relative to the start of the skeleton.
+fn shift(error: MergeError, by: usize) -> MergeError {
+    match error {
+        MergeError::UnclosedPlaceholder { offset } => MergeError::UnclosedPlaceholder {
+            offset: offset + by,
+        },
+        MergeError::InvalidPlaceholder { offset } => MergeError::InvalidPlaceholder {
+            offset: offset + by,
+        },
+        error => error,
+    }
+}
+
+/// Whether the text of a document of the given data type is escaped as markup.
+fn escaping(data_type: &str) -> Result<bool, MergeError> {
+    if PLAINTEXT_DATA_TYPES.contains(&data_type) {
+        Ok(false)
+    } else if MARKUP_DATA_TYPES.contains(&data_type) {
+        Ok(true)
+    } else {
+        Err(MergeError::UnsupportedDataType(String::from(data_type)))
+    }
+}
+
+/// The text of the target of the unit, or of its source when it has not been translated,
+/// as it is written in a document of the data type of the unit.
+fn merged_text(unit: &Unit, file_data_type: &str) -> Result<String, MergeError> {
+    let markup = escaping(unit.data_type.as_deref().unwrap_or(file_data_type))?;
+    let mut text = String::new();
+    if let Some(value) = unit.target.as_ref().or(unit.source.as_ref()) {
+        push_content(&mut text, &value.content, markup, false)
+            .map_err(|_| MergeError::MissingNativeCode(unit.id.clone()))?;
+    }
+    Ok(text)
+}
+
+/// Writes the content of a unit, escaping its text as markup if needed. Native code, which is
+/// the text of the content of `<ph>`, `<bpt>`, `<ept>` and `<it>`, is written as is.
+fn push_content(
+    text: &mut String,
+    content: &[ContentPart],
+    markup: bool,
+    native: bool,
+) -> Result<(), ()> {
+    for part in content {
+        match part {
+            ContentPart::Text(t) if markup && !native => push_escaped(text, t),
+            ContentPart::Text(t) => text.push_str(t),
+            ContentPart::Code(code) => match code.kind {
+                InlineKind::Mrk | InlineKind::Sub => {
+                    push_content(text, &code.content, markup, false)?
+                }
+                InlineKind::G if !markup => push_content(text, &code.content, markup, false)?,
+                InlineKind::Ph | InlineKind::Bpt | InlineKind::Ept | InlineKind::It => {
+                    push_content(text, &code.content, markup, true)?
+                }
+                InlineKind::G | InlineKind::X | InlineKind::Bx | InlineKind::Ex => return Err(()),
+            },
+            ContentPart::Extension(_) => (),
+        }
+    }
+    Ok(())
+}
+
+fn push_escaped(text: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '&' => text.push_str("&amp;"),
+            '<' => text.push_str("&lt;"),
+            '>' => text.push_str("&gt;"),
+            '"' => text.push_str("&quot;"),
+            '\'' => text.push_str("&#39;"),
+            c => text.push(c),
+        }
+    }
+}
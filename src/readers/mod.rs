@@ -0,0 +1,530 @@
+//! Defines the input readers used to populate a translation `Store`.
+
+mod xliff12;
+mod xliff20;
+
+use crate::error::XliffError;
+use crate::store::{
+    BinUnit, BodyItem, ContentPart, Group, InlineCode, InlineKind, Store, TagCtx, TranslationFile,
+    Unit, UnitValue,
+};
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::Event;
+use quick_xml::events::Event::{Empty, End, Eof, Start};
+use quick_xml::events::{BytesStart, BytesText};
+use quick_xml::{Reader, Writer};
+use std::io::{self, BufRead, Read};
+
+/// Namespace of XLIFF 1.2 documents.
+pub(crate) const XLIFF_12_NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:1.2";
+
+/// Namespace of XLIFF 2.0 documents.
+pub(crate) const XLIFF_20_NAMESPACE: &str = "urn:oasis:names:tc:xliff:document:2.0";
+
+/// Namespace of the XLIFF 2.0 Metadata module.
+pub(crate) const METADATA_NAMESPACE: &str = "urn:oasis:names:tc:xliff:metadata:2.0";
+
+/// The namespace an element belongs to, resolved from its prefix and the namespace
+/// declarations in scope.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub(crate) enum Namespace {
+    /// The element has no namespace. Such elements are read as XLIFF, as some tools do not
+    /// declare the XLIFF namespace.
+    None,
+    Xliff12,
+    Xliff20,
+    Metadata,
+    /// Any other namespace, including prefixes which have not been declared.
+    Foreign,
+}
+
+impl Namespace {
+    fn resolve(namespace: Option<&[u8]>, name: &[u8]) -> Self {
+        match namespace {
+            None if name.contains(&b':') => Namespace::Foreign,
+            None => Namespace::None,
+            Some(ns) if ns == XLIFF_12_NAMESPACE.as_bytes() => Namespace::Xliff12,
+            Some(ns) if ns == XLIFF_20_NAMESPACE.as_bytes() => Namespace::Xliff20,
+            Some(ns) if ns == METADATA_NAMESPACE.as_bytes() => Namespace::Metadata,
+            Some(_) => Namespace::Foreign,
+        }
+    }
+
+    /// Whether elements of this namespace are XLIFF core elements.
+    pub(crate) fn is_xliff(self) -> bool {
+        matches!(
+            self,
+            Namespace::None | Namespace::Xliff12 | Namespace::Xliff20
+        )
+    }
+
+    /// The XLIFF 1.2 element with the given local name, if this is an XLIFF namespace.
+    pub(crate) fn tag(self, local_name: &[u8]) -> Option<TagCtx> {
+        match self.is_xliff() {
+            true => TagCtx::from(local_name),
+            false => None,
+        }
+    }
+
+    /// The XLIFF 2.0 element with the given local name, including the elements
+    /// of the Metadata module.
+    pub(crate) fn tag_v20(self, local_name: &[u8]) -> Option<TagCtx> {
+        match self {
+            Namespace::Metadata => TagCtx::from_metadata(local_name),
+            _ if self.is_xliff() => TagCtx::from_v20(local_name),
+            _ => None,
+        }
+    }
+
+    /// The XLIFF 1.2 inline element with the given local name, if this is an XLIFF namespace.
+    pub(crate) fn inline_kind(self, local_name: &[u8]) -> Option<InlineKind> {
+        match self.is_xliff() {
+            true => InlineKind::from(local_name),
+            false => None,
+        }
+    }
+}
+
+/// The XLIFF version of a document.
+#[derive(PartialEq, Copy, Clone, Debug)]
+enum Version {
+    V12,
+    V20,
+}
+
+/// Reads an XLIFF document of any supported version into the provided store.
+pub(crate) fn read<R: BufRead>(store: &mut Store, r: R) -> Result<(), XliffError> {
+    let mut source = LineTracker::new(r);
+    let result = read_document(store, &mut source);
+    result.map_err(|e| e.located(&source.line_breaks))
+}
+
+fn read_document<R: BufRead>(store: &mut Store, r: R) -> Result<(), XliffError> {
+    let mut buf = Vec::new();
+    let mut ns_buf = Vec::new();
+    let mut r = Reader::from_reader(r);
+
+    loop {
+        let offset = r.buffer_position();
+        match next_event(&mut r, &mut buf, &mut ns_buf)? {
+            (ns, Start(ref e)) => {
+                check_root(ns, e, offset)?;
+                return match detect_version(ns, e, offset)? {
+                    Version::V12 => {
+                        xliff12::ReaderXliff12::new(store).read(&mut r, &mut ns_buf, e, offset)
+                    }
+                    Version::V20 => {
+                        xliff20::ReaderXliff20::new(store).read(&mut r, &mut ns_buf, e, offset)
+                    }
+                };
+            }
+            (ns, Empty(ref e)) => return check_root(ns, e, offset),
+            (_, Eof) => return Ok(()),
+            _ => (),
+        }
+    }
+}
+
+fn check_root(ns: Namespace, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+    match ns.is_xliff() && e.local_name() == b"xliff" {
+        true => Ok(()),
+        false => Err(XliffError::structure(
+            "the document root must be an <xliff> element",
+            offset,
+        )),
+    }
+}
+
+/// Determines the XLIFF version from the `version` attribute of the root element,
+/// falling back to its namespace.
+fn detect_version(ns: Namespace, root: &BytesStart, offset: usize) -> Result<Version, XliffError> {
+    for a in root.attributes() {
+        let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+        if attr.key == b"version" {
+            return match attribute_value(&attr, offset)?.starts_with("2.") {
+                true => Ok(Version::V20),
+                false => Ok(Version::V12),
+            };
+        }
+    }
+
+    match ns {
+        Namespace::Xliff20 => Ok(Version::V20),
+        _ => Ok(Version::V12),
+    }
+}
+
+/// Reads the next XML event along with the namespace of its element, converting parser errors.
+///
+/// The namespace declarations in scope are kept in `ns_buf`, which must be provided to each call
+/// made while reading a document. Events other than elements have no namespace.
+pub(crate) fn next_event<'b, R: BufRead>(
+    r: &mut Reader<R>,
+    buf: &'b mut Vec<u8>,
+    ns_buf: &mut Vec<u8>,
+) -> Result<(Namespace, Event<'b>), XliffError> {
+    buf.clear();
+    let (namespace, event) = r
+        .read_namespaced_event(buf, ns_buf)
+        .map_err(|e| XliffError::xml(e, r.buffer_position()))?;
+    let ns = match &event {
+        Start(e) | Empty(e) => Namespace::resolve(namespace, e.name()),
+        End(e) => Namespace::resolve(namespace, e.name()),
+        _ => Namespace::None,
+    };
+    Ok((ns, event))
+}
+
+/// Reads the unescaped value of an attribute as a string.
+pub(crate) fn attribute_value(attr: &Attribute, offset: usize) -> Result<String, XliffError> {
+    let value = attr
+        .unescaped_value()
+        .map_err(|e| XliffError::xml(e, offset))?;
+    String::from_utf8(value.into_owned()).map_err(|e| XliffError::utf8(e.utf8_error(), offset))
+}
+
+/// Reads an attribute which is not part of the model along with its qualified name.
+pub(crate) fn extension_attribute(
+    attr: &Attribute,
+    offset: usize,
+) -> Result<(String, String), XliffError> {
+    let key = String::from_utf8(attr.key.to_vec())
+        .map_err(|e| XliffError::utf8(e.utf8_error(), offset))?;
+    Ok((key, attribute_value(attr, offset)?))
+}
+
+/// Reads an element which is not part of the model along with all of its content,
+/// returning it as XML markup.
+pub(crate) fn element_markup<R: BufRead>(
+    r: &mut Reader<R>,
+    ns_buf: &mut Vec<u8>,
+    start: &BytesStart,
+    offset: usize,
+) -> Result<String, XliffError> {
+    let mut writer = Writer::new(Vec::new());
+    writer
+        .write_event(Event::Start(start.to_owned()))
+        .map_err(|e| XliffError::xml(e, offset))?;
+
+    let mut buf = Vec::new();
+    let mut depth = 1;
+    while depth > 0 {
+        let (_, event) = next_event(r, &mut buf, ns_buf)?;
+        match event {
+            Start(_) => depth += 1,
+            End(_) => depth -= 1,
+            Eof => {
+                return Err(XliffError::structure(
+                    format!("<{}> is not closed", String::from_utf8_lossy(start.name())),
+                    offset,
+                ))
+            }
+            _ => (),
+        }
+        writer
+            .write_event(event)
+            .map_err(|e| XliffError::xml(e, offset))?;
+    }
+    markup_text(writer.into_inner(), offset)
+}
+
+/// Returns the XML markup of an empty element, a comment or a processing instruction
+/// which is not part of the model.
+pub(crate) fn node_markup(event: Event, offset: usize) -> Result<String, XliffError> {
+    let mut writer = Writer::new(Vec::new());
+    writer
+        .write_event(event)
+        .map_err(|e| XliffError::xml(e, offset))?;
+    markup_text(writer.into_inner(), offset)
+}
+
+fn markup_text(markup: Vec<u8>, offset: usize) -> Result<String, XliffError> {
+    String::from_utf8(markup).map_err(|e| XliffError::utf8(e.utf8_error(), offset))
+}
+
+/// Returns the file which is currently being read.
+pub(crate) fn current_file<'s>(
+    store: &'s mut Store,
+    element: &str,
+    offset: usize,
+) -> Result<&'s mut TranslationFile, XliffError> {
+    match store.groups.last_mut() {
+        None => Err(XliffError::structure(
+            format!("<{}> must be placed inside a <file> element", element),
+            offset,
+        )),
+        Some(file) => Ok(file),
+    }
+}
+
+/// Returns the body of the innermost of the `depth` open groups of the current file,
+/// or the body of the file itself when no group is open.
+pub(crate) fn current_body<'s>(
+    store: &'s mut Store,
+    depth: usize,
+    element: &str,
+    offset: usize,
+) -> Result<&'s mut Vec<BodyItem>, XliffError> {
+    let mut body = &mut current_file(store, element, offset)?.body;
+    for _ in 0..depth {
+        body = match body.last_mut() {
+            Some(BodyItem::Group(group)) => &mut group.body,
+            _ => {
+                return Err(XliffError::structure(
+                    format!("<{}> must be placed inside a <group> element", element),
+                    offset,
+                ))
+            }
+        };
+    }
+    Ok(body)
+}
+
+/// Returns the innermost of the `depth` open groups of the current file.
+pub(crate) fn current_group<'s>(
+    store: &'s mut Store,
+    depth: usize,
+    element: &str,
+    offset: usize,
+) -> Result<&'s mut Group, XliffError> {
+    let body = match depth {
+        0 => None,
+        _ => current_body(store, depth - 1, element, offset)?.last_mut(),
+    };
+    match body {
+        Some(BodyItem::Group(group)) => Ok(group),
+        _ => Err(XliffError::structure(
+            format!("<{}> must be placed inside a <group> element", element),
+            offset,
+        )),
+    }
+}
+
+/// Returns the translation unit which is currently being read,
+/// placed inside `depth` open groups of the current file.
+pub(crate) fn current_unit<'s>(
+    store: &'s mut Store,
+    depth: usize,
+    element: &str,
+    offset: usize,
+) -> Result<&'s mut Unit, XliffError> {
+    match current_body(store, depth, element, offset)?.last_mut() {
+        Some(BodyItem::Unit(unit)) => Ok(unit),
+        _ => Err(XliffError::structure(
+            format!("<{}> must be placed inside a translation unit", element),
+            offset,
+        )),
+    }
+}
+
+/// Returns the binary unit which is currently being read,
+/// placed inside `depth` open groups of the current file.
+pub(crate) fn current_bin_unit<'s>(
+    store: &'s mut Store,
+    depth: usize,
+    element: &str,
+    offset: usize,
+) -> Result<&'s mut BinUnit, XliffError> {
+    match current_body(store, depth, element, offset)?.last_mut() {
+        Some(BodyItem::BinUnit(bin_unit)) => Ok(bin_unit),
+        _ => Err(XliffError::structure(
+            format!("<{}> must be placed inside a <bin-unit> element", element),
+            offset,
+        )),
+    }
+}
+
+/// Collects the text, inline codes and unrecognised nodes of a `<source>` or `<target>` element
+/// as it is being read.
+pub(crate) struct ContentBuilder {
+    /// The content of the element followed by the content of each open inline code.
+    frames: Vec<(Option<InlineCode>, Vec<ContentPart>)>,
+}
+
+impl ContentBuilder {
+    pub(crate) fn new() -> Self {
+        ContentBuilder {
+            frames: vec![(None, vec![])],
+        }
+    }
+
+    pub(crate) fn push_text(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some((_, content)) = self.frames.last_mut() {
+            match content.last_mut() {
+                Some(ContentPart::Text(t)) => t.push_str(text),
+                _ => content.push(ContentPart::Text(String::from(text))),
+            }
+        }
+    }
+
+    /// Starts an inline code which receives the content read until it is closed.
+    pub(crate) fn open_code(&mut self, code: InlineCode) {
+        self.frames.push((Some(code), vec![]));
+    }
+
+    /// Ends the innermost open inline code.
+    pub(crate) fn close_code(&mut self) {
+        if self.frames.len() > 1 {
+            if let Some((Some(mut code), content)) = self.frames.pop() {
+                code.content = content;
+                self.add_code(code);
+            }
+        }
+    }
+
+    /// Adds an inline code without content.
+    pub(crate) fn add_code(&mut self, code: InlineCode) {
+        if let Some((_, content)) = self.frames.last_mut() {
+            content.push(ContentPart::Code(code));
+        }
+    }
+
+    /// Adds an element, comment or processing instruction which is not part of the model.
+    pub(crate) fn add_extension(&mut self, markup: String) {
+        if let Some((_, content)) = self.frames.last_mut() {
+            content.push(ContentPart::Extension(markup));
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> UnitValue {
+        while self.frames.len() > 1 {
+            self.close_code();
+        }
+        UnitValue {
+            content: self.frames.pop().map(|(_, c)| c).unwrap_or_default(),
+        }
+    }
+}
+
+/// Reads the content of a CDATA section, which is not escaped.
+pub(crate) fn cdata_text(e: &BytesText, offset: usize) -> Result<String, XliffError> {
+    std::str::from_utf8(e.escaped())
+        .map(String::from)
+        .map_err(|e| XliffError::utf8(e, offset))
+}
+
+/// Collapses each sequence of white space into a single space and removes leading and trailing
+/// white space, as done for elements with `xml:space="default"`.
+///
+/// The native code held by `<ph>`, `<bpt>`, `<ept>` and `<it>` elements is left untouched.
+pub(crate) fn normalize_space(value: &mut UnitValue) {
+    collapse_space(&mut value.content, &mut false);
+
+    if let Some(ContentPart::Text(text)) = value.content.first_mut() {
+        *text = String::from(text.trim_start_matches(is_space));
+    }
+    if let Some(ContentPart::Text(text)) = value.content.last_mut() {
+        *text = String::from(text.trim_end_matches(is_space));
+    }
+    value.content.retain(|part| match part {
+        ContentPart::Text(text) => !text.is_empty(),
+        ContentPart::Code(_) | ContentPart::Extension(_) => true,
+    });
+}
+
+/// Collapses the white space of the content, `previous_space` telling whether the text placed
+/// before it ends with white space so that runs are collapsed across inline elements.
+fn collapse_space(content: &mut [ContentPart], previous_space: &mut bool) {
+    for part in content.iter_mut() {
+        match part {
+            ContentPart::Text(text) => {
+                let mut collapsed = String::with_capacity(text.len());
+                for c in text.chars() {
+                    match is_space(c) {
+                        true if *previous_space => (),
+                        true => collapsed.push(' '),
+                        false => collapsed.push(c),
+                    }
+                    *previous_space = is_space(c);
+                }
+                *text = collapsed;
+            }
+            ContentPart::Code(code) => match code.kind {
+                InlineKind::G | InlineKind::Mrk | InlineKind::Sub => {
+                    collapse_space(&mut code.content, previous_space)
+                }
+                // codes standing for native content separate the text around them
+                _ => *previous_space = false,
+            },
+            // so do the nodes which are not part of the model, as they are written back in place
+            ContentPart::Extension(_) => *previous_space = false,
+        }
+    }
+}
+
+/// White space characters as defined by XML.
+fn is_space(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\r' | '\n')
+}
+
+/// Reads an inline element of the given kind and its attributes.
+pub(crate) fn inline_code(
+    kind: InlineKind,
+    e: &BytesStart,
+    offset: usize,
+) -> Result<InlineCode, XliffError> {
+    let mut code = InlineCode::new(kind, None);
+
+    for a in e.attributes() {
+        let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+        match attr.key {
+            b"id" => code.id = Some(attribute_value(&attr, offset)?),
+            _ => code.attributes.push(extension_attribute(&attr, offset)?),
+        }
+    }
+
+    Ok(code)
+}
+
+/// Wraps the source of an XLIFF document and records where each line starts,
+/// so errors can be reported with a line and column.
+struct LineTracker<R: BufRead> {
+    inner: R,
+    consumed: usize,
+    line_breaks: Vec<usize>,
+}
+
+impl<R: BufRead> LineTracker<R> {
+    fn new(inner: R) -> Self {
+        LineTracker {
+            inner,
+            consumed: 0,
+            line_breaks: vec![],
+        }
+    }
+}
+
+impl<R: BufRead> Read for LineTracker<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        for (ix, byte) in buf[..count].iter().enumerate() {
+            if *byte == b'\n' {
+                self.line_breaks.push(self.consumed + ix);
+            }
+        }
+        self.consumed += count;
+        Ok(count)
+    }
+}
+
+impl<R: BufRead> BufRead for LineTracker<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        // the bytes being consumed are still held in the inner buffer
+        if let Ok(available) = self.inner.fill_buf() {
+            for (ix, byte) in available[..amt.min(available.len())].iter().enumerate() {
+                if *byte == b'\n' {
+                    self.line_breaks.push(self.consumed + ix);
+                }
+            }
+        }
+        self.consumed += amt;
+        self.inner.consume(amt)
+    }
+}
@@ -0,0 +1,1221 @@
+//! Defines the XLIFF 1.2 reader
+
+use super::{
+    attribute_value, cdata_text, current_bin_unit, current_body, current_file, current_group,
+    current_unit, element_markup, extension_attribute, inline_code, next_event, node_markup,
+    normalize_space, ContentBuilder,
+};
+use crate::error::XliffError;
+use crate::store::{
+    AltTrans, Annotates, BinFile, BinUnit, BodyItem, ContentPart, Context, ContextGroup, Count,
+    CountGroup, Extensions, Group, Header, Locale, Note, Phase, Prop, PropGroup, ReferenceFile,
+    StateQualifier, Store, TagCtx, TargetState, Tool, TranslationFile, Unit, UnitValue,
+    BASE64_FORM,
+};
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::BytesStart;
+use quick_xml::events::Event::{CData, Comment, Empty, End, Eof, Start, Text, PI};
+use quick_xml::Reader;
+use std::io::BufRead;
+
+/// Populates a `Store` from the contents of an XLIFF 1.2 document.
+///
+/// The text, CDATA sections and character references of `<source>`, `<target>` and `<note>`
+/// elements are joined into a single value. White space is normalized as described for
+/// `xml:space="default"`, unless `xml:space="preserve"` is specified on the element or one of
+/// its ancestors.
+///
+/// Elements, comments and processing instructions which are not part of the model are kept as
+/// markup in place: within the content of the element when they are found within text, or in the
+/// `Extensions` of the element along with their position among its children.
+pub(crate) struct ReaderXliff12<'s> {
+    store: &'s mut Store,
+    /// The open elements and whether white space is preserved within each of them.
+    tags: Vec<(TagCtx, bool)>,
+    /// The names of the recognised child elements read so far within each open element, giving
+    /// the order of the children and the position of the unrecognised nodes found between them.
+    children: Vec<Vec<String>>,
+    content: Option<ContentBuilder>,
+    /// The `<note>` being read, holding its attributes until its text is complete.
+    note: Option<Note>,
+    /// The `<context-group>` being read, added to its unit or group once it is closed.
+    context_group: Option<ContextGroup>,
+    /// The `<context>` being read, holding its attributes until its value is complete.
+    context: Option<Context>,
+    /// The `<count-group>` being read, added to its owner once it is closed.
+    count_group: Option<CountGroup>,
+    /// The `<count>` being read, holding its attributes until its value is complete.
+    count: Option<Count>,
+    /// The `<prop-group>` being read, added to its owner once it is closed.
+    prop_group: Option<PropGroup>,
+    /// The `<prop>` being read, holding its attributes until its value is complete.
+    prop: Option<Prop>,
+    /// The `<internal-file>` being read, holding its form until its data is complete.
+    internal_file: Option<BinFile>,
+}
+
+impl<'s> ReaderXliff12<'s> {
+    pub(crate) fn new(store: &'s mut Store) -> Self {
+        ReaderXliff12 {
+            store,
+            tags: vec![],
+            children: vec![],
+            content: None,
+            note: None,
+            context_group: None,
+            context: None,
+            count_group: None,
+            count: None,
+            prop_group: None,
+            prop: None,
+            internal_file: None,
+        }
+    }
+
+    /// Reads the document following the opening `<xliff>` element.
+    pub(crate) fn read<R: BufRead>(
+        mut self,
+        r: &mut Reader<R>,
+        ns_buf: &mut Vec<u8>,
+        root: &BytesStart,
+        root_offset: usize,
+    ) -> Result<(), XliffError> {
+        let mut buf = Vec::new();
+        self.open_tag(TagCtx::Xliff, root, root_offset)?;
+        self.handle_root(root, root_offset)?;
+
+        loop {
+            let offset = r.buffer_position();
+            let (ns, event) = next_event(r, &mut buf, ns_buf)?;
+            match event {
+                Start(ref e) => match (&mut self.content, ns.inline_kind(e.local_name())) {
+                    (Some(content), Some(kind)) => content.open_code(inline_code(kind, e, offset)?),
+                    _ => {
+                        if let Some(tag) = ns.tag(e.local_name()) {
+                            self.open_tag(tag, e, offset)?;
+                            match tag {
+                                TagCtx::File => self.handle_file(e, offset)?,
+                                TagCtx::Unit => self.handle_trans_unit(e, offset)?,
+                                TagCtx::Group => self.handle_group(e, offset)?,
+                                TagCtx::AltTrans => self.handle_alt_trans(e, offset)?,
+                                TagCtx::Header => self.handle_file_header(e, offset)?,
+                                TagCtx::Tool => self.handle_header_tool(e, offset)?,
+                                TagCtx::Phase => self.handle_phase(e, offset)?,
+                                TagCtx::Glossary | TagCtx::Reference => {
+                                    self.handle_reference_file(tag, offset)?
+                                }
+                                TagCtx::Target => {
+                                    self.handle_target(e, offset)?;
+                                    self.content = Some(ContentBuilder::new())
+                                }
+                                TagCtx::Note => {
+                                    self.note = Some(Self::read_note(e, offset)?);
+                                    self.content = Some(ContentBuilder::new())
+                                }
+                                TagCtx::Source => {
+                                    self.handle_source(e, offset)?;
+                                    self.content = Some(ContentBuilder::new())
+                                }
+                                TagCtx::SegSource => self.content = Some(ContentBuilder::new()),
+                                TagCtx::ContextGroup => self.handle_context_group(e, offset)?,
+                                TagCtx::Context => {
+                                    self.context = Some(Self::read_context(e, offset)?);
+                                    self.content = Some(ContentBuilder::new())
+                                }
+                                TagCtx::CountGroup => self.handle_count_group(e, offset)?,
+                                TagCtx::Count => {
+                                    self.count = Some(Self::read_count(e, offset)?);
+                                    self.content = Some(ContentBuilder::new())
+                                }
+                                TagCtx::PropGroup => self.handle_prop_group(e, offset)?,
+                                TagCtx::BinUnit => self.handle_bin_unit(e, offset)?,
+                                TagCtx::InternalFile => {
+                                    self.internal_file = Some(Self::read_internal_file(e, offset)?);
+                                    self.content = Some(ContentBuilder::new())
+                                }
+                                TagCtx::ExternalFile => self.handle_external_file(e, offset)?,
+                                TagCtx::Prop => {
+                                    self.prop = Some(Self::read_prop(e, offset)?);
+                                    self.content = Some(ContentBuilder::new())
+                                }
+                                _ => (),
+                            }
+                        } else {
+                            let markup = element_markup(r, ns_buf, e, offset)?;
+                            self.add_extension(markup, offset)?;
+                        }
+                    }
+                },
+                Empty(ref e) => match (&mut self.content, ns.inline_kind(e.local_name())) {
+                    (Some(content), Some(kind)) => content.add_code(inline_code(kind, e, offset)?),
+                    _ => {
+                        if let Some(tag) = ns.tag(e.local_name()) {
+                            self.open_tag(tag, e, offset)?;
+                            match tag {
+                                TagCtx::Header => self.handle_file_header(e, offset)?,
+                                TagCtx::Tool => self.handle_header_tool(e, offset)?,
+                                TagCtx::Phase => self.handle_phase(e, offset)?,
+                                TagCtx::Group => self.handle_group(e, offset)?,
+                                TagCtx::ContextGroup => {
+                                    self.handle_context_group(e, offset)?;
+                                    self.add_context_group(offset)?;
+                                }
+                                TagCtx::CountGroup => {
+                                    self.handle_count_group(e, offset)?;
+                                    self.add_count_group(offset)?;
+                                }
+                                TagCtx::PropGroup => {
+                                    self.handle_prop_group(e, offset)?;
+                                    self.add_prop_group(offset)?;
+                                }
+                                TagCtx::ExternalFile => self.handle_external_file(e, offset)?,
+                                TagCtx::Source
+                                | TagCtx::SegSource
+                                | TagCtx::Target
+                                | TagCtx::Note
+                                | TagCtx::Context
+                                | TagCtx::Count
+                                | TagCtx::Prop
+                                | TagCtx::InternalFile => {
+                                    match tag {
+                                        TagCtx::Source => self.handle_source(e, offset)?,
+                                        TagCtx::Context => {
+                                            self.context = Some(Self::read_context(e, offset)?)
+                                        }
+                                        TagCtx::Count => {
+                                            self.count = Some(Self::read_count(e, offset)?)
+                                        }
+                                        TagCtx::Target => self.handle_target(e, offset)?,
+                                        TagCtx::Note => {
+                                            self.note = Some(Self::read_note(e, offset)?)
+                                        }
+                                        TagCtx::Prop => {
+                                            self.prop = Some(Self::read_prop(e, offset)?)
+                                        }
+                                        TagCtx::InternalFile => {
+                                            self.internal_file =
+                                                Some(Self::read_internal_file(e, offset)?)
+                                        }
+                                        _ => (),
+                                    }
+                                    self.content = Some(ContentBuilder::new());
+                                    self.add_content(tag, offset)?;
+                                }
+                                _ => (),
+                            }
+                            self.close_tag(tag, offset)?;
+                        } else {
+                            let markup = node_markup(Empty(e.to_owned()), offset)?;
+                            self.add_extension(markup, offset)?;
+                        }
+                    }
+                },
+                End(ref e) => match (&mut self.content, ns.inline_kind(e.local_name())) {
+                    (Some(content), Some(_)) => content.close_code(),
+                    _ => {
+                        if let Some(tag) = ns.tag(e.local_name()) {
+                            match tag {
+                                TagCtx::Source
+                                | TagCtx::SegSource
+                                | TagCtx::Target
+                                | TagCtx::Note
+                                | TagCtx::Context
+                                | TagCtx::Count
+                                | TagCtx::Prop
+                                | TagCtx::InternalFile => self.add_content(tag, offset)?,
+                                TagCtx::ContextGroup => self.add_context_group(offset)?,
+                                TagCtx::CountGroup => self.add_count_group(offset)?,
+                                TagCtx::PropGroup => self.add_prop_group(offset)?,
+                                _ => (),
+                            }
+                            self.close_tag(tag, offset)?;
+                        }
+                    }
+                },
+                Text(e) => {
+                    if let Some(content) = self.content.as_mut() {
+                        let text = e
+                            .unescape_and_decode(r)
+                            .map_err(|e| XliffError::xml(e, offset))?;
+                        content.push_text(&text);
+                    }
+                }
+                CData(e) => {
+                    if let Some(content) = self.content.as_mut() {
+                        content.push_text(&cdata_text(&e, offset)?);
+                    }
+                }
+                Comment(e) => {
+                    let markup = node_markup(Comment(e), offset)?;
+                    self.add_extension(markup, offset)?;
+                }
+                PI(e) => {
+                    let markup = node_markup(PI(e), offset)?;
+                    self.add_extension(markup, offset)?;
+                }
+                Eof => break,
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Keeps the attributes of the root `<xliff>` element which are not written by the
+    /// XLIFF 1.2 writer, such as the declarations of extension namespaces.
+    fn handle_root(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"version" | b"xmlns" | b"xmlns:xsi" | b"xsi:schemaLocation" => (),
+                _ => self
+                    .store
+                    .extensions
+                    .attributes
+                    .push(extension_attribute(&attr, offset)?),
+            }
+        }
+        Ok(())
+    }
+
+    /// Keeps an unrecognised element, comment or processing instruction in the element
+    /// it has been found in, along with its position among the children of the element.
+    /// Nodes found within text are kept in the content being read. Nodes found in elements
+    /// which do not keep extensions are dropped.
+    fn add_extension(&mut self, markup: String, offset: usize) -> Result<(), XliffError> {
+        if let Some(content) = self.content.as_mut() {
+            content.add_extension(markup);
+            return Ok(());
+        }
+        let parent = match self.tags.last() {
+            Some((tag, _)) => *tag,
+            None => return Ok(()),
+        };
+        let position = self.children.last().map_or(0, Vec::len);
+        let depth = self.depth();
+        let element = parent.to_str();
+        match parent {
+            TagCtx::Body => {
+                current_body(self.store, depth, element, offset)?.push(BodyItem::Extension(markup))
+            }
+            // nodes placed among the items of a group are kept in order with them
+            TagCtx::Group
+                if !current_group(self.store, depth, element, offset)?
+                    .body
+                    .is_empty() =>
+            {
+                current_body(self.store, depth, element, offset)?.push(BodyItem::Extension(markup))
+            }
+            _ => {
+                if let Some(extensions) = self.extensions_of(parent, offset)? {
+                    extensions.nodes.push((position, markup));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The extensions of the innermost open element of the given kind,
+    /// when the element keeps extensions.
+    fn extensions_of(
+        &mut self,
+        element: TagCtx,
+        offset: usize,
+    ) -> Result<Option<&mut Extensions>, XliffError> {
+        let depth = self.depth();
+        let name = element.to_str();
+        Ok(match element {
+            TagCtx::Xliff => Some(&mut self.store.extensions),
+            TagCtx::File => Some(&mut current_file(self.store, name, offset)?.extensions),
+            TagCtx::Group => Some(&mut current_group(self.store, depth, name, offset)?.extensions),
+            TagCtx::Unit => Some(&mut current_unit(self.store, depth, name, offset)?.extensions),
+            TagCtx::BinUnit => {
+                Some(&mut current_bin_unit(self.store, depth, name, offset)?.extensions)
+            }
+            TagCtx::AltTrans => current_unit(self.store, depth, name, offset)?
+                .alternatives
+                .last_mut()
+                .map(|alternative| &mut alternative.extensions),
+            TagCtx::Header | TagCtx::Tool | TagCtx::Phase => {
+                let file = current_file(self.store, name, offset)?;
+                match (file.header.as_mut(), element) {
+                    (Some(header), TagCtx::Tool) => {
+                        header.tools.last_mut().map(|t| &mut t.extensions)
+                    }
+                    (Some(header), TagCtx::Phase) => {
+                        header.phases.last_mut().map(|p| &mut p.extensions)
+                    }
+                    (Some(header), _) => Some(&mut header.extensions),
+                    (None, _) => None,
+                }
+            }
+            _ => None,
+        })
+    }
+
+    /// Stores the content collected for the `<source>`, `<target>` or `<note>` element
+    /// being closed, normalizing its white space unless it is to be preserved.
+    fn add_content(&mut self, tag: TagCtx, offset: usize) -> Result<(), XliffError> {
+        let mut value = match self.content.take() {
+            None => return Ok(()),
+            Some(content) => content.finish(),
+        };
+        if tag == TagCtx::InternalFile {
+            return self.add_internal_file(value.plain_text(), offset);
+        }
+        if !self.preserve_space() {
+            normalize_space(&mut value);
+        }
+
+        let parent = self.parent();
+        let depth = self.depth();
+        let note = match tag {
+            TagCtx::Note => {
+                let mut note = self.note.take().unwrap_or_else(|| Note::new(""));
+                Self::set_note_text(&mut note, &value);
+                Some(note)
+            }
+            _ => None,
+        };
+
+        match (tag, parent, note) {
+            (TagCtx::Count, TagCtx::CountGroup, _) => {
+                if let (Some(mut count), Some(group)) =
+                    (self.count.take(), self.count_group.as_mut())
+                {
+                    count.value = value.plain_text().trim().parse().map_err(|_| {
+                        XliffError::structure("<count> must contain a whole number", offset)
+                    })?;
+                    group.counts.push(count);
+                }
+            }
+            (TagCtx::Prop, TagCtx::PropGroup, _) => {
+                if let (Some(mut prop), Some(group)) = (self.prop.take(), self.prop_group.as_mut())
+                {
+                    prop.value = value.plain_text();
+                    group.props.push(prop);
+                }
+            }
+            (TagCtx::Context, TagCtx::ContextGroup, _) => {
+                if let (Some(mut context), Some(group)) =
+                    (self.context.take(), self.context_group.as_mut())
+                {
+                    context.value = value.plain_text();
+                    group.contexts.push(context);
+                }
+            }
+            (_, TagCtx::AltTrans, note) => {
+                let unit = current_unit(self.store, depth, tag.to_str(), offset)?;
+                if let Some(alternative) = unit.alternatives.last_mut() {
+                    match (tag, note) {
+                        (TagCtx::Source, _) => alternative.source = Some(value),
+                        (TagCtx::SegSource, _) => alternative.seg_source = Some(value),
+                        (TagCtx::Target, _) => alternative.target = value,
+                        (TagCtx::Note, Some(note)) => alternative.notes.push(note),
+                        _ => (),
+                    }
+                }
+            }
+            (TagCtx::SegSource, _, _) => {
+                current_unit(self.store, depth, tag.to_str(), offset)?.seg_source = Some(value)
+            }
+            (TagCtx::Source, _, _) => {
+                current_unit(self.store, depth, tag.to_str(), offset)?.source = Some(value)
+            }
+            (TagCtx::Target, _, _) => {
+                current_unit(self.store, depth, tag.to_str(), offset)?.target = Some(value)
+            }
+            (TagCtx::Note, TagCtx::Unit, Some(note)) => {
+                current_unit(self.store, depth, tag.to_str(), offset)?
+                    .notes
+                    .push(note)
+            }
+            (TagCtx::Note, TagCtx::BinUnit, Some(note)) => {
+                current_bin_unit(self.store, depth, tag.to_str(), offset)?
+                    .notes
+                    .push(note)
+            }
+            (TagCtx::Note, TagCtx::Group, Some(note)) => {
+                current_group(self.store, depth, tag.to_str(), offset)?
+                    .notes
+                    .push(note)
+            }
+            (TagCtx::Note, TagCtx::Phase, Some(note)) => {
+                let file = current_file(self.store, tag.to_str(), offset)?;
+                if let Some(phase) = file.header.as_mut().and_then(|h| h.phases.last_mut()) {
+                    phase.notes.push(note);
+                }
+            }
+            (TagCtx::Note, TagCtx::Glossary, Some(note))
+            | (TagCtx::Note, TagCtx::Reference, Some(note)) => {
+                if let Some(reference) = self.current_reference_file(offset)? {
+                    reference.note = Some(note);
+                }
+            }
+            (TagCtx::Note, TagCtx::Header, Some(note)) => {
+                let file = current_file(self.store, tag.to_str(), offset)?;
+                if let Some(header) = file.header.as_mut() {
+                    header.notes.push(note);
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn handle_trans_unit(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut unit = Unit::new();
+        unit.translate = self.inherited_translate(offset)?;
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"id" => {
+                    unit.id = attribute_value(&attr, offset)?;
+                }
+                b"translate" => {
+                    unit.translate = Self::translate_value(&attribute_value(&attr, offset)?);
+                }
+                b"approved" => {
+                    unit.approved =
+                        matches!(attribute_value(&attr, offset)?.as_str(), "yes" | "true");
+                }
+                b"resname" => unit.resname = Some(attribute_value(&attr, offset)?),
+                b"restype" => unit.restype = Some(attribute_value(&attr, offset)?),
+                b"datatype" => unit.data_type = Some(attribute_value(&attr, offset)?),
+                b"maxwidth" => unit.max_width = Self::width_value(&attr, offset)?,
+                b"minwidth" => unit.min_width = Self::width_value(&attr, offset)?,
+                b"size-unit" => unit.size_unit = Some(attribute_value(&attr, offset)?),
+                b"charclass" => unit.char_class = Some(attribute_value(&attr, offset)?),
+                // white space is always preserved by the writer
+                b"xml:space" => (),
+                _ => unit
+                    .extensions
+                    .attributes
+                    .push(extension_attribute(&attr, offset)?),
+            }
+        }
+
+        let file = current_file(self.store, TagCtx::Unit.to_str(), offset)?;
+        unit.source_locale = file.source_locale.clone();
+        unit.target_locale = file.target_locale.clone();
+
+        // the unit has been pushed on the tag stack, its group is one level up
+        let depth = self.depth();
+        current_body(self.store, depth, TagCtx::Unit.to_str(), offset)?.push(BodyItem::Unit(unit));
+        Ok(())
+    }
+
+    fn handle_bin_unit(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        // the source is replaced once the <bin-source> has been read
+        let source = BinFile::Internal {
+            form: None,
+            data: vec![],
+        };
+        let mut bin_unit = BinUnit::new("", "", source);
+        bin_unit.translate = self.inherited_translate(offset)?;
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"id" => bin_unit.id = attribute_value(&attr, offset)?,
+                b"mime-type" => bin_unit.mime_type = attribute_value(&attr, offset)?,
+                b"translate" => {
+                    bin_unit.translate = Self::translate_value(&attribute_value(&attr, offset)?)
+                }
+                _ => bin_unit
+                    .extensions
+                    .attributes
+                    .push(extension_attribute(&attr, offset)?),
+            }
+        }
+
+        let depth = self.depth();
+        current_body(self.store, depth, TagCtx::BinUnit.to_str(), offset)?
+            .push(BodyItem::BinUnit(bin_unit));
+        Ok(())
+    }
+
+    /// Reads the form of an `<internal-file>`, its data is added once the element is closed.
+    fn read_internal_file(e: &BytesStart, offset: usize) -> Result<BinFile, XliffError> {
+        let mut form = None;
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            if attr.key == b"form" {
+                form = Some(attribute_value(&attr, offset)?);
+            }
+        }
+        Ok(BinFile::Internal { form, data: vec![] })
+    }
+
+    /// Stores the data of the `<internal-file>` being closed, decoding it when its form
+    /// is `base64`.
+    fn add_internal_file(&mut self, text: String, offset: usize) -> Result<(), XliffError> {
+        let bin_file = match self.internal_file.take() {
+            Some(BinFile::Internal { form, .. }) => {
+                let data = match form.as_deref() {
+                    Some(BASE64_FORM) => {
+                        let encoded: String = text.split_whitespace().collect();
+                        base64::decode(encoded).map_err(|_| {
+                            XliffError::structure(
+                                "<internal-file> must contain valid base64 data",
+                                offset,
+                            )
+                        })?
+                    }
+                    _ => text.into_bytes(),
+                };
+                BinFile::Internal { form, data }
+            }
+            _ => return Ok(()),
+        };
+        self.set_bin_file(bin_file, TagCtx::InternalFile, offset)
+    }
+
+    fn handle_external_file(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut href = String::new();
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            if attr.key == b"href" {
+                href = attribute_value(&attr, offset)?;
+            }
+        }
+        self.set_bin_file(BinFile::External { href }, TagCtx::ExternalFile, offset)
+    }
+
+    /// Sets the data of the `<bin-source>`, `<bin-target>`, `<skl>`, `<glossary>` or
+    /// `<reference>` the current element belongs to.
+    fn set_bin_file(
+        &mut self,
+        bin_file: BinFile,
+        tag: TagCtx,
+        offset: usize,
+    ) -> Result<(), XliffError> {
+        let depth = self.depth();
+        match self.parent() {
+            TagCtx::BinSource => {
+                current_bin_unit(self.store, depth, tag.to_str(), offset)?.source = bin_file
+            }
+            TagCtx::BinTarget => {
+                current_bin_unit(self.store, depth, tag.to_str(), offset)?.target = Some(bin_file)
+            }
+            TagCtx::Skl => {
+                if let Some(header) = current_file(self.store, tag.to_str(), offset)?
+                    .header
+                    .as_mut()
+                {
+                    header.skeleton = Some(bin_file);
+                }
+            }
+            TagCtx::Glossary | TagCtx::Reference => {
+                if let Some(reference) = self.current_reference_file(offset)? {
+                    reference.file = bin_file;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn handle_context_group(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut group = ContextGroup::new();
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"name" => group.name = Some(attribute_value(&attr, offset)?),
+                b"purpose" => group.purpose = Some(attribute_value(&attr, offset)?),
+                _ => (),
+            }
+        }
+
+        self.context_group = Some(group);
+        Ok(())
+    }
+
+    /// Adds the `<context-group>` being closed to the unit or group it belongs to.
+    fn add_context_group(&mut self, offset: usize) -> Result<(), XliffError> {
+        let group = match self.context_group.take() {
+            None => return Ok(()),
+            Some(group) => group,
+        };
+
+        let depth = self.depth();
+        let element = TagCtx::ContextGroup.to_str();
+        match self.parent() {
+            TagCtx::Unit => current_unit(self.store, depth, element, offset)?
+                .context_groups
+                .push(group),
+            TagCtx::BinUnit => current_bin_unit(self.store, depth, element, offset)?
+                .context_groups
+                .push(group),
+            TagCtx::Group => current_group(self.store, depth, element, offset)?
+                .context_groups
+                .push(group),
+            TagCtx::AltTrans => {
+                let unit = current_unit(self.store, depth, element, offset)?;
+                if let Some(alternative) = unit.alternatives.last_mut() {
+                    alternative.context_groups.push(group);
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn handle_count_group(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut group = CountGroup::new("");
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            if attr.key == b"name" {
+                group.name = attribute_value(&attr, offset)?;
+            }
+        }
+
+        self.count_group = Some(group);
+        Ok(())
+    }
+
+    /// Adds the `<count-group>` being closed to the header, group or unit it belongs to.
+    fn add_count_group(&mut self, offset: usize) -> Result<(), XliffError> {
+        let group = match self.count_group.take() {
+            None => return Ok(()),
+            Some(group) => group,
+        };
+
+        let depth = self.depth();
+        let element = TagCtx::CountGroup.to_str();
+        match self.parent() {
+            TagCtx::Unit => current_unit(self.store, depth, element, offset)?
+                .count_groups
+                .push(group),
+            TagCtx::BinUnit => current_bin_unit(self.store, depth, element, offset)?
+                .count_groups
+                .push(group),
+            TagCtx::Group => current_group(self.store, depth, element, offset)?
+                .count_groups
+                .push(group),
+            TagCtx::Header => {
+                if let Some(header) = current_file(self.store, element, offset)?.header.as_mut() {
+                    header.count_groups.push(group);
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn handle_prop_group(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut group = PropGroup::new();
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            if attr.key == b"name" {
+                group.name = Some(attribute_value(&attr, offset)?);
+            }
+        }
+
+        self.prop_group = Some(group);
+        Ok(())
+    }
+
+    /// Adds the `<prop-group>` being closed to the header, group or unit it belongs to.
+    fn add_prop_group(&mut self, offset: usize) -> Result<(), XliffError> {
+        let group = match self.prop_group.take() {
+            None => return Ok(()),
+            Some(group) => group,
+        };
+
+        let depth = self.depth();
+        let element = TagCtx::PropGroup.to_str();
+        match self.parent() {
+            TagCtx::Unit => current_unit(self.store, depth, element, offset)?
+                .prop_groups
+                .push(group),
+            TagCtx::BinUnit => current_bin_unit(self.store, depth, element, offset)?
+                .prop_groups
+                .push(group),
+            TagCtx::Group => current_group(self.store, depth, element, offset)?
+                .prop_groups
+                .push(group),
+            TagCtx::AltTrans => {
+                let unit = current_unit(self.store, depth, element, offset)?;
+                if let Some(alternative) = unit.alternatives.last_mut() {
+                    alternative.prop_groups.push(group);
+                }
+            }
+            TagCtx::Header => {
+                if let Some(header) = current_file(self.store, element, offset)?.header.as_mut() {
+                    header.prop_groups.push(group);
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Reads the attributes of a `<prop>`, its value is added once the element is closed.
+    fn read_prop(e: &BytesStart, offset: usize) -> Result<Prop, XliffError> {
+        let mut prop = Prop::new("", "");
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"prop-type" => prop.prop_type = attribute_value(&attr, offset)?,
+                b"xml:lang" => prop.lang = Some(attribute_value(&attr, offset)?),
+                _ => (),
+            }
+        }
+        Ok(prop)
+    }
+
+    /// Reads the attributes of a `<count>`, its value is added once the element is closed.
+    fn read_count(e: &BytesStart, offset: usize) -> Result<Count, XliffError> {
+        let mut count = Count {
+            count_type: None,
+            unit: None,
+            phase_name: None,
+            value: 0,
+        };
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"count-type" => count.count_type = Some(attribute_value(&attr, offset)?),
+                b"unit" => count.unit = Some(attribute_value(&attr, offset)?),
+                b"phase-name" => count.phase_name = Some(attribute_value(&attr, offset)?),
+                _ => (),
+            }
+        }
+        Ok(count)
+    }
+
+    /// Reads the attributes of a `<context>`, its value is added once the element is closed.
+    fn read_context(e: &BytesStart, offset: usize) -> Result<Context, XliffError> {
+        let mut context = Context::new("", "");
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"context-type" => context.context_type = attribute_value(&attr, offset)?,
+                b"match-mandatory" => {
+                    context.match_mandatory =
+                        matches!(attribute_value(&attr, offset)?.as_str(), "yes" | "true")
+                }
+                _ => (),
+            }
+        }
+        Ok(context)
+    }
+
+    /// Sets the text of a note from its content. The unrecognised nodes found within the text
+    /// are kept along with the length of the text placed before them.
+    fn set_note_text(note: &mut Note, value: &UnitValue) {
+        note.text.clear();
+        for part in &value.content {
+            match part {
+                ContentPart::Extension(markup) => note
+                    .extensions
+                    .nodes
+                    .push((note.text.len(), markup.clone())),
+                _ => part.push_plain_text(&mut note.text),
+            }
+        }
+    }
+
+    /// Reads the attributes of a `<note>`, its text is added once the element is closed.
+    fn read_note(e: &BytesStart, offset: usize) -> Result<Note, XliffError> {
+        let mut note = Note::new("");
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"from" => note.from = Some(attribute_value(&attr, offset)?),
+                b"xml:lang" => note.lang = Some(attribute_value(&attr, offset)?),
+                b"priority" => {
+                    note.priority = attribute_value(&attr, offset)?
+                        .trim()
+                        .parse()
+                        .ok()
+                        .filter(|priority| (1..=10).contains(priority))
+                }
+                b"annotates" => note.annotates = Annotates::from(&attribute_value(&attr, offset)?),
+                _ => note
+                    .extensions
+                    .attributes
+                    .push(extension_attribute(&attr, offset)?),
+            }
+        }
+        Ok(note)
+    }
+
+    /// Reads the attributes of the `<source>` of a translation unit or of an alternate
+    /// translation.
+    fn handle_source(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let parent = self.parent();
+        let depth = self.depth();
+        let unit = match parent {
+            TagCtx::Unit | TagCtx::AltTrans => {
+                current_unit(self.store, depth, TagCtx::Source.to_str(), offset)?
+            }
+            _ => return Ok(()),
+        };
+        let extensions = match parent {
+            TagCtx::AltTrans => match unit.alternatives.last_mut() {
+                Some(alternative) => &mut alternative.source_extensions,
+                None => return Ok(()),
+            },
+            _ => &mut unit.source_extensions,
+        };
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            extensions
+                .attributes
+                .push(extension_attribute(&attr, offset)?);
+        }
+        Ok(())
+    }
+
+    /// Reads the workflow state and the language of the `<target>` of a translation unit.
+    /// Units keep the `target-language` of their file unless the target declares its own.
+    fn handle_target(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        match self.parent() {
+            TagCtx::Unit => (),
+            TagCtx::AltTrans => return self.handle_alt_trans_target(e, offset),
+            _ => return Ok(()),
+        }
+
+        let depth = self.depth();
+        let unit = current_unit(self.store, depth, TagCtx::Target.to_str(), offset)?;
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"state" => unit.state = Some(TargetState::from(&attribute_value(&attr, offset)?)),
+                b"state-qualifier" => {
+                    unit.state_qualifier =
+                        Some(StateQualifier::from(&attribute_value(&attr, offset)?))
+                }
+                b"phase-name" => unit.phase_name = Some(attribute_value(&attr, offset)?),
+                b"xml:lang" => {
+                    unit.target_locale = Some(Locale::new(attribute_value(&attr, offset)?))
+                }
+                _ => unit
+                    .target_extensions
+                    .attributes
+                    .push(extension_attribute(&attr, offset)?),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the language and the state of the `<target>` of an alternate translation.
+    fn handle_alt_trans_target(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let depth = self.depth();
+        let unit = current_unit(self.store, depth, TagCtx::Target.to_str(), offset)?;
+        let alternative = match unit.alternatives.last_mut() {
+            Some(alternative) => alternative,
+            None => return Ok(()),
+        };
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"xml:lang" => {
+                    alternative.target_locale = Some(Locale::new(attribute_value(&attr, offset)?))
+                }
+                b"state" => {
+                    alternative.state = Some(TargetState::from(&attribute_value(&attr, offset)?))
+                }
+                _ => alternative
+                    .target_extensions
+                    .attributes
+                    .push(extension_attribute(&attr, offset)?),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_alt_trans(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut alternative = AltTrans::new(UnitValue { content: vec![] });
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"match-quality" => {
+                    alternative.match_quality = Some(attribute_value(&attr, offset)?)
+                }
+                b"origin" => alternative.origin = Some(attribute_value(&attr, offset)?),
+                b"tool-id" => alternative.tool_id = Some(attribute_value(&attr, offset)?),
+                b"alttranstype" => {
+                    alternative.alt_trans_type = Some(attribute_value(&attr, offset)?)
+                }
+                _ => alternative
+                    .extensions
+                    .attributes
+                    .push(extension_attribute(&attr, offset)?),
+            }
+        }
+
+        let depth = self.depth();
+        current_unit(self.store, depth, TagCtx::AltTrans.to_str(), offset)?
+            .alternatives
+            .push(alternative);
+        Ok(())
+    }
+
+    fn handle_group(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut group = Group::new();
+        // the group itself is already on the tag stack
+        let depth = self.depth() - 1;
+        group.translate = match depth {
+            0 => true,
+            _ => current_group(self.store, depth, TagCtx::Group.to_str(), offset)?.translate,
+        };
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"id" => group.id = Some(attribute_value(&attr, offset)?),
+                b"resname" => group.resname = Some(attribute_value(&attr, offset)?),
+                b"restype" => group.restype = Some(attribute_value(&attr, offset)?),
+                b"translate" => {
+                    group.translate = Self::translate_value(&attribute_value(&attr, offset)?)
+                }
+                _ => group
+                    .extensions
+                    .attributes
+                    .push(extension_attribute(&attr, offset)?),
+            }
+        }
+
+        current_body(self.store, depth, TagCtx::Group.to_str(), offset)?
+            .push(BodyItem::Group(group));
+        Ok(())
+    }
+
+    /// The translate value of the innermost open group, `true` outside of groups.
+    fn inherited_translate(&mut self, offset: usize) -> Result<bool, XliffError> {
+        let depth = self.depth();
+        match depth {
+            0 => Ok(true),
+            _ => Ok(current_group(self.store, depth, TagCtx::Unit.to_str(), offset)?.translate),
+        }
+    }
+
+    /// Reads the value of a maxwidth or minwidth attribute, which must be a whole number.
+    fn width_value(attr: &Attribute, offset: usize) -> Result<Option<u32>, XliffError> {
+        match attribute_value(attr, offset)?.trim().parse() {
+            Ok(width) => Ok(Some(width)),
+            Err(_) => Err(XliffError::structure(
+                format!(
+                    "the {} attribute must be a whole number",
+                    String::from_utf8_lossy(attr.key)
+                ),
+                offset,
+            )),
+        }
+    }
+
+    /// Reads the value of a translate attribute, which is `yes` or `no` in the specification
+    /// while `true` and `false` are written by some tools.
+    fn translate_value(value: &str) -> bool {
+        !matches!(value, "no" | "false")
+    }
+
+    /// The parent of the current element, which is still on top of the tag stack.
+    fn parent(&self) -> TagCtx {
+        let count = self.tags.len();
+        match count >= 2 {
+            true => self.tags[count - 2].0,
+            false => TagCtx::Xliff,
+        }
+    }
+
+    /// The number of groups which are currently open.
+    fn depth(&self) -> usize {
+        self.tags
+            .iter()
+            .filter(|(tag, _)| *tag == TagCtx::Group)
+            .count()
+    }
+
+    fn handle_file(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut file = TranslationFile::new("");
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"original" => {
+                    file.address = attribute_value(&attr, offset)?;
+                }
+                b"source-language" => {
+                    file.source_locale = Some(Locale::new(attribute_value(&attr, offset)?))
+                }
+                b"target-language" => {
+                    file.target_locale = Some(Locale::new(attribute_value(&attr, offset)?))
+                }
+                b"datatype" => file.data_type = attribute_value(&attr, offset)?,
+                _ => file
+                    .extensions
+                    .attributes
+                    .push(extension_attribute(&attr, offset)?),
+            }
+        }
+        self.store.groups.push(file);
+        Ok(())
+    }
+
+    fn handle_header_tool(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let file = current_file(self.store, TagCtx::Tool.to_str(), offset)?;
+        match file.header.as_mut() {
+            None => (),
+            Some(header) => {
+                let mut tool = Tool::new(String::new(), String::new());
+
+                for a in e.attributes() {
+                    let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+                    match attr.key {
+                        b"tool-id" => {
+                            tool.id = attribute_value(&attr, offset)?;
+                        }
+                        b"tool-name" => {
+                            tool.name = attribute_value(&attr, offset)?;
+                        }
+                        b"tool-version" => {
+                            tool.version = Some(attribute_value(&attr, offset)?);
+                        }
+                        b"tool-company" => {
+                            tool.company = Some(attribute_value(&attr, offset)?);
+                        }
+                        _ => tool
+                            .extensions
+                            .attributes
+                            .push(extension_attribute(&attr, offset)?),
+                    }
+                }
+
+                header.tools.push(tool);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_reference_file(&mut self, tag: TagCtx, offset: usize) -> Result<(), XliffError> {
+        // the file is replaced once its <internal-file> or <external-file> has been read
+        let reference = ReferenceFile::external("");
+        let file = current_file(self.store, tag.to_str(), offset)?;
+        if let Some(header) = file.header.as_mut() {
+            match tag {
+                TagCtx::Glossary => header.glossaries.push(reference),
+                _ => header.references.push(reference),
+            }
+        }
+        Ok(())
+    }
+
+    /// The `<glossary>` or `<reference>` which is the parent of the current element.
+    fn current_reference_file(
+        &mut self,
+        offset: usize,
+    ) -> Result<Option<&mut ReferenceFile>, XliffError> {
+        let parent = self.parent();
+        let file = current_file(self.store, parent.to_str(), offset)?;
+        Ok(file.header.as_mut().and_then(|header| match parent {
+            TagCtx::Glossary => header.glossaries.last_mut(),
+            TagCtx::Reference => header.references.last_mut(),
+            _ => None,
+        }))
+    }
+
+    fn handle_phase(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut phase = Phase::new("", "");
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            let value = attribute_value(&attr, offset)?;
+            match attr.key {
+                b"phase-name" => phase.phase_name = value,
+                b"process-name" => phase.process_name = value,
+                b"company-name" => phase.company_name = Some(value),
+                b"tool-id" => phase.tool_id = Some(value),
+                b"date" => phase.date = Some(value),
+                b"job-id" => phase.job_id = Some(value),
+                b"contact-name" => phase.contact_name = Some(value),
+                b"contact-email" => phase.contact_email = Some(value),
+                b"contact-phone" => phase.contact_phone = Some(value),
+                _ => phase
+                    .extensions
+                    .attributes
+                    .push(extension_attribute(&attr, offset)?),
+            }
+        }
+
+        let file = current_file(self.store, TagCtx::Phase.to_str(), offset)?;
+        if let Some(header) = file.header.as_mut() {
+            header.phases.push(phase);
+        }
+        Ok(())
+    }
+
+    fn handle_file_header(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut header = Header::new();
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            header
+                .extensions
+                .attributes
+                .push(extension_attribute(&attr, offset)?);
+        }
+        current_file(self.store, TagCtx::Header.to_str(), offset)?.header = Some(header);
+        Ok(())
+    }
+
+    /// Tracks the opened element along with its `xml:space` behaviour,
+    /// which is inherited from the parent element unless specified.
+    fn open_tag(
+        &mut self,
+        open_tag: TagCtx,
+        e: &BytesStart,
+        offset: usize,
+    ) -> Result<(), XliffError> {
+        let mut preserve = self.preserve_space();
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            if attr.key == b"xml:space" {
+                preserve = attribute_value(&attr, offset)? == "preserve";
+            }
+        }
+        self.tags.push((open_tag, preserve));
+        if let Some(children) = self.children.last_mut() {
+            children.push(String::from(open_tag.to_str()));
+        }
+        self.children.push(vec![]);
+        Ok(())
+    }
+
+    /// Closes the given element along with the elements left open within it,
+    /// keeping the order of the children of those which keep extensions.
+    fn close_tag(&mut self, close_tag: TagCtx, offset: usize) -> Result<(), XliffError> {
+        while let Some((tag, _)) = self.tags.last().copied() {
+            let order = self.children.pop().unwrap_or_default();
+            if let Some(extensions) = self.extensions_of(tag, offset)? {
+                extensions.order = order;
+            }
+            self.tags.pop();
+            if tag == close_tag {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether white space is preserved within the current element.
+    fn preserve_space(&self) -> bool {
+        match self.tags.last() {
+            Some((_, preserve)) => *preserve,
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,726 @@
+//! Defines the XLIFF 2.0 reader
+
+use super::{
+    attribute_value, cdata_text, current_body, current_file, current_group, current_unit,
+    element_markup, next_event, ContentBuilder, Namespace, METADATA_NAMESPACE, XLIFF_20_NAMESPACE,
+};
+use crate::error::XliffError;
+use crate::store::{
+    Annotates, BodyItem, ContentPart, Context, ContextGroup, Count, CountGroup, Group, Header,
+    InlineCode, InlineKind, Locale, Note, Prop, PropGroup, StateQualifier, Store, TagCtx,
+    TargetState, Tool, TranslationFile, Unit, UnitValue, CTYPES_V20,
+};
+use quick_xml::events::BytesStart;
+use quick_xml::events::Event::{CData, Empty, End, Eof, Start, Text};
+use quick_xml::Reader;
+use std::io::BufRead;
+
+/// The content of a `<segment>` or `<ignorable>` element of the unit being read.
+struct Part {
+    ignorable: bool,
+    state: Option<String>,
+    source: ContentBuilder,
+    target: Option<ContentBuilder>,
+}
+
+/// Inline elements of XLIFF 2.0, by local name.
+const INLINE_ELEMENTS: &[&[u8]] = &[b"cp", b"ph", b"pc", b"sc", b"ec", b"mrk", b"sm", b"em"];
+
+/// An `<mda:metaGroup>` element holding XLIFF 1.2 data written by `WriterXliff20`.
+struct MetaGroup {
+    category: String,
+    metas: Vec<(String, String)>,
+}
+
+impl MetaGroup {
+    /// Returns the value of the first meta of the given type.
+    fn meta(&self, name: &str) -> Option<String> {
+        self.metas
+            .iter()
+            .find(|(meta_type, _)| meta_type == name)
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Reads a `context-group` metadata group, each meta other than `name` and `purpose` being
+    /// a context of its type.
+    fn context_group(&self) -> ContextGroup {
+        let mut group = ContextGroup::new();
+        for (meta_type, value) in &self.metas {
+            match meta_type.as_str() {
+                "name" => group.name = Some(value.clone()),
+                "purpose" => group.purpose = Some(value.clone()),
+                context_type => group.contexts.push(Context::new(context_type, value)),
+            }
+        }
+        group
+    }
+
+    /// Reads a `count-group` metadata group, each meta other than `name` being a count typed
+    /// `count-type:unit`.
+    fn count_group(&self) -> CountGroup {
+        let mut group = CountGroup::new(&self.meta("name").unwrap_or_default());
+        for (meta_type, value) in self.metas.iter().filter(|(t, _)| t != "name") {
+            let value = match value.trim().parse() {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            let (count_type, unit) = match meta_type.split_once(':') {
+                Some((count_type, unit)) => (count_type, Some(String::from(unit))),
+                None => (meta_type.as_str(), None),
+            };
+            group.counts.push(Count {
+                count_type: Some(String::from(count_type)).filter(|t| !t.is_empty()),
+                unit,
+                phase_name: None,
+                value,
+            });
+        }
+        group
+    }
+
+    /// Reads a `prop-group` metadata group, each meta other than `name` being a property of its
+    /// type.
+    fn prop_group(&self) -> PropGroup {
+        let mut group = PropGroup::new();
+        for (meta_type, value) in &self.metas {
+            match meta_type.as_str() {
+                "name" => group.name = Some(value.clone()),
+                prop_type => group.props.push(Prop::new(prop_type, value)),
+            }
+        }
+        group
+    }
+}
+
+/// The metas of a `tool` metadata group which are read into the fields of a `Tool`.
+const TOOL_METAS: &[&str] = &["tool-id", "tool-name", "tool-version", "tool-company"];
+
+/// Populates a `Store` from the contents of an XLIFF 2.0 document.
+///
+/// The `<segment>` and `<ignorable>` elements of a `<unit>` are joined in document order into the
+/// `source` and `target` of the translation `Unit`. Ignorable content without a `<target>` is
+/// copied from its `<source>`, as required by the specification.
+///
+/// `<group>` elements are read into nested `Group`s, and the `name` of groups and units into their
+/// `resname`. The `state` of the first `<segment>` is read into the `state` of the unit.
+///
+/// Inline codes are read into the XLIFF 1.2 `InlineCode` they are written from by `WriterXliff20`.
+/// Codes referring to native code in the `<originalData>` of the unit hold it as their content:
+/// `<ph/>` is read as `<ph>`, `<sc/>` as `<bpt>` and `<ec/>` as `<ept>`, or as `<it>` when
+/// isolated. Codes without native code are read as `<x/>`, `<bx/>` and `<ex/>`, and `<pc>` as
+/// `<g>`. `<mrk>` is read as `<mrk>`, its `id` as `mid` and its `type` as `mtype`. The `startRef`
+/// of an `<ec/>` becomes its `id`, the one of the code it ends. Standalone markers, `<sm/>` and
+/// `<em/>`, are not read.
+///
+/// The metadata groups written by `WriterXliff20` are read back into the XLIFF 1.2 data they
+/// hold, such as the `data_type` and header `tools` of the `TranslationFile` or the context,
+/// count and property groups of groups and units.
+pub(crate) struct ReaderXliff20<'s> {
+    store: &'s mut Store,
+    tags: Vec<TagCtx>,
+    /// The native code held by the `<originalData>` of the unit being read, by identifier.
+    data: Vec<(String, String)>,
+    source_locale: Option<Locale>,
+    target_locale: Option<Locale>,
+    file_translate: bool,
+    parts: Vec<Part>,
+    note: Option<Note>,
+    meta_group: Option<MetaGroup>,
+    meta: Option<(String, String)>,
+}
+
+impl<'s> ReaderXliff20<'s> {
+    pub(crate) fn new(store: &'s mut Store) -> Self {
+        ReaderXliff20 {
+            store,
+            tags: vec![],
+            data: vec![],
+            source_locale: None,
+            target_locale: None,
+            file_translate: true,
+            parts: vec![],
+            note: None,
+            meta_group: None,
+            meta: None,
+        }
+    }
+
+    /// Reads the document following the opening `<xliff>` element.
+    pub(crate) fn read<R: BufRead>(
+        mut self,
+        r: &mut Reader<R>,
+        ns_buf: &mut Vec<u8>,
+        root: &BytesStart,
+        root_offset: usize,
+    ) -> Result<(), XliffError> {
+        let mut buf = Vec::new();
+        self.handle_xliff(root, root_offset)?;
+        self.tags.push(TagCtx::Xliff);
+
+        loop {
+            let offset = r.buffer_position();
+            let (ns, event) = next_event(r, &mut buf, ns_buf)?;
+            match event {
+                Start(ref e) if self.is_inline(ns, e.local_name()) => self.open_code(e, offset)?,
+                Empty(ref e) if self.is_inline(ns, e.local_name()) => {
+                    self.open_code(e, offset)?;
+                    self.close_code(e.local_name());
+                }
+                End(ref e) if self.is_inline(ns, e.local_name()) => self.close_code(e.local_name()),
+                Start(ref e) => match ns.tag_v20(e.local_name()) {
+                    Some(tag) => self.open_tag(tag, e, offset)?,
+                    // the text of extension elements is not part of the content they are found in
+                    None if ns == Namespace::Foreign => {
+                        element_markup(r, ns_buf, e, offset)?;
+                    }
+                    None => (),
+                },
+                Empty(ref e) => {
+                    if let Some(tag) = ns.tag_v20(e.local_name()) {
+                        self.open_tag(tag, e, offset)?;
+                        self.close_tag(tag, offset)?;
+                    }
+                }
+                End(ref e) => {
+                    if let Some(tag) = ns.tag_v20(e.local_name()) {
+                        self.close_tag(tag, offset)?;
+                    }
+                }
+                Text(e) => {
+                    let text = e
+                        .unescape_and_decode(r)
+                        .map_err(|e| XliffError::xml(e, offset))?;
+                    self.add_text(&text);
+                }
+                CData(e) => self.add_text(&cdata_text(&e, offset)?),
+                Eof => break,
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn open_tag(&mut self, tag: TagCtx, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        match tag {
+            TagCtx::File => self.handle_file(e, offset)?,
+            TagCtx::Group => self.handle_group(e, offset)?,
+            TagCtx::Unit => self.handle_unit(e, offset)?,
+            TagCtx::Segment | TagCtx::Ignorable => self.parts.push(Part {
+                ignorable: tag == TagCtx::Ignorable,
+                state: Self::attribute(e, b"state", offset)?,
+                source: ContentBuilder::new(),
+                target: None,
+            }),
+            TagCtx::Target => {
+                if let Some(part) = self.parts.last_mut() {
+                    part.target = Some(ContentBuilder::new());
+                }
+            }
+            TagCtx::Data => {
+                let id = Self::attribute(e, b"id", offset)?.unwrap_or_default();
+                self.data.push((id, String::new()));
+            }
+            TagCtx::Note => {
+                let mut note = Note::new("");
+                note.priority = Self::attribute(e, b"priority", offset)?
+                    .and_then(|priority| priority.trim().parse().ok())
+                    .filter(|priority| (1..=10).contains(priority));
+                note.annotates = Self::attribute(e, b"appliesTo", offset)?
+                    .and_then(|applies_to| Annotates::from(&applies_to));
+                self.note = Some(note);
+            }
+            TagCtx::MetaGroup => {
+                self.meta_group = Some(MetaGroup {
+                    category: Self::attribute(e, b"category", offset)?.unwrap_or_default(),
+                    metas: vec![],
+                })
+            }
+            TagCtx::Meta => {
+                let meta_type = Self::attribute(e, b"type", offset)?.unwrap_or_default();
+                self.meta = Some((meta_type, String::new()));
+            }
+            _ => (),
+        }
+        self.tags.push(tag);
+        Ok(())
+    }
+
+    fn close_tag(&mut self, close_tag: TagCtx, offset: usize) -> Result<(), XliffError> {
+        while let Some(tag) = self.tags.pop() {
+            if tag == close_tag {
+                break;
+            }
+        }
+
+        match close_tag {
+            TagCtx::Unit => self.add_unit_content(offset)?,
+            TagCtx::Note => {
+                if let Some(note) = self.note.take() {
+                    self.add_note(note, offset)?;
+                }
+            }
+            TagCtx::Meta => {
+                if let (Some(meta), Some(group)) = (self.meta.take(), self.meta_group.as_mut()) {
+                    group.metas.push(meta);
+                }
+            }
+            TagCtx::MetaGroup => {
+                if let Some(group) = self.meta_group.take() {
+                    self.add_meta_group(group, offset)?;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn add_text(&mut self, text: &str) {
+        match self.tags.last() {
+            Some(TagCtx::Source) | Some(TagCtx::Target) => {
+                if let Some(content) = self.content() {
+                    content.push_text(text);
+                }
+            }
+            Some(TagCtx::Data) => {
+                if let Some((_, data)) = self.data.last_mut() {
+                    data.push_str(text);
+                }
+            }
+            Some(TagCtx::Note) => {
+                if let Some(note) = self.note.as_mut() {
+                    note.text.push_str(text);
+                }
+            }
+            Some(TagCtx::Meta) => {
+                if let Some((_, value)) = self.meta.as_mut() {
+                    value.push_str(text);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// The content of the `<source>` or `<target>` being read.
+    fn content(&mut self) -> Option<&mut ContentBuilder> {
+        let part = self.parts.last_mut()?;
+        match self.tags.last() {
+            Some(TagCtx::Source) => Some(&mut part.source),
+            Some(TagCtx::Target) => part.target.as_mut(),
+            _ => None,
+        }
+    }
+
+    /// Whether the element is an inline element found within content or native code.
+    fn is_inline(&self, ns: Namespace, local_name: &[u8]) -> bool {
+        let in_content = matches!(
+            self.tags.last(),
+            Some(TagCtx::Source) | Some(TagCtx::Target) | Some(TagCtx::Data)
+        );
+        in_content && ns.is_xliff() && INLINE_ELEMENTS.contains(&local_name)
+    }
+
+    /// Reads the start of an inline element, or the whole of an empty one.
+    fn open_code(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut attributes = vec![];
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            let key = String::from_utf8_lossy(attr.key).into_owned();
+            attributes.push((key, attribute_value(&attr, offset)?));
+        }
+        let attribute = |name: &str| {
+            attributes
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        if e.local_name() == b"cp" {
+            // a character which cannot be written in XML, such as a control character
+            let c = attribute("hex")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .and_then(std::char::from_u32);
+            if let Some(c) = c {
+                self.add_text(c.encode_utf8(&mut [0; 4]));
+            }
+            return Ok(());
+        }
+
+        let data = attribute("dataRef").and_then(|data_ref| {
+            self.data
+                .iter()
+                .find(|(id, _)| id == data_ref)
+                .map(|(_, data)| data.clone())
+        });
+        let isolated = attribute("isolated") == Some("yes");
+        let (kind, id) = match (e.local_name(), &data) {
+            (b"ph", Some(_)) => (InlineKind::Ph, attribute("id")),
+            (b"ph", None) => (InlineKind::X, attribute("id")),
+            (b"pc", _) => (InlineKind::G, attribute("id")),
+            (b"sc", Some(_)) if isolated => (InlineKind::It, attribute("id")),
+            (b"sc", Some(_)) => (InlineKind::Bpt, attribute("id")),
+            (b"sc", None) => (InlineKind::Bx, attribute("id")),
+            (b"ec", Some(_)) if isolated => (InlineKind::It, attribute("id")),
+            (b"ec", Some(_)) => (InlineKind::Ept, attribute("startRef")),
+            (b"ec", None) => (InlineKind::Ex, attribute("startRef").or(attribute("id"))),
+            (b"mrk", _) => (InlineKind::Mrk, None),
+            _ => return Ok(()),
+        };
+
+        let mut code = InlineCode::new(kind, id);
+        if kind == InlineKind::Mrk {
+            let mtype = match (attribute("type"), attribute("translate")) {
+                (Some("term"), _) => "term",
+                (Some("comment"), _) => "x-comment",
+                (_, Some("no")) => "protected",
+                _ => "x-generic",
+            };
+            code.attributes
+                .push((String::from("mtype"), String::from(mtype)));
+            if let Some(mid) = attribute("id") {
+                code.attributes
+                    .push((String::from("mid"), String::from(mid)));
+            }
+            if let (Some("comment"), Some(comment)) = (attribute("type"), attribute("value")) {
+                code.attributes
+                    .push((String::from("comment"), String::from(comment)));
+            }
+        } else {
+            if kind == InlineKind::It {
+                let pos = match e.local_name() {
+                    b"sc" => "open",
+                    _ => "close",
+                };
+                code.attributes
+                    .push((String::from("pos"), String::from(pos)));
+            }
+            let ctype = CTYPES_V20.iter().find(|(_, code_type, sub_type)| {
+                attribute("type") == Some(*code_type) && attribute("subType") == *sub_type
+            });
+            if let Some((ctype, _, _)) = ctype {
+                code.attributes
+                    .push((String::from("ctype"), String::from(*ctype)));
+            }
+            if let Some(equiv) = attribute("equiv") {
+                code.attributes
+                    .push((String::from("equiv-text"), String::from(equiv)));
+            }
+        }
+        if let Some(data) = data {
+            code.content.push(ContentPart::Text(data));
+        }
+
+        if let Some(content) = self.content() {
+            match kind {
+                InlineKind::G | InlineKind::Mrk => content.open_code(code),
+                _ => content.add_code(code),
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the end of an inline element.
+    fn close_code(&mut self, local_name: &[u8]) {
+        if local_name == b"pc" || local_name == b"mrk" {
+            if let Some(content) = self.content() {
+                content.close_code();
+            }
+        }
+    }
+
+    /// The number of open `<group>` elements.
+    fn depth(&self) -> usize {
+        self.tags
+            .iter()
+            .filter(|tag| **tag == TagCtx::Group)
+            .count()
+    }
+
+    /// Whether the content of the innermost open `<group>`, or of the file, is to be translated.
+    fn translate(&mut self, offset: usize) -> Result<bool, XliffError> {
+        match self.depth() {
+            0 => Ok(self.file_translate),
+            depth => {
+                Ok(current_group(self.store, depth, TagCtx::Group.to_str_v20(), offset)?.translate)
+            }
+        }
+    }
+
+    fn add_unit_content(&mut self, offset: usize) -> Result<(), XliffError> {
+        let parts: Vec<Part> = self.parts.drain(..).collect();
+        let depth = self.depth();
+        let unit = current_unit(self.store, depth, TagCtx::Unit.to_str_v20(), offset)?;
+
+        let parts: Vec<(bool, Option<String>, UnitValue, Option<UnitValue>)> = parts
+            .into_iter()
+            .map(|p| {
+                (
+                    p.ignorable,
+                    p.state,
+                    p.source.finish(),
+                    p.target.map(|t| t.finish()),
+                )
+            })
+            .collect();
+
+        if !parts.is_empty() {
+            unit.source = Some(join(parts.iter().map(|(_, _, source, _)| source)));
+        }
+
+        if parts
+            .iter()
+            .any(|(ignorable, _, _, target)| !ignorable && target.is_some())
+        {
+            let target = parts.iter().filter_map(|(ignorable, _, source, target)| {
+                match (target, ignorable) {
+                    (Some(target), _) => Some(target),
+                    (None, true) => Some(source),
+                    (None, false) => None,
+                }
+            });
+            unit.target = Some(join(target));
+        }
+
+        // the exact XLIFF 1.2 state, if any, has been read from the metadata of the unit
+        if unit.state.is_none() && unit.target.is_some() {
+            unit.state = parts
+                .iter()
+                .find(|(ignorable, _, _, _)| !ignorable)
+                .and_then(|(_, state, _, _)| state.as_deref())
+                .and_then(TargetState::from_v20);
+        }
+
+        Ok(())
+    }
+
+    fn add_note(&mut self, note: Note, offset: usize) -> Result<(), XliffError> {
+        let count = self.tags.len();
+        let depth = self.depth();
+        // the note is closed, so its <notes> parent is on top and the owner below it
+        match count >= 2 && self.tags[count - 1] == TagCtx::Notes {
+            false => (),
+            true => match self.tags[count - 2] {
+                TagCtx::Unit => {
+                    current_unit(self.store, depth, TagCtx::Note.to_str(), offset)?
+                        .notes
+                        .push(note);
+                }
+                TagCtx::Group => {
+                    current_group(self.store, depth, TagCtx::Note.to_str(), offset)?
+                        .notes
+                        .push(note);
+                }
+                TagCtx::File => {
+                    current_file(self.store, TagCtx::Note.to_str(), offset)?
+                        .header
+                        .get_or_insert_with(Header::new)
+                        .notes
+                        .push(note);
+                }
+                _ => (),
+            },
+        }
+        Ok(())
+    }
+
+    fn add_meta_group(&mut self, group: MetaGroup, offset: usize) -> Result<(), XliffError> {
+        let count = self.tags.len();
+        let depth = self.depth();
+        let element = TagCtx::MetaGroup.to_str_v20();
+        // the group is closed, so its <mda:metadata> parent is on top and the owner below it
+        if count < 2 || self.tags[count - 1] != TagCtx::Metadata {
+            return Ok(());
+        }
+
+        match self.tags[count - 2] {
+            TagCtx::File => {
+                let file = current_file(self.store, element, offset)?;
+                match group.category.as_str() {
+                    "datatype" => file.data_type = group.meta("datatype").unwrap_or_default(),
+                    "tool" => {
+                        let mut tool = Tool::new(
+                            group.meta("tool-id").unwrap_or_default(),
+                            group.meta("tool-name").unwrap_or_default(),
+                        );
+                        tool.version = group.meta("tool-version");
+                        tool.company = group.meta("tool-company");
+                        tool.extensions.attributes = group
+                            .metas
+                            .into_iter()
+                            .filter(|(meta_type, _)| !TOOL_METAS.contains(&meta_type.as_str()))
+                            .collect();
+                        file.header.get_or_insert_with(Header::new).tools.push(tool);
+                    }
+                    "count-group" => file
+                        .header
+                        .get_or_insert_with(Header::new)
+                        .count_groups
+                        .push(group.count_group()),
+                    "prop-group" => file
+                        .header
+                        .get_or_insert_with(Header::new)
+                        .prop_groups
+                        .push(group.prop_group()),
+                    "attributes" => file.extensions.attributes.extend(group.metas),
+                    _ => (),
+                }
+            }
+            TagCtx::Group => {
+                let target = current_group(self.store, depth, element, offset)?;
+                match group.category.as_str() {
+                    "group" => target.restype = group.meta("restype"),
+                    "context-group" => target.context_groups.push(group.context_group()),
+                    "count-group" => target.count_groups.push(group.count_group()),
+                    "prop-group" => target.prop_groups.push(group.prop_group()),
+                    "attributes" => target.extensions.attributes.extend(group.metas),
+                    _ => (),
+                }
+            }
+            TagCtx::Unit => {
+                let unit = current_unit(self.store, depth, element, offset)?;
+                match group.category.as_str() {
+                    "trans-unit" => {
+                        unit.restype = group.meta("restype");
+                        unit.data_type = group.meta("datatype");
+                        unit.max_width = group.meta("maxwidth").and_then(|w| w.parse().ok());
+                        unit.min_width = group.meta("minwidth").and_then(|w| w.parse().ok());
+                        unit.size_unit = group.meta("size-unit");
+                        unit.char_class = group.meta("charclass");
+                        unit.approved = group.meta("approved").as_deref() == Some("yes");
+                    }
+                    "target" => {
+                        unit.state = group.meta("state").map(|state| TargetState::from(&state));
+                        unit.state_qualifier = group
+                            .meta("state-qualifier")
+                            .map(|qualifier| StateQualifier::from(&qualifier));
+                        unit.phase_name = group.meta("phase-name");
+                    }
+                    "context-group" => unit.context_groups.push(group.context_group()),
+                    "count-group" => unit.count_groups.push(group.count_group()),
+                    "prop-group" => unit.prop_groups.push(group.prop_group()),
+                    "attributes" => unit.extensions.attributes.extend(group.metas),
+                    _ => (),
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn attribute(e: &BytesStart, name: &[u8], offset: usize) -> Result<Option<String>, XliffError> {
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            if attr.key == name {
+                return Ok(Some(attribute_value(&attr, offset)?));
+            }
+        }
+        Ok(None)
+    }
+
+    fn handle_xliff(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"srcLang" => {
+                    self.source_locale = Some(Locale::new(attribute_value(&attr, offset)?));
+                }
+                b"trgLang" => {
+                    self.target_locale = Some(Locale::new(attribute_value(&attr, offset)?));
+                }
+                // keeps the prefixes of foreign namespaces, which unrecognised attributes may use
+                key if key.starts_with(b"xmlns:") => {
+                    let value = attribute_value(&attr, offset)?;
+                    if value != XLIFF_20_NAMESPACE && value != METADATA_NAMESPACE {
+                        let name = String::from_utf8_lossy(key).into_owned();
+                        self.store.extensions.attributes.push((name, value));
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_file(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut file = TranslationFile::new("");
+        file.source_locale = self.source_locale.clone();
+        file.target_locale = self.target_locale.clone();
+        self.file_translate = true;
+
+        let mut original = None;
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"id" => file.id = Some(attribute_value(&attr, offset)?),
+                b"original" => original = Some(attribute_value(&attr, offset)?),
+                b"translate" => self.file_translate = attribute_value(&attr, offset)? != "no",
+                _ => (),
+            }
+        }
+
+        // `original` is optional in XLIFF 2.0, the required `id` is used in its absence
+        file.address = original.or_else(|| file.id.clone()).unwrap_or_default();
+        self.store.groups.push(file);
+        Ok(())
+    }
+
+    fn handle_group(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut group = Group::new();
+        group.translate = self.translate(offset)?;
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"id" => group.id = Some(attribute_value(&attr, offset)?),
+                b"name" => group.resname = Some(attribute_value(&attr, offset)?),
+                b"translate" => group.translate = attribute_value(&attr, offset)? != "no",
+                _ => (),
+            }
+        }
+
+        let depth = self.depth();
+        current_body(self.store, depth, TagCtx::Group.to_str_v20(), offset)?
+            .push(BodyItem::Group(group));
+        Ok(())
+    }
+
+    fn handle_unit(&mut self, e: &BytesStart, offset: usize) -> Result<(), XliffError> {
+        let mut unit = Unit::new();
+        unit.translate = self.translate(offset)?;
+
+        for a in e.attributes() {
+            let attr = a.map_err(|e| XliffError::xml(e, offset))?;
+            match attr.key {
+                b"id" => unit.id = attribute_value(&attr, offset)?,
+                b"name" => unit.resname = Some(attribute_value(&attr, offset)?),
+                b"translate" => unit.translate = attribute_value(&attr, offset)? != "no",
+                _ => (),
+            }
+        }
+
+        let file = current_file(self.store, TagCtx::Unit.to_str_v20(), offset)?;
+        unit.source_locale = file.source_locale.clone();
+        unit.target_locale = file.target_locale.clone();
+        let depth = self.depth();
+        current_body(self.store, depth, TagCtx::Unit.to_str_v20(), offset)?
+            .push(BodyItem::Unit(unit));
+        self.parts.clear();
+        self.data.clear();
+        Ok(())
+    }
+}
+
+/// Joins the content of the segments of a unit, merging the text placed around their boundaries.
+fn join<'a, I: Iterator<Item = &'a UnitValue>>(parts: I) -> UnitValue {
+    let mut content: Vec<ContentPart> = vec![];
+    for part in parts.flat_map(|part| &part.content) {
+        match (content.last_mut(), part) {
+            (Some(ContentPart::Text(text)), ContentPart::Text(more)) => text.push_str(more),
+            _ => content.push(part.clone()),
+        }
+    }
+    match content.is_empty() {
+        true => UnitValue::new(""),
+        false => UnitValue { content },
+    }
+}
@@ -1,32 +1,230 @@
 //! Defines a translation store implementation which can be used to interact with XLIFF files
 
-use quick_xml::events::BytesStart;
-use quick_xml::events::Event::{Empty, End, Eof, Start, Text};
-use quick_xml::Reader;
-use std::io::BufRead;
+use crate::error::XliffError;
+use crate::readers;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
 
 /// The content of a translation unit or a note
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub struct UnitValue {
-    /// Plain text value of the node.
-    pub text: String,
+    /// The text and inline codes of the node, in document order.
+    pub content: Vec<ContentPart>,
 }
 
-impl Clone for UnitValue {
-    fn clone(&self) -> Self {
+impl UnitValue {
+    /// Creates a value holding plain text only.
+    pub fn new(text: &str) -> Self {
         UnitValue {
-            text: self.text.clone(),
+            content: vec![ContentPart::Text(String::from(text))],
+        }
+    }
+
+    /// Returns the text of the node without its inline codes.
+    ///
+    /// The content of `<g>` and `<mrk>` elements is part of the text, while the native code held
+    /// by `<ph>`, `<bpt>`, `<ept>` and `<it>` elements is left out.
+    pub fn plain_text(&self) -> String {
+        let mut text = String::new();
+        for part in &self.content {
+            part.push_plain_text(&mut text);
         }
+        text
+    }
+}
+
+impl From<&str> for UnitValue {
+    fn from(text: &str) -> Self {
+        UnitValue::new(text)
+    }
+}
+
+/// The attributes and child nodes of an element which are not part of the model, such as
+/// vendor extensions in their own namespace or XML comments. They are kept so that a document
+/// which has been read can be written back without losing data.
+#[derive(Clone, Default)]
+pub struct Extensions {
+    /// The unrecognised attributes, with their qualified name and unescaped value,
+    /// in document order.
+    pub attributes: Vec<(String, String)>,
+    /// The unrecognised child elements, comments and processing instructions, as XML markup
+    /// in document order, each along with the number of recognised children placed before it
+    /// so it is written back in place. Within a `<note>`, the position is the length in bytes
+    /// of the text placed before the node instead.
+    pub nodes: Vec<(usize, String)>,
+    /// The names of the recognised child elements in document order, so children read in an
+    /// order other than the one of the schema are written back in the same order. As it only
+    /// affects the layout of the document, it is not compared by `==`.
+    pub order: Vec<String>,
+}
+
+impl PartialEq for Extensions {
+    fn eq(&self, other: &Self) -> bool {
+        self.attributes == other.attributes && self.nodes == other.nodes
+    }
+}
+
+impl Extensions {
+    /// Whether no unrecognised attribute or node has been kept.
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty() && self.nodes.is_empty()
     }
+}
+
+/// A fragment of the content of a `<source>` or `<target>` element.
+#[derive(PartialEq, Clone)]
+pub enum ContentPart {
+    /// Plain text.
+    Text(String),
+    /// An inline element such as `<g>`, `<x/>` or `<ph>`.
+    Code(InlineCode),
+    /// An element, comment or processing instruction which is not part of the model,
+    /// kept as XML markup. It is not part of the text.
+    Extension(String),
+}
 
-    fn clone_from(&mut self, source: &Self) {
-        self.text = source.text.clone();
+impl ContentPart {
+    pub(crate) fn push_plain_text(&self, text: &mut String) {
+        match self {
+            ContentPart::Text(t) => text.push_str(t),
+            ContentPart::Code(code) => {
+                if code.kind.contains_text() {
+                    for part in &code.content {
+                        part.push_plain_text(text);
+                    }
+                }
+            }
+            ContentPart::Extension(_) => (),
+        }
     }
 }
 
+/// Inline element - Codes and markers placed within the text of `<source>` and `<target>`.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#Struct_InLine
+#[derive(PartialEq, Clone)]
+pub struct InlineCode {
+    /// The kind of inline element.
+    pub kind: InlineKind,
+    /// Identifier - The id attribute, used to match inline codes between `<source>` and `<target>`.
+    /// `<mrk>` and `<sub>` elements have no identifier.
+    pub id: Option<String>,
+    /// The remaining attributes of the element, such as `ctype`, `rid`, `mtype` or `equiv-text`,
+    /// in document order.
+    pub attributes: Vec<(String, String)>,
+    /// The content of the element. Text for `<g>`, `<mrk>` and `<sub>`, the native code for
+    /// `<ph>`, `<bpt>`, `<ept>` and `<it>`. Always empty for `<x/>`, `<bx/>` and `<ex/>`.
+    pub content: Vec<ContentPart>,
+}
+
+impl InlineCode {
+    /// Creates an empty inline element of the given kind.
+    pub fn new(kind: InlineKind, id: Option<&str>) -> Self {
+        InlineCode {
+            kind,
+            id: id.map(String::from),
+            attributes: vec![],
+            content: vec![],
+        }
+    }
+
+    /// Returns the value of the attribute with the given name.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub(crate) fn attributes(&self) -> Vec<(&str, &str)> {
+        let mut attributes = vec![];
+        if let Some(id) = &self.id {
+            attributes.push(("id", id.as_str()));
+        }
+        for (key, value) in &self.attributes {
+            attributes.push((key.as_str(), value.as_str()));
+        }
+        attributes
+    }
+}
+
+/// The inline elements of XLIFF 1.2.
+#[derive(PartialEq, Copy, Clone)]
+pub enum InlineKind {
+    /// Generic group placeholder - `<g>` wraps text which is formatted by the original document.
+    G,
+    /// Generic placeholder - `<x/>` replaces a standalone code of the original document.
+    X,
+    /// Begin paired placeholder - `<bx/>` replaces the beginning of a paired code.
+    Bx,
+    /// End paired placeholder - `<ex/>` replaces the end of a paired code.
+    Ex,
+    /// Placeholder - `<ph>` holds a standalone native code.
+    Ph,
+    /// Begin paired tag - `<bpt>` holds the native code beginning a paired sequence.
+    Bpt,
+    /// End paired tag - `<ept>` holds the native code ending a paired sequence.
+    Ept,
+    /// Isolated tag - `<it>` holds a native code whose pair is outside of the segment.
+    It,
+    /// Marker - `<mrk>` delimits a span of text with special meaning, such as a term.
+    Mrk,
+    /// Sub-flow - `<sub>` holds translatable text within a native code.
+    Sub,
+}
+
+impl InlineKind {
+    pub(crate) fn from(name: &[u8]) -> Option<Self> {
+        match name {
+            b"g" => Some(InlineKind::G),
+            b"x" => Some(InlineKind::X),
+            b"bx" => Some(InlineKind::Bx),
+            b"ex" => Some(InlineKind::Ex),
+            b"ph" => Some(InlineKind::Ph),
+            b"bpt" => Some(InlineKind::Bpt),
+            b"ept" => Some(InlineKind::Ept),
+            b"it" => Some(InlineKind::It),
+            b"mrk" => Some(InlineKind::Mrk),
+            b"sub" => Some(InlineKind::Sub),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_str(self) -> &'static str {
+        match self {
+            InlineKind::G => "g",
+            InlineKind::X => "x",
+            InlineKind::Bx => "bx",
+            InlineKind::Ex => "ex",
+            InlineKind::Ph => "ph",
+            InlineKind::Bpt => "bpt",
+            InlineKind::Ept => "ept",
+            InlineKind::It => "it",
+            InlineKind::Mrk => "mrk",
+            InlineKind::Sub => "sub",
+        }
+    }
+
+    /// Whether the content of the element is translatable text rather than native code.
+    fn contains_text(self) -> bool {
+        matches!(self, InlineKind::G | InlineKind::Mrk)
+    }
+}
+
+/// The `ctype` values of XLIFF 1.2 inline codes with an XLIFF 2.0 equivalent, along with the
+/// `type` and `subType` of the XLIFF 2.0 code.
+pub(crate) const CTYPES_V20: &[(&str, &str, Option<&str>)] = &[
+    ("bold", "fmt", Some("xlf:b")),
+    ("italic", "fmt", Some("xlf:i")),
+    ("underlined", "fmt", Some("xlf:u")),
+    ("lb", "fmt", Some("xlf:lb")),
+    ("link", "link", None),
+    ("image", "image", None),
+];
+
 /// A unit of translatable data.
 /// Translation unit - The `<trans-unit>` elements contains a `<source>, `<target>` and associated elements.
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub struct Unit {
     /// Identifier - uniquely identify the `<trans-unit>` within all
     /// `<trans-unit>` and `<bin-unit>` elements within the same `<file>.
@@ -34,21 +232,78 @@ pub struct Unit {
     /// Indicates whether the `<trans-unit>` is to be translated.
     /// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#translate
     pub translate: bool,
+    /// Resource name - The resname attribute, the resource identifier used by the original
+    /// document, such as the key of an Android string.
+    pub resname: Option<String>,
+    /// Resource type - The restype attribute indicates the resource type of the unit,
+    /// such as `string` or `x-android-plurals`.
+    pub restype: Option<String>,
+    /// Data type - The datatype attribute specifies the kind of text of the unit,
+    /// when it differs from the datatype of the `<file>`.
+    pub data_type: Option<String>,
+    /// Maximum width - The maxwidth attribute, the largest allowed width of the target,
+    /// measured in `size_unit`.
+    pub max_width: Option<u32>,
+    /// Minimum width - The minwidth attribute, the smallest allowed width of the target,
+    /// measured in `size_unit`.
+    pub min_width: Option<u32>,
+    /// Size unit - The size-unit attribute, the unit of `max_width` and `min_width`, such as
+    /// `char`, `byte` or `pixel`. The specification defaults to `pixel` when it is missing.
+    pub size_unit: Option<String>,
+    /// Character class - The charclass attribute, the set of characters allowed in the target.
+    pub char_class: Option<String>,
     /// Source translation. The `<source>` element is used to delimit a unit of text
     /// that could be a paragraph, a title, a menu item, a caption, etc.
     pub source: Option<UnitValue>,
     /// Target translation. The `<target>` element contains the translation of the content
     /// of the sibling `<source>` element.
     pub target: Option<UnitValue>,
+    /// Segmented source. The `<seg-source>` element holds the content of `<source>` with each
+    /// sentence delimited by a `<mrk mtype="seg">` element.
+    pub seg_source: Option<UnitValue>,
     /// Source language - The language for the `<source>` elements in the given `<file>` element.
     pub source_locale: Option<Locale>,
-    /// Target language - The language for the `<target>` elements in the given `<file>` element.
+    /// Target language - The `xml:lang` of the `<target>` element, or the `target-language`
+    /// of the `<file>` element when the target does not declare its own.
     pub target_locale: Option<Locale>,
-    /// Note - The `<note>` element is used to add localization-related
-    /// comments to the XLIFF document. The content of `<note>` may be instructions
-    /// from developers about how to handle the `<source>, comments from the translator
-    /// about the translation, or any comment from anyone involved in processing the XLIFF file.
-    pub note: Option<UnitValue>,
+    /// Context groups - The `<context-group>` elements describing the context of the unit,
+    /// such as its location in the original source code.
+    pub context_groups: Vec<ContextGroup>,
+    /// Count groups - The `<count-group>` elements holding counts for the unit,
+    /// such as its number of words.
+    pub count_groups: Vec<CountGroup>,
+    /// Property groups - The `<prop-group>` elements holding tool specific properties
+    /// of the unit.
+    pub prop_groups: Vec<PropGroup>,
+    /// Notes - The `<note>` elements of the unit, in document order.
+    /// See `Note` for their content.
+    pub notes: Vec<Note>,
+    /// State - The state attribute of the `<target>`, indicating its place in the workflow.
+    /// It is only written along with a `<target>`.
+    pub state: Option<TargetState>,
+    /// State qualifier - The state-qualifier attribute of the `<target>`, describing how the
+    /// state was reached. It is only written along with a `<target>`.
+    pub state_qualifier: Option<StateQualifier>,
+    /// Phase name - The phase-name attribute of the `<target>`, the name of the `<phase>` in
+    /// which the target was produced. See `TranslationFile::target_phase`.
+    pub phase_name: Option<String>,
+    /// Approved - The approved attribute indicates whether the translation has been approved.
+    pub approved: bool,
+    /// Alternate translations - The `<alt-trans>` candidates proposed for this unit,
+    /// in document order.
+    pub alternatives: Vec<AltTrans>,
+    /// The unrecognised attributes and child nodes of the `<trans-unit>`.
+    pub extensions: Extensions,
+    /// The unrecognised attributes of the `<source>`.
+    pub source_extensions: Extensions,
+    /// The unrecognised attributes of the `<target>`.
+    pub target_extensions: Extensions,
+}
+
+impl Default for Unit {
+    fn default() -> Self {
+        Unit::new()
+    }
 }
 
 impl Unit {
@@ -57,33 +312,1213 @@ impl Unit {
         Unit {
             id: String::new(),
             translate: true,
+            resname: None,
+            restype: None,
+            data_type: None,
+            max_width: None,
+            min_width: None,
+            size_unit: None,
+            char_class: None,
             source: None,
             target: None,
+            seg_source: None,
             source_locale: None,
             target_locale: None,
+            context_groups: vec![],
+            count_groups: vec![],
+            prop_groups: vec![],
+            notes: vec![],
+            state: None,
+            state_qualifier: None,
+            phase_name: None,
+            approved: false,
+            alternatives: vec![],
+            extensions: Extensions::default(),
+            source_extensions: Extensions::default(),
+            target_extensions: Extensions::default(),
+        }
+    }
+
+    /// Returns the number of words in the plain text of the `<source>`,
+    /// words being separated by white space.
+    pub fn word_count(&self) -> u64 {
+        self.source_text()
+            .map_or(0, |text| text.split_whitespace().count() as u64)
+    }
+
+    /// Returns the number of characters in the plain text of the `<source>`,
+    /// not counting white space.
+    pub fn character_count(&self) -> u64 {
+        self.source_text().map_or(0, |text| {
+            text.chars().filter(|c| !c.is_whitespace()).count() as u64
+        })
+    }
+
+    /// Returns the width of the target measured in the declared `size_unit`.
+    ///
+    /// Only the `char` and `glyph` units, counted as Unicode scalar values, `byte`, counted in
+    /// UTF-8, and `row` can be measured. `None` is returned for other units, such as the
+    /// default `pixel`, or when the unit has no target.
+    pub fn target_width(&self) -> Option<u64> {
+        let target = self.target_text()?;
+        let width = match self.size_unit.as_deref()? {
+            "char" | "glyph" => target.chars().count(),
+            "byte" => target.len(),
+            "row" => target.lines().count(),
+            _ => return None,
+        };
+        Some(width as u64)
+    }
+
+    /// Whether the target is wider than the `max_width` of the unit.
+    ///
+    /// Targets which cannot be measured, see `target_width`, are not reported.
+    pub fn exceeds_max_width(&self) -> bool {
+        match (self.max_width, self.target_width()) {
+            (Some(max_width), Some(width)) => width > u64::from(max_width),
+            _ => false,
+        }
+    }
+
+    /// Returns the value of the first context of the given `context-type`, such as `sourcefile`
+    /// or `linenumber`, in any of the context groups of the unit.
+    pub fn context(&self, context_type: &str) -> Option<&str> {
+        self.context_groups
+            .iter()
+            .flat_map(|group| group.contexts.iter())
+            .find(|context| context.context_type == context_type)
+            .map(|context| context.value.as_str())
+    }
+
+    /// Returns the value of the first property of the given `prop-type` in any of the property
+    /// groups of the unit.
+    pub fn prop(&self, prop_type: &str) -> Option<&str> {
+        find_prop(&self.prop_groups, prop_type)
+    }
+
+    /// Returns the note written by the developer of the original document, such as the comment
+    /// Xcode writes for each unit.
+    ///
+    /// This is the first note which is either `from` a `developer` or has no author.
+    pub fn developer_note(&self) -> Option<&Note> {
+        self.notes
+            .iter()
+            .find(|note| matches!(note.from.as_deref(), None | Some("developer")))
+    }
+
+    /// Returns the segments of the `<seg-source>` along with the matching segments of the
+    /// `<target>`, in document order.
+    ///
+    /// Units without a `<seg-source>` have no segments.
+    pub fn segments(&self) -> Vec<Segment> {
+        let mut markers = vec![];
+        if let Some(seg_source) = &self.seg_source {
+            collect_segments(&seg_source.content, &mut markers);
+        }
+
+        markers
+            .into_iter()
+            .filter_map(|marker| {
+                let mid = marker.attribute("mid")?;
+                let target = self
+                    .target
+                    .as_ref()
+                    .and_then(|t| find_segment(&t.content, mid))
+                    .map(|t| UnitValue {
+                        content: t.content.clone(),
+                    });
+                Some(Segment {
+                    mid: String::from(mid),
+                    source: UnitValue {
+                        content: marker.content.clone(),
+                    },
+                    target,
+                })
+            })
+            .collect()
+    }
+
+    /// Replaces the content of the target segment identified by `mid`.
+    ///
+    /// A unit without a `<target>` is given one with the structure of its `<seg-source>` and
+    /// empty segments. A target segment missing from an existing `<target>` is appended to it.
+    /// Returns `false`, leaving the unit unchanged, when the `<seg-source>` has no such segment.
+    pub fn set_segment_target(&mut self, mid: &str, value: UnitValue) -> bool {
+        let source_marker = match self
+            .seg_source
+            .as_ref()
+            .and_then(|s| find_segment(&s.content, mid))
+        {
+            None => return false,
+            Some(marker) => marker.clone(),
+        };
+
+        if self.target.is_none() {
+            let mut target = self
+                .seg_source
+                .clone()
+                .unwrap_or(UnitValue { content: vec![] });
+            clear_segments(&mut target.content);
+            self.target = Some(target);
+        }
+
+        if let Some(target) = self.target.as_mut() {
+            match find_segment_mut(&mut target.content, mid) {
+                Some(marker) => marker.content = value.content,
+                None => {
+                    let mut marker = source_marker;
+                    marker.content = value.content;
+                    target.content.push(ContentPart::Code(marker));
+                }
+            }
+        }
+        true
+    }
+
+    /// Returns the alternate translation with the highest match quality.
+    ///
+    /// Candidates without a numeric match quality are only returned when no other candidate
+    /// has one. The first of several equal candidates is returned.
+    pub fn best_alternative(&self) -> Option<&AltTrans> {
+        let mut best: Option<&AltTrans> = None;
+        for alternative in &self.alternatives {
+            best = match best {
+                Some(b) if b.match_percent() >= alternative.match_percent() => Some(b),
+                _ => Some(alternative),
+            };
+        }
+        best
+    }
+
+    /// Returns the alternate translations with a match quality of at least `threshold` percent,
+    /// in document order.
+    pub fn alternatives_above(&self, threshold: f32) -> Vec<&AltTrans> {
+        self.alternatives
+            .iter()
+            .filter(|a| matches!(a.match_percent(), Some(quality) if quality >= threshold))
+            .collect()
+    }
+
+    /// Get the plain text of the `<source>` element in this translation `Unit`.
+    pub fn source_text(&self) -> Option<String> {
+        self.source.as_ref().map(UnitValue::plain_text)
+    }
+
+    /// Get the plain text of the `<target>` element in this translation `Unit`.
+    pub fn target_text(&self) -> Option<String> {
+        self.target.as_ref().map(UnitValue::plain_text)
+    }
+}
+
+/// Context group - The `<context-group>` element holds the `<context>` elements which describe
+/// the context of a `<trans-unit>` or `<group>`.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#context-group
+#[derive(PartialEq, Clone)]
+pub struct ContextGroup {
+    /// Name - The optional name attribute of the group.
+    pub name: Option<String>,
+    /// Purpose - The purpose attribute, such as `information`, `location` or `match`.
+    pub purpose: Option<String>,
+    /// The `<context>` elements of the group, in document order.
+    pub contexts: Vec<Context>,
+}
+
+impl ContextGroup {
+    /// Creates an empty context group.
+    pub fn new() -> Self {
+        ContextGroup {
+            name: None,
+            purpose: None,
+            contexts: vec![],
+        }
+    }
+
+    pub(crate) fn attributes(&self) -> Vec<(&str, &str)> {
+        let mut attributes = vec![];
+        if let Some(name) = &self.name {
+            attributes.push(("name", name.as_str()));
+        }
+        if let Some(purpose) = &self.purpose {
+            attributes.push(("purpose", purpose.as_str()));
+        }
+        attributes
+    }
+}
+
+impl Default for ContextGroup {
+    fn default() -> Self {
+        ContextGroup::new()
+    }
+}
+
+/// Context - The `<context>` element describes the context of a `<source>` within the original
+/// document, for example the name of the source file or a line number.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#context
+#[derive(PartialEq, Clone)]
+pub struct Context {
+    /// Context type - The context-type attribute, such as `sourcefile`, `linenumber`
+    /// or a user defined `x-` value.
+    pub context_type: String,
+    /// The content of the `<context>` element.
+    pub value: String,
+    /// Match mandatory - The match-mandatory attribute indicates that the context must match
+    /// for a translation to be reused.
+    pub match_mandatory: bool,
+}
+
+impl Context {
+    /// Creates a context of the given type.
+    pub fn new(context_type: &str, value: &str) -> Self {
+        Context {
+            context_type: String::from(context_type),
+            value: String::from(value),
+            match_mandatory: false,
+        }
+    }
+
+    pub(crate) fn attributes(&self) -> Vec<(&str, &str)> {
+        let mut attributes = vec![("context-type", self.context_type.as_str())];
+        if self.match_mandatory {
+            attributes.push(("match-mandatory", "yes"));
+        }
+        attributes
+    }
+}
+
+/// The name of the count groups filled in by `Store::compute_counts`.
+pub const COMPUTED_COUNT_GROUP: &str = "total";
+
+/// Count group - The `<count-group>` element holds the `<count>` elements of a `<header>`,
+/// `<group>` or `<trans-unit>`.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#count-group
+#[derive(PartialEq, Clone)]
+pub struct CountGroup {
+    /// Name - The name attribute, which is unique within the `<file>`.
+    pub name: String,
+    /// The `<count>` elements of the group, in document order.
+    pub counts: Vec<Count>,
+}
+
+impl CountGroup {
+    /// Creates an empty count group with the given name.
+    pub fn new(name: &str) -> Self {
+        CountGroup {
+            name: String::from(name),
+            counts: vec![],
+        }
+    }
+
+    /// Returns the first count of the given `count-type` measured in the given `unit`.
+    pub fn count(&self, count_type: &str, unit: &str) -> Option<u64> {
+        self.counts
+            .iter()
+            .find(|c| {
+                c.count_type.as_deref() == Some(count_type) && c.unit.as_deref() == Some(unit)
+            })
+            .map(|c| c.value)
+    }
+
+    pub(crate) fn attributes(&self) -> Vec<(&str, &str)> {
+        vec![("name", self.name.as_str())]
+    }
+}
+
+/// Count - The `<count>` element holds a count, such as the number of words of the source text.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#count
+#[derive(PartialEq, Clone)]
+pub struct Count {
+    /// Count type - The count-type attribute, such as `total`, `num-usages`, `repetition`
+    /// or a user defined `x-` value.
+    pub count_type: Option<String>,
+    /// Unit - The unit attribute, such as `word`, `character` or `line`.
+    pub unit: Option<String>,
+    /// Phase name - The phase-name attribute refers to the `<phase>` in which the count was made.
+    pub phase_name: Option<String>,
+    /// The counted number.
+    pub value: u64,
+}
+
+impl Count {
+    /// Creates a count of the given type, measured in the given unit.
+    pub fn new(count_type: &str, unit: &str, value: u64) -> Self {
+        Count {
+            count_type: Some(String::from(count_type)),
+            unit: Some(String::from(unit)),
+            phase_name: None,
+            value,
+        }
+    }
+
+    pub(crate) fn attributes(&self) -> Vec<(&str, &str)> {
+        let mut attributes = vec![];
+        if let Some(count_type) = &self.count_type {
+            attributes.push(("count-type", count_type.as_str()));
+        }
+        if let Some(phase_name) = &self.phase_name {
+            attributes.push(("phase-name", phase_name.as_str()));
+        }
+        if let Some(unit) = &self.unit {
+            attributes.push(("unit", unit.as_str()));
+        }
+        attributes
+    }
+}
+
+/// Replaces the count group with the given name, or adds it if missing.
+fn set_count_group(count_groups: &mut Vec<CountGroup>, group: CountGroup) {
+    match count_groups.iter_mut().find(|g| g.name == group.name) {
+        Some(existing) => *existing = group,
+        None => count_groups.push(group),
+    }
+}
+
+/// Returns a count group with the `total` number of words and characters.
+fn total_counts(name: &str, words: u64, characters: u64) -> CountGroup {
+    let mut group = CountGroup::new(name);
+    group.counts.push(Count::new("total", "word", words));
+    group
+        .counts
+        .push(Count::new("total", "character", characters));
+    group
+}
+
+/// Property group - The `<prop-group>` element holds the `<prop>` elements of a `<header>`,
+/// `<group>` or `<trans-unit>`, tool specific data which is not described by the specification.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#prop-group
+#[derive(PartialEq, Clone)]
+pub struct PropGroup {
+    /// Name - The optional name attribute of the group.
+    pub name: Option<String>,
+    /// The `<prop>` elements of the group, in document order.
+    pub props: Vec<Prop>,
+}
+
+impl PropGroup {
+    /// Creates an empty property group.
+    pub fn new() -> Self {
+        PropGroup {
+            name: None,
+            props: vec![],
+        }
+    }
+
+    /// Returns the value of the first property of the given `prop-type`.
+    pub fn prop(&self, prop_type: &str) -> Option<&str> {
+        self.props
+            .iter()
+            .find(|prop| prop.prop_type == prop_type)
+            .map(|prop| prop.value.as_str())
+    }
+
+    pub(crate) fn attributes(&self) -> Vec<(&str, &str)> {
+        let mut attributes = vec![];
+        if let Some(name) = &self.name {
+            attributes.push(("name", name.as_str()));
+        }
+        attributes
+    }
+}
+
+impl Default for PropGroup {
+    fn default() -> Self {
+        PropGroup::new()
+    }
+}
+
+/// Property - The `<prop>` element holds a tool specific value, such as a ticket number.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#prop
+#[derive(PartialEq, Clone)]
+pub struct Prop {
+    /// Property type - The prop-type attribute names the property, such as a user defined
+    /// `x-` value.
+    pub prop_type: String,
+    /// The content of the `<prop>` element.
+    pub value: String,
+    /// Language - The xml:lang attribute of the property.
+    pub lang: Option<String>,
+}
+
+impl Prop {
+    /// Creates a property of the given type.
+    pub fn new(prop_type: &str, value: &str) -> Self {
+        Prop {
+            prop_type: String::from(prop_type),
+            value: String::from(value),
+            lang: None,
+        }
+    }
+
+    pub(crate) fn attributes(&self) -> Vec<(&str, &str)> {
+        let mut attributes = vec![("prop-type", self.prop_type.as_str())];
+        if let Some(lang) = &self.lang {
+            attributes.push(("xml:lang", lang.as_str()));
+        }
+        attributes
+    }
+}
+
+/// Returns the value of the first property of the given type in any of the groups.
+fn find_prop<'a>(prop_groups: &'a [PropGroup], prop_type: &str) -> Option<&'a str> {
+    prop_groups.iter().find_map(|group| group.prop(prop_type))
+}
+
+/// Note - The `<note>` element is used to add localization-related comments to the XLIFF
+/// document. The content of `<note>` may be instructions from developers about how to handle the
+/// `<source>`, comments from the translator about the translation, or any comment from anyone
+/// involved in processing the XLIFF file.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#note
+#[derive(PartialEq, Clone)]
+pub struct Note {
+    /// The text of the note.
+    pub text: String,
+    /// Author - The from attribute indicates the author of the note.
+    pub from: Option<String>,
+    /// Priority - The priority attribute, from 1 (highest) to 10 (lowest).
+    /// Values outside of this range are ignored.
+    pub priority: Option<u8>,
+    /// Annotates - The annotates attribute indicates whether the note refers to the `<source>`,
+    /// the `<target>` or the unit in general.
+    pub annotates: Option<Annotates>,
+    /// Language - The xml:lang attribute specifies the language of the note.
+    pub lang: Option<String>,
+    /// The unrecognised attributes of the `<note>`.
+    pub extensions: Extensions,
+}
+
+impl Note {
+    /// Creates a note with the provided text.
+    pub fn new(text: &str) -> Self {
+        Note {
+            text: String::from(text),
+            from: None,
+            priority: None,
+            annotates: None,
+            lang: None,
+            extensions: Extensions::default(),
+        }
+    }
+
+    pub(crate) fn attributes(&self) -> Vec<(&str, String)> {
+        let mut attributes = vec![];
+        if let Some(lang) = &self.lang {
+            attributes.push(("xml:lang", lang.clone()));
+        }
+        if let Some(from) = &self.from {
+            attributes.push(("from", from.clone()));
+        }
+        if let Some(priority) = self.priority {
+            attributes.push(("priority", priority.to_string()));
+        }
+        if let Some(annotates) = self.annotates {
+            attributes.push(("annotates", String::from(annotates.to_str())));
+        }
+        attributes
+    }
+}
+
+impl From<&str> for Note {
+    fn from(text: &str) -> Self {
+        Note::new(text)
+    }
+}
+
+/// The values of the annotates attribute of a `<note>`.
+#[derive(PartialEq, Copy, Clone)]
+pub enum Annotates {
+    /// `source` - The note refers to the `<source>`.
+    Source,
+    /// `target` - The note refers to the `<target>`.
+    Target,
+    /// `general` - The note refers to the unit as a whole.
+    General,
+}
+
+impl Annotates {
+    pub(crate) fn from(value: &str) -> Option<Self> {
+        match value {
+            "source" => Some(Annotates::Source),
+            "target" => Some(Annotates::Target),
+            "general" => Some(Annotates::General),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_str(self) -> &'static str {
+        match self {
+            Annotates::Source => "source",
+            Annotates::Target => "target",
+            Annotates::General => "general",
+        }
+    }
+}
+
+/// Target state - The values of the state attribute of a `<target>`.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#state
+#[derive(PartialEq, Clone)]
+pub enum TargetState {
+    /// `final` - The terminating state.
+    Final,
+    /// `needs-adaptation` - Only non-textual information needs adaptation.
+    NeedsAdaptation,
+    /// `needs-l10n` - Both text and non-textual information need adaptation.
+    NeedsL10n,
+    /// `needs-review-adaptation` - Only non-textual information needs review.
+    NeedsReviewAdaptation,
+    /// `needs-review-l10n` - Both text and non-textual information need review.
+    NeedsReviewL10n,
+    /// `needs-review-translation` - Only the text needs review.
+    NeedsReviewTranslation,
+    /// `needs-translation` - The item needs to be translated.
+    NeedsTranslation,
+    /// `new` - The item is new.
+    New,
+    /// `signed-off` - Changes are reviewed and approved.
+    SignedOff,
+    /// `translated` - The item has been translated.
+    Translated,
+    /// A user defined value, which starts with `x-`.
+    Custom(String),
+}
+
+impl TargetState {
+    /// Returns the state for the provided attribute value.
+    /// Unknown values are kept as `Custom` states.
+    pub fn from(value: &str) -> Self {
+        match value {
+            "final" => TargetState::Final,
+            "needs-adaptation" => TargetState::NeedsAdaptation,
+            "needs-l10n" => TargetState::NeedsL10n,
+            "needs-review-adaptation" => TargetState::NeedsReviewAdaptation,
+            "needs-review-l10n" => TargetState::NeedsReviewL10n,
+            "needs-review-translation" => TargetState::NeedsReviewTranslation,
+            "needs-translation" => TargetState::NeedsTranslation,
+            "new" => TargetState::New,
+            "signed-off" => TargetState::SignedOff,
+            "translated" => TargetState::Translated,
+            custom => TargetState::Custom(String::from(custom)),
+        }
+    }
+
+    /// Returns the attribute value of the state.
+    pub fn as_str(&self) -> &str {
+        match self {
+            TargetState::Final => "final",
+            TargetState::NeedsAdaptation => "needs-adaptation",
+            TargetState::NeedsL10n => "needs-l10n",
+            TargetState::NeedsReviewAdaptation => "needs-review-adaptation",
+            TargetState::NeedsReviewL10n => "needs-review-l10n",
+            TargetState::NeedsReviewTranslation => "needs-review-translation",
+            TargetState::NeedsTranslation => "needs-translation",
+            TargetState::New => "new",
+            TargetState::SignedOff => "signed-off",
+            TargetState::Translated => "translated",
+            TargetState::Custom(value) => value.as_str(),
+        }
+    }
+
+    /// Whether the state is one of the `needs-review-*` states.
+    pub fn needs_review(&self) -> bool {
+        matches!(
+            self,
+            TargetState::NeedsReviewAdaptation
+                | TargetState::NeedsReviewL10n
+                | TargetState::NeedsReviewTranslation
+        )
+    }
+
+    /// The closest XLIFF 2.0 `state` of a `<segment>`, `None` for user defined states.
+    pub(crate) fn to_str_v20(&self) -> Option<&'static str> {
+        match self {
+            TargetState::New
+            | TargetState::NeedsTranslation
+            | TargetState::NeedsAdaptation
+            | TargetState::NeedsL10n => Some("initial"),
+            TargetState::Translated
+            | TargetState::NeedsReviewAdaptation
+            | TargetState::NeedsReviewL10n
+            | TargetState::NeedsReviewTranslation => Some("translated"),
+            TargetState::SignedOff => Some("reviewed"),
+            TargetState::Final => Some("final"),
+            TargetState::Custom(_) => None,
+        }
+    }
+
+    /// Returns the state for the provided XLIFF 2.0 `state` of a `<segment>`.
+    pub(crate) fn from_v20(value: &str) -> Option<Self> {
+        match value {
+            "initial" => Some(TargetState::New),
+            "translated" => Some(TargetState::Translated),
+            "reviewed" => Some(TargetState::SignedOff),
+            "final" => Some(TargetState::Final),
+            _ => None,
+        }
+    }
+}
+
+/// State qualifier - The values of the state-qualifier attribute of a `<target>`.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#state-qualifier
+#[derive(PartialEq, Clone)]
+pub enum StateQualifier {
+    /// `exact-match` - The translation is an exact match from a translation memory.
+    ExactMatch,
+    /// `fuzzy-match` - The translation is a fuzzy match from a translation memory.
+    FuzzyMatch,
+    /// `id-match` - The translation was matched by the identifier of the unit.
+    IdMatch,
+    /// `leveraged-glossary` - The translation comes from a glossary.
+    LeveragedGlossary,
+    /// `leveraged-inherited` - The translation comes from an existing translation.
+    LeveragedInherited,
+    /// `leveraged-mt` - The translation comes from machine translation.
+    LeveragedMt,
+    /// `leveraged-repository` - The translation comes from a database.
+    LeveragedRepository,
+    /// `leveraged-tm` - The translation comes from a translation memory.
+    LeveragedTm,
+    /// `mt-suggestion` - The translation is suggested by machine translation.
+    MtSuggestion,
+    /// `rejected-grammar` - The translation was rejected because of incorrect grammar.
+    RejectedGrammar,
+    /// `rejected-inaccurate` - The translation was rejected because it is incorrect.
+    RejectedInaccurate,
+    /// `rejected-length` - The translation was rejected because it is too long or too short.
+    RejectedLength,
+    /// `rejected-spelling` - The translation was rejected because of incorrect spelling.
+    RejectedSpelling,
+    /// `tm-suggestion` - The translation is suggested by a translation memory.
+    TmSuggestion,
+    /// A user defined value, which starts with `x-`.
+    Custom(String),
+}
+
+impl StateQualifier {
+    /// Returns the state qualifier for the provided attribute value.
+    /// Unknown values are kept as `Custom` qualifiers.
+    pub fn from(value: &str) -> Self {
+        match value {
+            "exact-match" => StateQualifier::ExactMatch,
+            "fuzzy-match" => StateQualifier::FuzzyMatch,
+            "id-match" => StateQualifier::IdMatch,
+            "leveraged-glossary" => StateQualifier::LeveragedGlossary,
+            "leveraged-inherited" => StateQualifier::LeveragedInherited,
+            "leveraged-mt" => StateQualifier::LeveragedMt,
+            "leveraged-repository" => StateQualifier::LeveragedRepository,
+            "leveraged-tm" => StateQualifier::LeveragedTm,
+            "mt-suggestion" => StateQualifier::MtSuggestion,
+            "rejected-grammar" => StateQualifier::RejectedGrammar,
+            "rejected-inaccurate" => StateQualifier::RejectedInaccurate,
+            "rejected-length" => StateQualifier::RejectedLength,
+            "rejected-spelling" => StateQualifier::RejectedSpelling,
+            "tm-suggestion" => StateQualifier::TmSuggestion,
+            custom => StateQualifier::Custom(String::from(custom)),
+        }
+    }
+
+    /// Returns the attribute value of the state qualifier.
+    pub fn as_str(&self) -> &str {
+        match self {
+            StateQualifier::ExactMatch => "exact-match",
+            StateQualifier::FuzzyMatch => "fuzzy-match",
+            StateQualifier::IdMatch => "id-match",
+            StateQualifier::LeveragedGlossary => "leveraged-glossary",
+            StateQualifier::LeveragedInherited => "leveraged-inherited",
+            StateQualifier::LeveragedMt => "leveraged-mt",
+            StateQualifier::LeveragedRepository => "leveraged-repository",
+            StateQualifier::LeveragedTm => "leveraged-tm",
+            StateQualifier::MtSuggestion => "mt-suggestion",
+            StateQualifier::RejectedGrammar => "rejected-grammar",
+            StateQualifier::RejectedInaccurate => "rejected-inaccurate",
+            StateQualifier::RejectedLength => "rejected-length",
+            StateQualifier::RejectedSpelling => "rejected-spelling",
+            StateQualifier::TmSuggestion => "tm-suggestion",
+            StateQualifier::Custom(value) => value.as_str(),
+        }
+    }
+}
+
+/// A sentence of a segmented translation unit, delimited by `<mrk mtype="seg">` elements.
+#[derive(PartialEq, Clone)]
+pub struct Segment {
+    /// Marker identifier - The mid attribute shared by the source and target markers.
+    pub mid: String,
+    /// The content of the segment in the `<seg-source>`.
+    pub source: UnitValue,
+    /// The content of the segment in the `<target>`, if translated.
+    pub target: Option<UnitValue>,
+}
+
+impl InlineCode {
+    /// Whether this is a `<mrk mtype="seg">` element delimiting a segment.
+    fn is_segment(&self) -> bool {
+        self.kind == InlineKind::Mrk && self.attribute("mtype") == Some("seg")
+    }
+}
+
+fn collect_segments<'a>(content: &'a [ContentPart], markers: &mut Vec<&'a InlineCode>) {
+    for part in content {
+        if let ContentPart::Code(code) = part {
+            match code.is_segment() {
+                true => markers.push(code),
+                false => collect_segments(&code.content, markers),
+            }
+        }
+    }
+}
+
+fn find_segment<'a>(content: &'a [ContentPart], mid: &str) -> Option<&'a InlineCode> {
+    let mut markers = vec![];
+    collect_segments(content, &mut markers);
+    markers
+        .into_iter()
+        .find(|marker| marker.attribute("mid") == Some(mid))
+}
+
+fn find_segment_mut<'a>(content: &'a mut [ContentPart], mid: &str) -> Option<&'a mut InlineCode> {
+    for part in content.iter_mut() {
+        if let ContentPart::Code(code) = part {
+            if code.is_segment() {
+                if code.attribute("mid") == Some(mid) {
+                    return Some(code);
+                }
+            } else if let Some(marker) = find_segment_mut(&mut code.content, mid) {
+                return Some(marker);
+            }
+        }
+    }
+    None
+}
+
+/// Returns the content with the segment markers replaced by the content they delimit.
+fn strip_segments(content: &[ContentPart]) -> Vec<ContentPart> {
+    let mut stripped = vec![];
+    for part in content {
+        match part {
+            ContentPart::Code(code) if code.is_segment() => {
+                stripped.extend(strip_segments(&code.content))
+            }
+            ContentPart::Code(code) => {
+                let mut code = code.clone();
+                code.content = strip_segments(&code.content);
+                stripped.push(ContentPart::Code(code));
+            }
+            ContentPart::Text(_) | ContentPart::Extension(_) => stripped.push(part.clone()),
+        }
+    }
+    stripped
+}
+
+fn clear_segments(content: &mut [ContentPart]) {
+    for part in content.iter_mut() {
+        if let ContentPart::Code(code) = part {
+            match code.is_segment() {
+                true => code.content.clear(),
+                false => clear_segments(&mut code.content),
+            }
+        }
+    }
+}
+
+/// Alternate translation - The `<alt-trans>` element contains a translation candidate for the
+/// `<source>` of its `<trans-unit>`, such as a match found in a translation memory.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#alttrans
+#[derive(PartialEq, Clone)]
+pub struct AltTrans {
+    /// The source text matched by the candidate, when it differs from the source of the unit.
+    pub source: Option<UnitValue>,
+    /// The segmented source matched by the candidate, see `Unit::seg_source`.
+    pub seg_source: Option<UnitValue>,
+    /// The proposed translation.
+    pub target: UnitValue,
+    /// Target language - The `xml:lang` of the `<target>`, the language of the proposed
+    /// translation.
+    pub target_locale: Option<Locale>,
+    /// State - The state attribute of the `<target>`.
+    pub state: Option<TargetState>,
+    /// Context groups - The `<context-group>` elements describing the context of the candidate.
+    pub context_groups: Vec<ContextGroup>,
+    /// Property groups - The `<prop-group>` elements holding tool specific properties
+    /// of the candidate.
+    pub prop_groups: Vec<PropGroup>,
+    /// Notes - The `<note>` elements of the candidate, in document order.
+    pub notes: Vec<Note>,
+    /// Match quality - The match-quality attribute, for example `87%`. Its format is defined
+    /// by the tool which produced the candidate.
+    pub match_quality: Option<String>,
+    /// Origin - The origin attribute specifies where the candidate comes from,
+    /// for example a translation memory.
+    pub origin: Option<String>,
+    /// Tool identifier - The tool-id attribute refers to the `<tool>` which produced the candidate.
+    pub tool_id: Option<String>,
+    /// Alternate translation type - The alttranstype attribute, such as `proposal`
+    /// (the default) or `previous-version`.
+    pub alt_trans_type: Option<String>,
+    /// The unrecognised attributes and child nodes of the `<alt-trans>`.
+    pub extensions: Extensions,
+    /// The unrecognised attributes of the `<source>`, such as its `xml:lang`.
+    pub source_extensions: Extensions,
+    /// The unrecognised attributes of the `<target>`.
+    pub target_extensions: Extensions,
+}
+
+impl AltTrans {
+    /// Creates a candidate proposing the given translation.
+    pub fn new(target: UnitValue) -> Self {
+        AltTrans {
+            source: None,
+            seg_source: None,
+            target,
+            target_locale: None,
+            state: None,
+            context_groups: vec![],
+            prop_groups: vec![],
+            notes: vec![],
+            match_quality: None,
+            origin: None,
+            tool_id: None,
+            alt_trans_type: None,
+            extensions: Extensions::default(),
+            source_extensions: Extensions::default(),
+            target_extensions: Extensions::default(),
+        }
+    }
+
+    /// Returns the match quality as a percentage, when it is a number optionally followed by `%`.
+    pub fn match_percent(&self) -> Option<f32> {
+        let quality = self.match_quality.as_deref()?.trim();
+        quality.trim_end_matches('%').trim_end().parse().ok()
+    }
+
+    pub(crate) fn attributes(&self) -> Vec<(&str, &str)> {
+        let mut attributes = vec![];
+        let values = [
+            ("match-quality", &self.match_quality),
+            ("origin", &self.origin),
+            ("tool-id", &self.tool_id),
+            ("alttranstype", &self.alt_trans_type),
+        ];
+        for (name, value) in values.iter() {
+            if let Some(value) = value {
+                attributes.push((*name, value.as_str()));
+            }
+        }
+        attributes
+    }
+}
+
+/// An element of a `<body>` or `<group>`, kept in document order.
+// units are by far the most common items, boxing them would only add an allocation each
+#[allow(clippy::large_enum_variant)]
+#[derive(PartialEq, Clone)]
+pub enum BodyItem {
+    /// A `<trans-unit>` element.
+    Unit(Unit),
+    /// A `<group>` element.
+    Group(Group),
+    /// A `<bin-unit>` element.
+    BinUnit(BinUnit),
+    /// An unrecognised element, comment or processing instruction, as XML markup.
+    /// See `Extensions`.
+    Extension(String),
+}
+
+/// Binary unit - The `<bin-unit>` element contains binary data, such as an icon or a bitmap,
+/// which may need to be localized.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#bin-unit
+#[derive(PartialEq, Clone)]
+pub struct BinUnit {
+    /// Identifier - uniquely identify the `<bin-unit>` within all
+    /// `<trans-unit>` and `<bin-unit>` elements within the same `<file>`.
+    pub id: String,
+    /// MIME type - The mime-type attribute indicates the type of the binary data.
+    pub mime_type: String,
+    /// Indicates whether the `<bin-unit>` is to be translated.
+    pub translate: bool,
+    /// Source data - The content of the `<bin-source>` element.
+    pub source: BinFile,
+    /// Target data - The content of the `<bin-target>` element, the localized binary data.
+    pub target: Option<BinFile>,
+    /// Context groups - The `<context-group>` elements describing the context of the unit.
+    pub context_groups: Vec<ContextGroup>,
+    /// Count groups - The `<count-group>` elements holding counts for the unit.
+    pub count_groups: Vec<CountGroup>,
+    /// Property groups - The `<prop-group>` elements holding tool specific properties
+    /// of the unit.
+    pub prop_groups: Vec<PropGroup>,
+    /// Notes - The `<note>` elements of the unit, in document order.
+    pub notes: Vec<Note>,
+    /// The unrecognised attributes and child nodes of the `<bin-unit>`.
+    pub extensions: Extensions,
+}
+
+impl BinUnit {
+    /// Creates a binary unit with the given identifier, type and source data.
+    pub fn new(id: &str, mime_type: &str, source: BinFile) -> Self {
+        BinUnit {
+            id: String::from(id),
+            mime_type: String::from(mime_type),
+            translate: true,
+            source,
+            target: None,
+            context_groups: vec![],
+            count_groups: vec![],
+            prop_groups: vec![],
+            notes: vec![],
+            extensions: Extensions::default(),
+        }
+    }
+
+    pub(crate) fn attributes(&self) -> Vec<(&str, &str)> {
+        vec![
+            ("id", self.id.as_str()),
+            ("mime-type", self.mime_type.as_str()),
+        ]
+    }
+}
+
+/// The data of a `<bin-source>`, `<bin-target>`, `<skl>`, `<glossary>` or `<reference>` element.
+#[derive(PartialEq, Clone)]
+pub enum BinFile {
+    /// Internal file - The `<internal-file>` element holds the data in the document.
+    Internal {
+        /// Form - The form attribute indicates the encoding of the data in the document,
+        /// such as `base64`. Data which is not valid UTF-8 text is always written as `base64`.
+        form: Option<String>,
+        /// The data, decoded when its form is `base64`.
+        data: Vec<u8>,
+    },
+    /// External file - The `<external-file>` element refers to data outside of the document.
+    External {
+        /// The href attribute, the location of the data.
+        href: String,
+    },
+}
+
+impl BinFile {
+    /// Creates an internal file holding the provided data, written in the `base64` form.
+    pub fn base64(data: Vec<u8>) -> Self {
+        BinFile::Internal {
+            form: Some(String::from(BASE64_FORM)),
+            data,
+        }
+    }
+
+    /// Returns the data held in the document, `None` for an external file.
+    pub fn data(&self) -> Option<&[u8]> {
+        match self {
+            BinFile::Internal { data, .. } => Some(data),
+            BinFile::External { .. } => None,
+        }
+    }
+
+    /// Whether the data is encoded in the `base64` form in the document.
+    pub(crate) fn is_base64(&self) -> bool {
+        matches!(self, BinFile::Internal { form: Some(form), .. } if form == BASE64_FORM)
+    }
+}
+
+/// A file provided along with the document, the content of a `<glossary>` or `<reference>`
+/// element of the `<header>`.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#glossary
+#[derive(PartialEq, Clone)]
+pub struct ReferenceFile {
+    /// The data of the file, held in the document or referred to.
+    pub file: BinFile,
+    /// Note - The optional `<note>` describing the file.
+    pub note: Option<Note>,
+}
+
+impl ReferenceFile {
+    /// Creates a reference to a file outside of the document.
+    pub fn external(href: &str) -> Self {
+        ReferenceFile {
+            file: BinFile::External {
+                href: String::from(href),
+            },
+            note: None,
+        }
+    }
+
+    /// Creates a file held in the document, written in the `base64` form.
+    pub fn internal(data: Vec<u8>) -> Self {
+        ReferenceFile {
+            file: BinFile::base64(data),
             note: None,
         }
     }
 
-    /// Get a reference to the value of the `<source>` element in this translation `Unit`.
-    pub fn source_text(&self) -> Option<&String> {
-        match self.source.as_ref() {
-            None => None,
-            Some(t) => Some(&t.text),
+    /// Creates a file held in the document from the contents of a local file.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(ReferenceFile::internal(fs::read(path)?))
+    }
+}
+
+/// The form of `<internal-file>` data encoded in base64.
+pub(crate) const BASE64_FORM: &str = "base64";
+
+/// Group - The `<group>` element specifies a set of elements that should be processed together.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#group
+#[derive(PartialEq, Clone)]
+pub struct Group {
+    /// Identifier - The optional id attribute of the `<group>`.
+    pub id: Option<String>,
+    /// Resource name - The resname attribute, the resource identifier used by the original document.
+    pub resname: Option<String>,
+    /// Resource type - The restype attribute indicates the resource type of the group.
+    pub restype: Option<String>,
+    /// Indicates whether the content of the `<group>` is to be translated.
+    /// Groups and units without a translate attribute inherit it from their parent group.
+    pub translate: bool,
+    /// Context groups - The `<context-group>` elements describing the context of the group.
+    pub context_groups: Vec<ContextGroup>,
+    /// Count groups - The `<count-group>` elements holding counts for the group.
+    pub count_groups: Vec<CountGroup>,
+    /// Property groups - The `<prop-group>` elements holding tool specific properties
+    /// of the group.
+    pub prop_groups: Vec<PropGroup>,
+    /// Localization-related comments about the group.
+    pub notes: Vec<Note>,
+    /// The translation units and nested groups, in document order.
+    pub body: Vec<BodyItem>,
+    /// The unrecognised attributes of the `<group>`. Its unrecognised child nodes are kept
+    /// in place in its `body`.
+    pub extensions: Extensions,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Group::new()
+    }
+}
+
+impl Group {
+    /// New group instance
+    pub fn new() -> Group {
+        Group {
+            id: None,
+            resname: None,
+            restype: None,
+            translate: true,
+            context_groups: vec![],
+            count_groups: vec![],
+            prop_groups: vec![],
+            notes: vec![],
+            body: vec![],
+            extensions: Extensions::default(),
+        }
+    }
+
+    /// Returns the translation units of the group and its nested groups, in document order.
+    pub fn units(&self) -> Vec<&Unit> {
+        let mut units = vec![];
+        collect_units(&self.body, &mut units);
+        units
+    }
+
+    /// Returns the translation units of the group and its nested groups for modification.
+    pub fn units_mut(&mut self) -> Vec<&mut Unit> {
+        let mut units = vec![];
+        collect_units_mut(&mut self.body, &mut units);
+        units
+    }
+
+    /// Returns the value of the first property of the given `prop-type` in any of the property
+    /// groups of the group. Properties are not inherited by nested groups and units.
+    pub fn prop(&self, prop_type: &str) -> Option<&str> {
+        find_prop(&self.prop_groups, prop_type)
+    }
+
+    /// Returns the binary units of the group and its nested groups, in document order.
+    pub fn bin_units(&self) -> Vec<&BinUnit> {
+        let mut bin_units = vec![];
+        collect_bin_units(&self.body, &mut bin_units);
+        bin_units
+    }
+
+    /// Returns the groups placed directly in this group.
+    pub fn groups(&self) -> Vec<&Group> {
+        child_groups(&self.body)
+    }
+
+    /// Returns the nested group found by following the provided path.
+    ///
+    /// See `TranslationFile::group` for how the path is matched.
+    pub fn group(&self, path: &[&str]) -> Option<&Group> {
+        find_group(&self.body, path)
+    }
+
+    pub(crate) fn attributes(&self) -> Vec<(&str, &str)> {
+        let mut attributes = vec![];
+        if let Some(id) = &self.id {
+            attributes.push(("id", id.as_str()));
+        }
+        if let Some(resname) = &self.resname {
+            attributes.push(("resname", resname.as_str()));
+        }
+        if let Some(restype) = &self.restype {
+            attributes.push(("restype", restype.as_str()));
+        }
+        attributes
+    }
+
+    /// Whether the group is known by the provided name, either its `id` or its `resname`.
+    fn is_named(&self, name: &str) -> bool {
+        self.id.as_deref() == Some(name) || self.resname.as_deref() == Some(name)
+    }
+}
+
+fn collect_units<'a>(body: &'a [BodyItem], units: &mut Vec<&'a Unit>) {
+    for item in body {
+        match item {
+            BodyItem::Unit(unit) => units.push(unit),
+            BodyItem::Group(group) => collect_units(&group.body, units),
+            BodyItem::BinUnit(_) | BodyItem::Extension(_) => (),
         }
     }
+}
 
-    /// Get a reference to the value of the `<target>` element in this translation `Unit`.
-    pub fn target_text(&self) -> Option<&String> {
-        match self.target.as_ref() {
-            None => None,
-            Some(t) => Some(&t.text),
+fn collect_units_mut<'a>(body: &'a mut [BodyItem], units: &mut Vec<&'a mut Unit>) {
+    for item in body {
+        match item {
+            BodyItem::Unit(unit) => units.push(unit),
+            BodyItem::Group(group) => collect_units_mut(&mut group.body, units),
+            BodyItem::BinUnit(_) | BodyItem::Extension(_) => (),
         }
     }
 }
 
+fn child_groups(body: &[BodyItem]) -> Vec<&Group> {
+    body.iter()
+        .filter_map(|item| match item {
+            BodyItem::Group(group) => Some(group),
+            _ => None,
+        })
+        .collect()
+}
+
+fn collect_bin_units<'a>(body: &'a [BodyItem], bin_units: &mut Vec<&'a BinUnit>) {
+    for item in body {
+        match item {
+            BodyItem::BinUnit(bin_unit) => bin_units.push(bin_unit),
+            BodyItem::Group(group) => collect_bin_units(&group.body, bin_units),
+            BodyItem::Unit(_) | BodyItem::Extension(_) => (),
+        }
+    }
+}
+
+fn find_group<'a>(body: &'a [BodyItem], path: &[&str]) -> Option<&'a Group> {
+    let (name, rest) = path.split_first()?;
+    let group = child_groups(body).into_iter().find(|g| g.is_named(name))?;
+    match rest.is_empty() {
+        true => Some(group),
+        false => find_group(&group.body, rest),
+    }
+}
+
 /// Language definition
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub struct Locale {
     /// A language code as described in the [RFC 4646], the successor to [RFC 3066].
     /// The values for this attribute follow the same rules as the values for xml:lang.
@@ -92,7 +1527,7 @@ pub struct Locale {
 }
 
 impl Locale {
-    fn new(identifier: String) -> Locale {
+    pub(crate) fn new(identifier: String) -> Locale {
         Locale {
             identifier: identifier.to_lowercase(),
         }
@@ -101,8 +1536,11 @@ impl Locale {
 
 /// File - The `<file>` element corresponds to a single extracted original document.
 /// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#file
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub struct TranslationFile {
+    /// File identifier - The id attribute of an XLIFF 2.0 `<file>` element.
+    /// XLIFF 1.2 files are identified by their `address` alone.
+    pub id: Option<String>,
     /// Original file - The original attribute specifies the name of
     /// the original file from which the contents of a `<file>` element has been extracted.
     pub address: String,
@@ -112,31 +1550,167 @@ pub struct TranslationFile {
     /// Target language - The language for the `<target>` elements
     /// in the given `<file>` element.
     pub target_locale: Option<Locale>,
-    /// Translation units - A collection of translation units for the given file
-    pub units: Vec<Unit>,
+    /// Body - The translation units and groups of the given file, in document order.
+    pub body: Vec<BodyItem>,
     /// Data type - The datatype attribute specifies the kind of text contained in the element.
     pub data_type: String,
 
     /// File header
     pub header: Option<Header>,
+    /// The unrecognised attributes and child nodes of the `<file>`.
+    pub extensions: Extensions,
 }
 
 impl TranslationFile {
-    fn new(address: &str) -> TranslationFile {
+    pub(crate) fn new(address: &str) -> TranslationFile {
         TranslationFile {
+            id: None,
             address: String::from(address),
             source_locale: None,
             target_locale: None,
-            units: vec![],
+            body: vec![],
             data_type: String::new(),
             header: None,
+            extensions: Extensions::default(),
         }
     }
 
+    /// Returns the translation units of the file, including those placed in groups,
+    /// in document order.
+    pub fn units(&self) -> Vec<&Unit> {
+        let mut units = vec![];
+        collect_units(&self.body, &mut units);
+        units
+    }
+
+    /// Returns the translation units of the file, including those placed in groups,
+    /// for modification.
+    pub fn units_mut(&mut self) -> Vec<&mut Unit> {
+        let mut units = vec![];
+        collect_units_mut(&mut self.body, &mut units);
+        units
+    }
+
+    /// Returns the binary units of the file, including those placed in groups,
+    /// in document order.
+    pub fn bin_units(&self) -> Vec<&BinUnit> {
+        let mut bin_units = vec![];
+        collect_bin_units(&self.body, &mut bin_units);
+        bin_units
+    }
+
+    /// Returns the number of words in the source of the translatable units of the file.
+    pub fn word_count(&self) -> u64 {
+        self.units()
+            .iter()
+            .filter(|u| u.translate)
+            .map(|u| u.word_count())
+            .sum()
+    }
+
+    /// Returns the number of characters, not counting white space, in the source of the
+    /// translatable units of the file.
+    pub fn character_count(&self) -> u64 {
+        self.units()
+            .iter()
+            .filter(|u| u.translate)
+            .map(|u| u.character_count())
+            .sum()
+    }
+
+    /// Fills in the word and character counts of the file and of each of its translatable units.
+    ///
+    /// See `Store::compute_counts`.
+    pub fn compute_counts(&mut self) {
+        let (words, characters) = (self.word_count(), self.character_count());
+        let header = self.header.get_or_insert_with(Header::new);
+        set_count_group(
+            &mut header.count_groups,
+            total_counts(COMPUTED_COUNT_GROUP, words, characters),
+        );
+
+        for unit in self.units_mut().into_iter().filter(|u| u.translate) {
+            let name = format!("{}-{}", COMPUTED_COUNT_GROUP, unit.id);
+            let group = total_counts(&name, unit.word_count(), unit.character_count());
+            set_count_group(&mut unit.count_groups, group);
+        }
+    }
+
+    /// Returns the value of the first property of the given `prop-type` in the property groups
+    /// of the `<header>` of the file.
+    pub fn prop(&self, prop_type: &str) -> Option<&str> {
+        self.header
+            .as_ref()
+            .and_then(|header| find_prop(&header.prop_groups, prop_type))
+    }
+
+    /// Returns the phase of the `<header>` with the given name.
+    pub fn phase(&self, phase_name: &str) -> Option<&Phase> {
+        self.header
+            .as_ref()
+            .and_then(|header| header.phases.iter().find(|p| p.phase_name == phase_name))
+    }
+
+    /// Returns the phase in which the target of the unit was produced, as referred to by
+    /// its `phase-name`.
+    pub fn target_phase(&self, unit: &Unit) -> Option<&Phase> {
+        unit.phase_name
+            .as_deref()
+            .and_then(|phase_name| self.phase(phase_name))
+    }
+
+    /// Appends a phase to the process history of the file, creating its `<header>` if needed.
+    ///
+    /// A phase with the same name is replaced, as phase names must be unique within a file.
+    pub fn add_phase(&mut self, phase: Phase) {
+        let phases = &mut self.header.get_or_insert_with(Header::new).phases;
+        phases.retain(|p| p.phase_name != phase.phase_name);
+        phases.push(phase);
+    }
+
+    /// Adds a glossary to the `<header>` of the file, creating the header if needed.
+    pub fn add_glossary(&mut self, glossary: ReferenceFile) {
+        self.header
+            .get_or_insert_with(Header::new)
+            .glossaries
+            .push(glossary);
+    }
+
+    /// Adds the contents of a local glossary file to the `<header>` of the file,
+    /// encoded in the `base64` form.
+    ///
+    /// # Errors
+    /// Returns an error if the glossary file cannot be read.
+    pub fn attach_glossary<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        self.add_glossary(ReferenceFile::from_path(path)?);
+        Ok(())
+    }
+
+    /// Adds reference material to the `<header>` of the file, creating the header if needed.
+    pub fn add_reference(&mut self, reference: ReferenceFile) {
+        self.header
+            .get_or_insert_with(Header::new)
+            .references
+            .push(reference);
+    }
+
+    /// Returns the groups placed directly in the `<body>` of the file.
+    pub fn groups(&self) -> Vec<&Group> {
+        child_groups(&self.body)
+    }
+
+    /// Returns the group found by following the provided path from the `<body>` of the file.
+    ///
+    /// Each element of the path is matched against the `id` or the `resname` of a group,
+    /// starting with the groups placed directly in the body.
+    pub fn group(&self, path: &[&str]) -> Option<&Group> {
+        find_group(&self.body, path)
+    }
+
     pub(crate) fn attributes(&self) -> Vec<(&str, &str)> {
         let mut file_attributes: Vec<(&str, &str)> = vec![
-            ("original", &self.address.as_str()),
-            ("datatype", &self.data_type.as_str()),
+            ("original", self.address.as_str()),
+            ("datatype", self.data_type.as_str()),
         ];
 
         if let Some(s_locale) = &self.source_locale {
@@ -151,10 +1725,80 @@ impl TranslationFile {
     }
 }
 
+/// Phase - The `<phase>` element describes a step of the process the document went through,
+/// such as its translation or review.
+/// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#phase
+#[derive(PartialEq, Clone)]
+pub struct Phase {
+    /// Phase name - The phase-name attribute identifies the phase within the `<file>`.
+    pub phase_name: String,
+    /// Process name - The process-name attribute, such as `translation` or `review`.
+    pub process_name: String,
+    /// Company name - The company-name attribute.
+    pub company_name: Option<String>,
+    /// Tool identifier - The tool-id attribute refers to a `<tool>` of the `<header>`.
+    pub tool_id: Option<String>,
+    /// Date - The date attribute, in the ISO 8601 format.
+    pub date: Option<String>,
+    /// Job identifier - The job-id attribute.
+    pub job_id: Option<String>,
+    /// Contact name - The contact-name attribute.
+    pub contact_name: Option<String>,
+    /// Contact email - The contact-email attribute.
+    pub contact_email: Option<String>,
+    /// Contact phone - The contact-phone attribute.
+    pub contact_phone: Option<String>,
+    /// Notes - The `<note>` elements of the phase, in document order.
+    pub notes: Vec<Note>,
+    /// The unrecognised attributes and child nodes of the `<phase>`.
+    pub extensions: Extensions,
+}
+
+impl Phase {
+    /// Creates a phase with the given name and process.
+    pub fn new(phase_name: &str, process_name: &str) -> Self {
+        Phase {
+            phase_name: String::from(phase_name),
+            process_name: String::from(process_name),
+            company_name: None,
+            tool_id: None,
+            date: None,
+            job_id: None,
+            contact_name: None,
+            contact_email: None,
+            contact_phone: None,
+            notes: vec![],
+            extensions: Extensions::default(),
+        }
+    }
+
+    pub(crate) fn attributes(&self) -> Vec<(&str, &str)> {
+        let mut attributes = vec![
+            ("phase-name", self.phase_name.as_str()),
+            ("process-name", self.process_name.as_str()),
+        ];
+        let optional = [
+            ("company-name", &self.company_name),
+            ("tool-id", &self.tool_id),
+            ("date", &self.date),
+            ("job-id", &self.job_id),
+            ("contact-name", &self.contact_name),
+            ("contact-email", &self.contact_email),
+            ("contact-phone", &self.contact_phone),
+        ];
+        for (name, value) in optional.iter() {
+            if let Some(value) = value {
+                attributes.push((*name, value.as_str()));
+            }
+        }
+        attributes
+    }
+}
+
 /// Tool - The `<tool>` element describes the tool that has been used
 /// to execute a given task in the document.
 /// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#tool_elem
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub struct Tool {
     /// Tool identifier - The tool-id attribute allows unique identification of a `<tool>` element.
     /// It is also used in other elements in the file to refer to the given `<tool>` element.
@@ -165,42 +1809,77 @@ pub struct Tool {
     pub version: Option<String>,
     /// Tool company - The tool-company attribute specifies the company from which a tool originates.
     pub company: Option<String>,
+    /// The unrecognised attributes and child nodes of the `<tool>`, such as the `build-num`
+    /// written by Xcode.
+    pub extensions: Extensions,
 }
 
 impl Tool {
-    fn new(id: String, name: String) -> Self {
+    pub(crate) fn new(id: String, name: String) -> Self {
         Tool {
             id,
             name,
             version: None,
             company: None,
+            extensions: Extensions::default(),
         }
     }
 
     pub(crate) fn attributes(&self) -> Vec<(&str, &str)> {
-        vec![
+        let mut attributes = vec![
             ("tool-id", self.id.as_str()),
             ("tool-name", self.name.as_str()),
-        ]
+        ];
+        if let Some(version) = &self.version {
+            attributes.push(("tool-version", version.as_str()));
+        }
+        if let Some(company) = &self.company {
+            attributes.push(("tool-company", company.as_str()));
+        }
+        attributes
     }
 }
 
 /// File header - The `<header>` element contains metadata relating to the `<file>` element.
 /// http://docs.oasis-open.org/xliff/v1.2/os/xliff-core.html#header
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub struct Header {
+    /// Skeleton - The `<skl>` element holds the original document with the translatable text
+    /// replaced by placeholders. See `merge::merge_file` to rebuild the translated document.
+    pub skeleton: Option<BinFile>,
+    /// Phases - The `<phase>` elements of the `<phase-group>`, the process history of the
+    /// document in document order.
+    pub phases: Vec<Phase>,
+    /// Glossaries - The `<glossary>` elements, glossaries to be used for the translation.
+    pub glossaries: Vec<ReferenceFile>,
+    /// References - The `<reference>` elements, non-translatable reference material.
+    pub references: Vec<ReferenceFile>,
     /// Tools used within this document
     pub tools: Vec<Tool>,
     ///Localization-related comments to the XLIFF document
-    pub notes: Vec<UnitValue>,
+    pub notes: Vec<Note>,
+    /// Count groups - The `<count-group>` elements holding counts for the whole file.
+    pub count_groups: Vec<CountGroup>,
+    /// Property groups - The `<prop-group>` elements holding tool specific properties
+    /// of the whole file.
+    pub prop_groups: Vec<PropGroup>,
+    /// The unrecognised attributes and child nodes of the `<header>`.
+    pub extensions: Extensions,
 }
 
 impl Header {
     /// Returns an empty header instance
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Header {
+            skeleton: None,
+            phases: vec![],
+            glossaries: vec![],
+            references: vec![],
             tools: vec![],
             notes: vec![],
+            count_groups: vec![],
+            prop_groups: vec![],
+            extensions: Extensions::default(),
         }
     }
 }
@@ -209,16 +1888,38 @@ impl Header {
 pub struct Store {
     /// A collection of file groups
     pub groups: Vec<TranslationFile>,
+    /// The unrecognised attributes and child nodes of the root `<xliff>` element, such as the
+    /// declarations of vendor extension namespaces.
+    pub extensions: Extensions,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Store::new()
+    }
 }
 
 impl Store {
     /// Returns an empty translation store instance
     pub fn new() -> Store {
-        Store { groups: vec![] }
+        Store {
+            groups: vec![],
+            extensions: Extensions::default(),
+        }
     }
 
     /// Configures the store with the provided translation contents
     ///
+    /// Both XLIFF 1.2 and XLIFF 2.0 documents are supported. The version is determined by the
+    /// `version` attribute of the root `<xliff>` element, or by its namespace if the attribute
+    /// is missing. XLIFF 2.0 segments are joined into the `source` and `target` of their unit.
+    ///
+    /// # Errors
+    /// Returns an error if the contents cannot be read, are not well-formed XML, contain
+    /// invalid UTF-8 or violate the structure of an XLIFF document. The error reports the line,
+    /// column and byte offset at which the problem was found. Any files and units read before
+    /// the error occurred remain in the store.
+    ///
     /// # Example
     /// ```no-run
     /// let mut file = File::open("translation.xliff").expect("Failed to open the file");
@@ -227,225 +1928,199 @@ impl Store {
     /// file.read_to_end(&mut buffer).expect("failed to read file");
     ///
     /// let mut sut: xliff::store::Store = Store::new();
-    /// sut.load(buffer.iter().as_slice());
+    /// sut.load(buffer.iter().as_slice())?;
     /// ```
-    pub fn load<R: BufRead>(&mut self, r: R) {
-        let mut buf = Vec::new();
-        let mut r = Reader::from_reader(r);
-
-        let mut tags: Vec<TagCtx> = vec![];
-
-        loop {
-            match r.read_event(&mut buf).unwrap() {
-                Start(ref e) => {
-                    if let Some(tag) = TagCtx::from(e.name()) {
-                        Store::open_tag(&mut tags, tag);
-                        match tag {
-                            TagCtx::File => self.handle_file(e),
-                            TagCtx::Unit => self.handle_trans_unit(e),
-                            TagCtx::Header => self.handle_file_header(e),
-                            TagCtx::Tool => self.handle_header_tool(e),
-                            _ => (),
-                        }
-                    }
-                }
-                Empty(ref e) => {
-                    if let Some(tag) = TagCtx::from(e.name()) {
-                        Store::open_tag(&mut tags, tag);
-                        match tag {
-                            TagCtx::Tool => self.handle_header_tool(e),
-                            _ => (),
-                        }
-                        Store::close_tag(&mut tags, tag);
-                    }
-                }
-                End(ref e) => {
-                    if let Some(tag) = TagCtx::from(e.name()) {
-                        Store::close_tag(&mut tags, tag);
-                    }
-                }
-                Text(e) => match tags.last() {
-                    None => (),
-                    Some(tag) => match tag {
-                        TagCtx::Source => self.add_unit_source(e.unescape_and_decode(&r).unwrap()),
-                        TagCtx::Target => self.add_unit_target(e.unescape_and_decode(&r).unwrap()),
-                        TagCtx::Note => {
-                            let count = tags.len();
-                            if count >= 2 {
-                                match &tags[count - 2] {
-                                    TagCtx::Header => {
-                                        self.add_header_note(e.unescape_and_decode(&r).unwrap())
-                                    }
-                                    TagCtx::Unit => {
-                                        self.add_unit_note(e.unescape_and_decode(&r).unwrap())
-                                    }
-                                    _ => (),
-                                }
-                            }
-                        }
-                        _ => (),
-                    },
-                },
-                Eof => break,
-                _ => (),
-            }
-            buf.clear();
-        }
+    pub fn load<R: BufRead>(&mut self, r: R) -> Result<(), XliffError> {
+        readers::read(self, r)
     }
 
-    fn add_unit_source(&mut self, text: String) {
-        match self.groups.last_mut().unwrap().units.last_mut() {
-            None => (),
-            Some(unit) => unit.source = Some(UnitValue { text }),
+    /// Fills in the word and character counts of each file and of its translatable units.
+    ///
+    /// The source text of the units is counted, words being separated by white space and
+    /// characters excluding white space. Units which are not to be translated are skipped.
+    /// Each count is written as `<count count-type="total" unit="word">` and
+    /// `<count count-type="total" unit="character">` elements in a `<count-group>`.
+    /// The group of a file is named `total` and placed in its `<header>`, the group of a unit
+    /// is named `total-` followed by the unit identifier, as names must be unique within a file.
+    /// Existing groups with these names are replaced.
+    pub fn compute_counts(&mut self) {
+        for file in self.groups.iter_mut() {
+            file.compute_counts();
         }
     }
 
-    fn add_unit_target(&mut self, text: String) {
-        match self.groups.last_mut().unwrap().units.last_mut() {
-            None => (),
-            Some(unit) => unit.target = Some(UnitValue { text }),
-        }
+    /// Returns the translation units matching the provided predicate, in document order.
+    ///
+    /// The search can be limited to a single file by providing its `address` as `domain`.
+    pub fn units_where<P>(&self, domain: Option<&str>, predicate: P) -> Vec<&Unit>
+    where
+        P: Fn(&Unit) -> bool,
+    {
+        self.groups
+            .iter()
+            .filter(|file| domain.map_or(true, |address| file.address == address))
+            .flat_map(|file| file.units())
+            .filter(|unit| predicate(unit))
+            .collect()
     }
 
-    fn add_unit_note(&mut self, text: String) {
-        match self.groups.last_mut().unwrap().units.last_mut() {
-            None => (),
-            Some(unit) => unit.note = Some(UnitValue { text }),
-        }
+    /// Returns the translation units in the provided state.
+    pub fn units_in_state(&self, domain: Option<&str>, state: &TargetState) -> Vec<&Unit> {
+        self.units_where(domain, |unit| unit.state.as_ref() == Some(state))
     }
 
-    fn add_header_note(&mut self, text: String) {
-        match self.groups.last_mut().unwrap().header.as_mut() {
-            None => (),
-            Some(header) => header.notes.push(UnitValue { text }),
-        }
+    /// Returns the translation units with a target in one of the `needs-review-*` states.
+    pub fn units_needing_review(&self, domain: Option<&str>) -> Vec<&Unit> {
+        self.units_where(domain, |unit| {
+            unit.state.as_ref().map_or(false, TargetState::needs_review)
+        })
     }
 
-    fn handle_trans_unit(&mut self, e: &BytesStart) {
-        let mut unit = Unit::new();
+    /// Returns the translation units which have not been approved.
+    pub fn unapproved_units(&self, domain: Option<&str>) -> Vec<&Unit> {
+        self.units_where(domain, |unit| !unit.approved)
+    }
 
-        e.attributes().for_each(|a| {
-            let attr = a.unwrap();
-            match attr.key {
-                b"id" => {
-                    unit.id = String::from_utf8(attr.value.into_owned()).unwrap();
-                }
-                b"translate" => {
-                    unit.translate =
-                        String::from_utf8(attr.value.into_owned()).unwrap() != false.to_string();
-                }
-                _ => (),
-            }
-        });
-
-        match self.groups.last_mut() {
-            None => panic!("missing group"),
-            Some(group) => {
-                unit.source_locale = Some(Locale::new(
-                    group.source_locale.as_ref().unwrap().identifier.clone(),
-                ));
-                unit.target_locale = Some(Locale::new(
-                    group.target_locale.as_ref().unwrap().identifier.clone(),
-                ));
-                group.units.push(unit);
-            }
-        }
+    /// Returns the translation units with a target wider than their `maxwidth`, measured in
+    /// their declared `size-unit`. See `Unit::target_width` for the units which are measured.
+    pub fn units_exceeding_max_width(&self, domain: Option<&str>) -> Vec<&Unit> {
+        self.units_where(domain, Unit::exceeds_max_width)
+    }
+
+    /// Returns the translation units with a target in a language other than the
+    /// `target-language` of their file.
+    pub fn units_with_mismatched_target_language(&self, domain: Option<&str>) -> Vec<&Unit> {
+        self.groups
+            .iter()
+            .filter(|file| domain.map_or(true, |address| file.address == address))
+            .flat_map(|file| {
+                file.units().into_iter().filter(move |unit| {
+                    unit.target.is_some() && unit.target_locale != file.target_locale
+                })
+            })
+            .collect()
     }
 
-    fn handle_file(&mut self, e: &BytesStart) -> () {
-        let mut file = TranslationFile::new("");
+    /// Returns the translation units with a property of the given `prop-type` and value,
+    /// such as the units linked to a ticket by an `x-jira` property.
+    pub fn units_with_prop(
+        &self,
+        domain: Option<&str>,
+        prop_type: &str,
+        value: &str,
+    ) -> Vec<&Unit> {
+        self.units_where(domain, |unit| {
+            unit.prop_groups
+                .iter()
+                .flat_map(|group| group.props.iter())
+                .any(|prop| prop.prop_type == prop_type && prop.value == value)
+        })
+    }
 
-        e.attributes().for_each(|a| {
-            let attr = a.unwrap();
-            match attr.key {
-                b"original" => {
-                    file.address = String::from_utf8(attr.value.into_owned()).unwrap();
-                }
-                b"source-language" => {
-                    file.source_locale = Some(Locale::new(
-                        String::from_utf8(attr.value.into_owned()).unwrap(),
-                    ))
-                }
-                b"target-language" => {
-                    file.target_locale = Some(Locale::new(
-                        String::from_utf8(attr.value.into_owned()).unwrap(),
-                    ))
+    /// Returns a copy of the store translating in the opposite direction, for back-translation
+    /// or to start a new language pair from existing translations.
+    ///
+    /// The `source-language` and `target-language` of each file are exchanged, and so are the
+    /// `<source>` and `<target>` of each unit along with their inline codes and unrecognised
+    /// attributes. Notes annotating the source now annotate the target and the other way round.
+    /// The workflow state, approval and alternate translations describe the former target, so
+    /// they are not kept. Counts describe the former source, so they are not kept either,
+    /// see `compute_counts` to count the new source. When the former target is segmented,
+    /// it becomes the `<seg-source>` and the `<source>` holds its text without the segment
+    /// markers. Units without a target are handled according to `untranslated`.
+    pub fn reversed(&self, untranslated: UntranslatedUnits) -> Store {
+        let groups = self
+            .groups
+            .iter()
+            .map(|file| {
+                let mut reversed = file.clone();
+                std::mem::swap(&mut reversed.source_locale, &mut reversed.target_locale);
+                if let Some(header) = reversed.header.as_mut() {
+                    header.count_groups.clear();
                 }
-                b"datatype" => file.data_type = String::from_utf8(attr.value.into_owned()).unwrap(),
-                _ => (),
-            }
-        });
-        self.groups.push(file);
-    }
-
-    fn handle_header_tool(&mut self, e: &BytesStart) -> () {
-        match self.groups.last_mut().unwrap().header.as_mut() {
-            None => (),
-            Some(header) => {
-                let mut id = String::new();
-                let mut name = String::new();
-
-                e.attributes().for_each(|a| {
-                    let attr = a.unwrap();
-                    match attr.key {
-                        b"tool-id" => {
-                            id = String::from_utf8(attr.value.into_owned()).unwrap();
-                        }
-                        b"tool-name" => {
-                            name = String::from_utf8(attr.value.into_owned()).unwrap();
-                        }
-                        _ => (),
-                    }
-                });
-
-                let mut tool = Tool::new(id, name);
-
-                e.attributes().for_each(|a| {
-                    let attr = a.unwrap();
-                    match attr.key {
-                        b"tool-version" => {
-                            tool.version =
-                                Some(String::from_utf8(attr.value.into_owned()).unwrap());
-                        }
-                        b"tool-company" => {
-                            tool.company =
-                                Some(String::from_utf8(attr.value.into_owned()).unwrap());
-                        }
-                        _ => (),
-                    }
-                });
+                reversed.body = reverse_body(&file.body, untranslated);
+                reversed
+            })
+            .collect();
 
-                header.tools.push(tool);
-            }
+        Store {
+            groups,
+            extensions: self.extensions.clone(),
         }
     }
+}
 
-    fn handle_file_header(&mut self, _e: &BytesStart) -> () {
-        match self.groups.last_mut() {
-            None => (),
-            Some(file) => file.header = Some(Header::new()),
-        }
-    }
+/// How `Store::reversed` handles the translation units which have no `<target>`.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum UntranslatedUnits {
+    /// The units are left out of the reversed store.
+    Drop,
+    /// The units are kept with an empty `<source>`, their former source becoming the target.
+    KeepEmpty,
+}
+
+fn reverse_body(body: &[BodyItem], untranslated: UntranslatedUnits) -> Vec<BodyItem> {
+    body.iter()
+        .filter_map(|item| match item {
+            BodyItem::Unit(unit) => reverse_unit(unit, untranslated).map(BodyItem::Unit),
+            BodyItem::Group(group) => {
+                let mut reversed = group.clone();
+                reversed.count_groups.clear();
+                reversed.body = reverse_body(&group.body, untranslated);
+                Some(BodyItem::Group(reversed))
+            }
+            _ => Some(item.clone()),
+        })
+        .collect()
+}
 
-    fn open_tag(tags: &mut Vec<TagCtx>, open_tag: TagCtx) -> () {
-        tags.push(open_tag)
+fn reverse_unit(unit: &Unit, untranslated: UntranslatedUnits) -> Option<Unit> {
+    if unit.target.is_none() && untranslated == UntranslatedUnits::Drop {
+        return None;
     }
 
-    fn close_tag(tags: &mut Vec<TagCtx>, close_tag: TagCtx) -> () {
-        let mut tag_closed = false;
-        while !tag_closed {
-            match tags.pop() {
-                None => {
-                    tag_closed = true;
-                }
-                Some(tag) => {
-                    if tag == close_tag {
-                        tag_closed = true;
-                    }
-                }
-            }
+    let mut reversed = unit.clone();
+    let mut markers = vec![];
+    if let Some(target) = &unit.target {
+        collect_segments(&target.content, &mut markers);
+    }
+    match markers.is_empty() {
+        true => {
+            reversed.source = Some(unit.target.clone().unwrap_or(UnitValue { content: vec![] }));
+            reversed.seg_source = None;
+            reversed.target = unit.source.clone();
+        }
+        false => {
+            reversed.source = unit.target.as_ref().map(|target| UnitValue {
+                content: strip_segments(&target.content),
+            });
+            reversed.seg_source = unit.target.clone();
+            reversed.target = unit.seg_source.clone().or_else(|| unit.source.clone());
         }
     }
+    reversed.count_groups.clear();
+    reversed.source_locale = unit.target_locale.clone();
+    reversed.target_locale = unit.source_locale.clone();
+    reversed.state = None;
+    reversed.state_qualifier = None;
+    reversed.phase_name = None;
+    reversed.approved = false;
+    reversed.alternatives = vec![];
+    // the language of the new target is held by `target_locale`
+    reversed.source_extensions = unit.target_extensions.clone();
+    reversed.target_extensions = unit.source_extensions.clone();
+    reversed
+        .target_extensions
+        .attributes
+        .retain(|(name, _)| name != "xml:lang");
+    for note in reversed.notes.iter_mut() {
+        note.annotates = match note.annotates {
+            Some(Annotates::Source) => Some(Annotates::Target),
+            Some(Annotates::Target) => Some(Annotates::Source),
+            annotates => annotates,
+        };
+    }
+    Some(reversed)
 }
 
 /// The XML tag in which the current operation is taking place
@@ -460,6 +2135,33 @@ pub(crate) enum TagCtx {
     Note,
     Unit,
     Xliff,
+    Segment,
+    Ignorable,
+    Notes,
+    Metadata,
+    MetaGroup,
+    Meta,
+    Group,
+    AltTrans,
+    SegSource,
+    ContextGroup,
+    Context,
+    CountGroup,
+    Count,
+    PropGroup,
+    Prop,
+    BinUnit,
+    BinSource,
+    BinTarget,
+    Skl,
+    PhaseGroup,
+    Phase,
+    Glossary,
+    Reference,
+    InternalFile,
+    ExternalFile,
+    OriginalData,
+    Data,
 }
 
 impl TagCtx {
@@ -474,12 +2176,59 @@ impl TagCtx {
             b"target" => Some(TagCtx::Target),
             b"note" => Some(TagCtx::Note),
             b"trans-unit" => Some(TagCtx::Unit),
+            b"group" => Some(TagCtx::Group),
+            b"alt-trans" => Some(TagCtx::AltTrans),
+            b"seg-source" => Some(TagCtx::SegSource),
+            b"context-group" => Some(TagCtx::ContextGroup),
+            b"context" => Some(TagCtx::Context),
+            b"count-group" => Some(TagCtx::CountGroup),
+            b"count" => Some(TagCtx::Count),
+            b"prop-group" => Some(TagCtx::PropGroup),
+            b"prop" => Some(TagCtx::Prop),
+            b"bin-unit" => Some(TagCtx::BinUnit),
+            b"bin-source" => Some(TagCtx::BinSource),
+            b"bin-target" => Some(TagCtx::BinTarget),
+            b"skl" => Some(TagCtx::Skl),
+            b"phase-group" => Some(TagCtx::PhaseGroup),
+            b"phase" => Some(TagCtx::Phase),
+            b"glossary" => Some(TagCtx::Glossary),
+            b"reference" => Some(TagCtx::Reference),
+            b"internal-file" => Some(TagCtx::InternalFile),
+            b"external-file" => Some(TagCtx::ExternalFile),
             _ => None,
         }
     }
 
-    pub(crate) fn to_str(&self) -> &str {
-        match &self {
+    pub(crate) fn from_v20(name: &[u8]) -> Option<Self> {
+        match name {
+            b"xliff" => Some(TagCtx::Xliff),
+            b"file" => Some(TagCtx::File),
+            b"unit" => Some(TagCtx::Unit),
+            b"segment" => Some(TagCtx::Segment),
+            b"ignorable" => Some(TagCtx::Ignorable),
+            b"source" => Some(TagCtx::Source),
+            b"target" => Some(TagCtx::Target),
+            b"notes" => Some(TagCtx::Notes),
+            b"note" => Some(TagCtx::Note),
+            b"group" => Some(TagCtx::Group),
+            b"originalData" => Some(TagCtx::OriginalData),
+            b"data" => Some(TagCtx::Data),
+            _ => None,
+        }
+    }
+
+    /// Elements of the XLIFF 2.0 Metadata module, by local name.
+    pub(crate) fn from_metadata(name: &[u8]) -> Option<Self> {
+        match name {
+            b"metadata" => Some(TagCtx::Metadata),
+            b"metaGroup" => Some(TagCtx::MetaGroup),
+            b"meta" => Some(TagCtx::Meta),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_str(self) -> &'static str {
+        match self {
             TagCtx::File => "file",
             TagCtx::Header => "header",
             TagCtx::Tool => "tool",
@@ -489,6 +2238,40 @@ impl TagCtx {
             TagCtx::Note => "note",
             TagCtx::Unit => "trans-unit",
             TagCtx::Xliff => "xliff",
+            TagCtx::Segment => "segment",
+            TagCtx::Ignorable => "ignorable",
+            TagCtx::Notes => "notes",
+            TagCtx::Metadata => "mda:metadata",
+            TagCtx::MetaGroup => "mda:metaGroup",
+            TagCtx::Meta => "mda:meta",
+            TagCtx::Group => "group",
+            TagCtx::AltTrans => "alt-trans",
+            TagCtx::SegSource => "seg-source",
+            TagCtx::ContextGroup => "context-group",
+            TagCtx::Context => "context",
+            TagCtx::CountGroup => "count-group",
+            TagCtx::Count => "count",
+            TagCtx::PropGroup => "prop-group",
+            TagCtx::Prop => "prop",
+            TagCtx::BinUnit => "bin-unit",
+            TagCtx::BinSource => "bin-source",
+            TagCtx::BinTarget => "bin-target",
+            TagCtx::Skl => "skl",
+            TagCtx::PhaseGroup => "phase-group",
+            TagCtx::Phase => "phase",
+            TagCtx::Glossary => "glossary",
+            TagCtx::Reference => "reference",
+            TagCtx::InternalFile => "internal-file",
+            TagCtx::ExternalFile => "external-file",
+            TagCtx::OriginalData => "originalData",
+            TagCtx::Data => "data",
+        }
+    }
+
+    pub(crate) fn to_str_v20(self) -> &'static str {
+        match self {
+            TagCtx::Unit => "unit",
+            tag => tag.to_str(),
         }
     }
 }
@@ -1,5 +1,6 @@
 //! Defines helper functions which can be used to retrieve translations
 
+use crate::error::XliffError;
 use crate::store::{Store, Unit};
 use std::fs::File;
 use std::io::Read;
@@ -16,34 +17,35 @@ impl T {
     ///
     /// # Errors
     /// This function will return an error if reading or interpreting the XLIFF string is unsuccessfull.
-    pub fn load_str(string: &str) -> Self {
+    pub fn load_str(string: &str) -> Result<Self, XliffError> {
         let mut store: Store = Store::new();
-        store.load(string.as_bytes());
+        store.load(string.as_bytes())?;
 
-        return T { store };
+        Ok(T { store })
     }
 
     /// Reads and interprets the contents of the specified file.
     ///
     /// # Errors
-    /// This function will return an error if reading the file is not successful.
-    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+    /// This function will return an error if reading the file is not successful
+    /// or if its contents are not a valid XLIFF document.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, XliffError> {
         let file_path = path.as_ref();
-        let mut file = File::open(file_path).unwrap();
+        let mut file = File::open(file_path)?;
 
         let mut buffer: Vec<u8> = Default::default();
-        file.read_to_end(&mut buffer).expect("failed to read file");
+        file.read_to_end(&mut buffer)?;
 
         let mut store: Store = Store::new();
-        store.load(buffer.iter().as_slice());
+        store.load(buffer.iter().as_slice())?;
 
-        return T { store };
+        Ok(T { store })
     }
 
     /// Returns the first translation matching the provided `unit_id`.
     ///
     /// The value of `unit_id` is used to match against the `id` attribute of each `<trans-unit>`
-    /// element.
+    /// element, including those placed inside `<group>` elements.
     /// The `id` attribute values are determined by the tool that created the extracted the xliff
     /// document, they may or may not be the same as the translation source value.
     ///
@@ -58,11 +60,11 @@ impl T {
     /// use std::env;
     /// use xliff::t::T;
     ///
-    /// let translations = T::load("./en.xliff");
+    /// let translations = T::load("./en.xliff").expect("failed to load translations");
     ///
     ///    match translations.t(None, "fIC-hX-uRv.text") {
     ///        None => println!("translation not found"),
-    ///        Some(unit) => println!("> {}", unit.target_text().unwrap_or(&String::new())),
+    ///        Some(unit) => println!("> {}", unit.target_text().unwrap_or_default()),
     ///    }
     /// ```
     ///
@@ -72,43 +74,33 @@ impl T {
     /// use std::env;
     /// use xliff::t::T;
     ///
-    /// let translations = T::load("./en.xliff");
+    /// let translations = T::load("./en.xliff").expect("failed to load translations");
     ///
     ///    match translations.t(Some("SampleApp/en.lproj/Localizable.strings"), "fIC-hX-uRv.text") {
     ///        None => println!("translation not found"),
-    ///        Some(unit) => println!("> {}", unit.target_text().unwrap_or(&String::new())),
+    ///        Some(unit) => println!("> {}", unit.target_text().unwrap_or_default()),
     ///    }
     /// ```
     pub fn t(&self, domain: Option<&str>, unit_id: &str) -> Option<&Unit> {
         match domain {
             None => {
                 for group in self.store.groups.iter() {
-                    match group.units.iter().find(|u| {
-                        return u.id == String::from(unit_id);
-                    }) {
+                    match group.units().into_iter().find(|u| u.id == unit_id) {
                         None => (),
                         Some(result) => return Some(result),
                     }
                 }
             }
-            Some(address) => {
-                match self.store.groups.iter().find(|g| {
-                    return g.address == String::from(address);
-                }) {
+            Some(address) => match self.store.groups.iter().find(|g| g.address == address) {
+                None => (),
+                Some(group) => match group.units().into_iter().find(|u| u.id == unit_id) {
                     None => (),
-                    Some(group) => {
-                        match group.units.iter().find(|u| {
-                            return u.id == String::from(unit_id);
-                        }) {
-                            None => (),
-                            Some(result) => return Some(result),
-                        }
-                    }
-                }
-            }
+                    Some(result) => return Some(result),
+                },
+            },
         }
 
-        return None;
+        None
     }
 
     /// Returns the first translation matching the provided `source_text`.
@@ -127,11 +119,11 @@ impl T {
     /// use std::env;
     /// use xliff::t::T;
     ///
-    /// let translations = T::load("./en.xliff");
+    /// let translations = T::load("./en.xliff").expect("failed to load translations");
     ///
     ///    match translations.t_source(None, "Some text") {
     ///        None => println!("translation not found"),
-    ///        Some(unit) => println!("> {}", unit.target_text().unwrap_or(&String::new())),
+    ///        Some(unit) => println!("> {}", unit.target_text().unwrap_or_default()),
     ///    }
     /// ```
     ///
@@ -141,42 +133,112 @@ impl T {
     /// use std::env;
     /// use xliff::t::T;
     ///
-    /// let translations = T::load("./en.xliff");
+    /// let translations = T::load("./en.xliff").expect("failed to load translations");
     ///
     ///    match translations.t_source(Some("SampleApp/en.lproj/Localizable.strings"), "Some text") {
     ///        None => println!("translation not found"),
-    ///        Some(unit) => println!("> {}", unit.target_text().unwrap_or(&String::new())),
+    ///        Some(unit) => println!("> {}", unit.target_text().unwrap_or_default()),
     ///    }
     /// ```
     pub fn t_source(&self, domain: Option<&str>, source_text: &str) -> Option<&Unit> {
         match domain {
             None => {
                 for group in self.store.groups.iter() {
-                    match group.units.iter().find(|u| {
-                        return u.source_text() == Some(&String::from(source_text));
-                    }) {
+                    match group
+                        .units()
+                        .into_iter()
+                        .find(|u| u.source_text().as_deref() == Some(source_text))
+                    {
                         None => (),
                         Some(result) => return Some(result),
                     }
                 }
             }
-            Some(address) => {
-                match self.store.groups.iter().find(|g| {
-                    return g.address == String::from(address);
-                }) {
-                    None => (),
-                    Some(group) => {
-                        match group.units.iter().find(|u| {
-                            return u.source_text() == Some(&String::from(source_text));
-                        }) {
-                            None => (),
-                            Some(result) => return Some(result),
-                        }
+            Some(address) => match self.store.groups.iter().find(|g| g.address == address) {
+                None => (),
+                Some(group) => {
+                    match group
+                        .units()
+                        .into_iter()
+                        .find(|u| u.source_text().as_deref() == Some(source_text))
+                    {
+                        None => (),
+                        Some(result) => return Some(result),
                     }
                 }
-            }
+            },
         }
 
-        return None;
+        None
+    }
+
+    /// Returns the first translation matching the provided `resname`.
+    ///
+    /// The value of `resname` is used to match against the `resname` attribute of each
+    /// `<trans-unit>`, the resource identifier used by the original document such as the key
+    /// of an Android string, which may differ from the `id` of the unit.
+    ///
+    /// The specificity of the match can be increased by providing a value for `domain`
+    /// which is used to match against the `address` attribute of `<file>` elements.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use xliff::t::T;
+    ///
+    /// let translations = T::load("./en.xliff").expect("failed to load translations");
+    ///
+    ///    match translations.t_resname(None, "app_name") {
+    ///        None => println!("translation not found"),
+    ///        Some(unit) => println!("> {}", unit.target_text().unwrap_or_default()),
+    ///    }
+    /// ```
+    pub fn t_resname(&self, domain: Option<&str>, resname: &str) -> Option<&Unit> {
+        self.store
+            .units_where(domain, |u| u.resname.as_deref() == Some(resname))
+            .into_iter()
+            .next()
+    }
+
+    /// Returns the first translation matching the provided `source_text` and context.
+    ///
+    /// Identical source texts can have different translations depending on where they are used,
+    /// as with the `msgctxt` of gettext. Only units with a `<context>` of the given
+    /// `context_type` and `context` value are matched, in any of their context groups.
+    ///
+    /// The specificity of the match can be increased by providing a value for `domain`
+    /// which is used to match against the `address` attribute of `<file>` elements.
+    ///
+    /// # Example
+    ///
+    /// The following example will retrieve the translation of `Open` used as a menu item:
+    ///
+    /// ```rust,no_run
+    /// use xliff::t::T;
+    ///
+    /// let translations = T::load("./en.xliff").expect("failed to load translations");
+    ///
+    ///    match translations.t_source_context(None, "x-gettext-msgctxt", "menu", "Open") {
+    ///        None => println!("translation not found"),
+    ///        Some(unit) => println!("> {}", unit.target_text().unwrap_or_default()),
+    ///    }
+    /// ```
+    pub fn t_source_context(
+        &self,
+        domain: Option<&str>,
+        context_type: &str,
+        context: &str,
+        source_text: &str,
+    ) -> Option<&Unit> {
+        self.store
+            .units_where(domain, |u| {
+                u.source_text().as_deref() == Some(source_text)
+                    && u.context_groups
+                        .iter()
+                        .flat_map(|group| group.contexts.iter())
+                        .any(|c| c.context_type == context_type && c.value == context)
+            })
+            .into_iter()
+            .next()
     }
 }
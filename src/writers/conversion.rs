@@ -0,0 +1,401 @@
+//! Finds the contents of a `Store` which cannot be written as an XLIFF 2.0 document.
+//!
+//! `WriterXliff20` writes the XLIFF 1.2 data of a store to its XLIFF 2.0 equivalent, or to
+//! metadata when there is none. The remaining data cannot be written: the writer fails with a
+//! `ConversionError` listing it before anything is written. In lenient mode the document is
+//! written anyway and the losses are returned as warnings.
+
+use std::error::Error;
+use std::fmt;
+
+use crate::store::{
+    BodyItem, ContentPart, ContextGroup, CountGroup, Extensions, InlineCode, InlineKind, Note,
+    PropGroup, Store, Unit, CTYPES_V20,
+};
+
+/// XLIFF 1.2 data which has no XLIFF 2.0 equivalent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Unmapped {
+    /// An element, such as `alt-trans` or `bin-unit`.
+    Element(&'static str),
+    /// An attribute of the given element, or the value it has.
+    Attribute {
+        /// The name of the element.
+        element: &'static str,
+        /// The qualified name of the attribute.
+        name: String,
+    },
+    /// An element, comment or processing instruction which is not part of the model, kept
+    /// within the given element.
+    Extension(&'static str),
+}
+
+impl fmt::Display for Unmapped {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unmapped::Element(element) => write!(f, "<{}> has no XLIFF 2.0 equivalent", element),
+            Unmapped::Attribute { element, name } => write!(
+                f,
+                "the {} attribute of <{}> has no XLIFF 2.0 equivalent",
+                name, element
+            ),
+            Unmapped::Extension(element) => {
+                write!(
+                    f,
+                    "an unrecognised node within <{}> cannot be written",
+                    element
+                )
+            }
+        }
+    }
+}
+
+/// Data of the store which is not written, along with where it has been found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Loss {
+    /// The position of the file within the `groups` of the store, starting at 0, if the data
+    /// belongs to a file.
+    pub file_index: Option<usize>,
+    /// The `original` attribute of the file, if the data belongs to a file.
+    pub file: Option<String>,
+    /// The identifier of the `<trans-unit>` or `<bin-unit>` holding the data, if any.
+    pub unit: Option<String>,
+    /// The data which is not written.
+    pub data: Unmapped,
+}
+
+impl fmt::Display for Loss {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.file_index, &self.file) {
+            (Some(file_index), Some(file)) => write!(f, "file {} ({:?})", file_index, file)?,
+            _ => write!(f, "document")?,
+        }
+        if let Some(unit) = &self.unit {
+            write!(f, ", unit {:?}", unit)?;
+        }
+        write!(f, ": {}", self.data)
+    }
+}
+
+/// The error returned when a store holds data which cannot be written as XLIFF 2.0.
+#[derive(Debug)]
+pub struct ConversionError {
+    /// Every loss found, in document order.
+    pub losses: Vec<Loss>,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the store holds data which cannot be written as XLIFF 2.0 ({} problems)",
+            self.losses.len()
+        )?;
+        for loss in &self.losses {
+            write!(f, "\n- {}", loss)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConversionError {}
+
+/// Returns the data of the store which `WriterXliff20` cannot write, in document order.
+pub fn losses(store: &Store) -> Vec<Loss> {
+    let mut finder = LossFinder {
+        losses: vec![],
+        file_index: None,
+        file: None,
+        unit: None,
+        source_locale: None,
+    };
+
+    // the namespace declarations are written on the <xliff> element
+    for (name, _) in &store.extensions.attributes {
+        if !is_namespace_declaration(name) {
+            finder.attribute("xliff", name);
+        }
+    }
+    finder.nodes("xliff", &store.extensions);
+
+    for (ix, file) in store.groups.iter().enumerate() {
+        finder.file_index = Some(ix);
+        finder.file = Some(file.address.clone());
+        // the unrecognised attributes of files, groups and units are written as metadata
+        finder.nodes("file", &file.extensions);
+        if let Some(header) = &file.header {
+            if header.skeleton.is_some() {
+                finder.lose(Unmapped::Element("skl"));
+            }
+            if !header.phases.is_empty() {
+                finder.lose(Unmapped::Element("phase-group"));
+            }
+            for _ in &header.glossaries {
+                finder.lose(Unmapped::Element("glossary"));
+            }
+            for _ in &header.references {
+                finder.lose(Unmapped::Element("reference"));
+            }
+            // the unrecognised attributes of tools are written as metas
+            for tool in &header.tools {
+                finder.nodes("tool", &tool.extensions);
+            }
+            finder.extensions("header", &header.extensions);
+            finder.annotations(
+                &[],
+                &header.count_groups,
+                &header.prop_groups,
+                &header.notes,
+            );
+        }
+        finder.source_locale = file.source_locale.as_ref().map(|l| l.identifier.clone());
+        finder.body(&file.body, "body");
+    }
+
+    finder.losses
+}
+
+/// Whether the attribute is the declaration of a namespace prefix.
+pub(crate) fn is_namespace_declaration(name: &str) -> bool {
+    name.starts_with("xmlns:")
+}
+
+/// Whether the markup is a comment or a processing instruction, which can be written anywhere.
+pub(crate) fn is_comment_or_pi(markup: &str) -> bool {
+    markup.starts_with("<!--") || markup.starts_with("<?")
+}
+
+/// The XLIFF 2.0 attributes of an inline code other than its identifiers and references, along
+/// with the names of the attributes which have no equivalent.
+///
+/// The `ctype` of codes is written as their `type` and `subType`, and their `equiv-text` as
+/// `equiv`. The `mtype` of a `<mrk>` is written as the `term` type, or `translate="no"` when it is
+/// `protected`, and its `comment` as the `comment` type along with its `value`.
+pub(crate) fn code_attributes(code: &InlineCode) -> (Vec<(&'static str, String)>, Vec<&str>) {
+    let mut attributes = vec![];
+    let mut unmapped = vec![];
+
+    for (name, value) in &code.attributes {
+        match (code.kind, name.as_str()) {
+            (InlineKind::Mrk, "mtype") => match value.as_str() {
+                "term" => attributes.push(("type", String::from("term"))),
+                "protected" => attributes.push(("translate", String::from("no"))),
+                "x-generic" | "x-comment" => (),
+                _ => unmapped.push(name.as_str()),
+            },
+            (InlineKind::Mrk, "mid") => (),
+            (InlineKind::Mrk, "comment") => (),
+            (InlineKind::Mrk, _) => unmapped.push(name.as_str()),
+            (_, "ctype") => match CTYPES_V20.iter().find(|(ctype, _, _)| ctype == value) {
+                Some((_, code_type, sub_type)) => {
+                    attributes.push(("type", String::from(*code_type)));
+                    if let Some(sub_type) = sub_type {
+                        attributes.push(("subType", String::from(*sub_type)));
+                    }
+                }
+                None => unmapped.push(name.as_str()),
+            },
+            (InlineKind::G, "equiv-text") => unmapped.push(name.as_str()),
+            (_, "equiv-text") => attributes.push(("equiv", value.clone())),
+            // pairs the end of a code with its start
+            (_, "rid") => (),
+            (InlineKind::It, "pos") => (),
+            _ => unmapped.push(name.as_str()),
+        }
+    }
+
+    if let (InlineKind::Mrk, Some(comment)) = (code.kind, code.attribute("comment")) {
+        match attributes.iter().any(|(name, _)| *name == "type") {
+            true => unmapped.push("comment"),
+            false => {
+                attributes.push(("type", String::from("comment")));
+                attributes.push(("value", String::from(comment)));
+            }
+        }
+    }
+
+    (attributes, unmapped)
+}
+
+/// Whether the inline code holds native code, written to the `<originalData>` of its unit.
+pub(crate) fn has_native_code(code: &InlineCode) -> bool {
+    matches!(
+        code.kind,
+        InlineKind::Ph | InlineKind::Bpt | InlineKind::Ept | InlineKind::It
+    )
+}
+
+/// The native code held by an inline code. The translatable text of its `<sub>` elements and its
+/// unrecognised nodes have no equivalent.
+pub(crate) fn native_code(code: &InlineCode) -> String {
+    let mut native = String::new();
+    for part in &code.content {
+        if let ContentPart::Text(text) = part {
+            native.push_str(text);
+        }
+    }
+    native
+}
+
+struct LossFinder {
+    losses: Vec<Loss>,
+    file_index: Option<usize>,
+    file: Option<String>,
+    unit: Option<String>,
+    /// The `source-language` of the file, which is the `srcLang` of the document.
+    source_locale: Option<String>,
+}
+
+impl LossFinder {
+    fn lose(&mut self, data: Unmapped) {
+        self.losses.push(Loss {
+            file_index: self.file_index,
+            file: self.file.clone(),
+            unit: self.unit.clone(),
+            data,
+        });
+    }
+
+    fn attribute(&mut self, element: &'static str, name: &str) {
+        self.lose(Unmapped::Attribute {
+            element,
+            name: String::from(name),
+        });
+    }
+
+    fn nodes(&mut self, element: &'static str, extensions: &Extensions) {
+        for _ in &extensions.nodes {
+            self.lose(Unmapped::Extension(element));
+        }
+    }
+
+    fn extensions(&mut self, element: &'static str, extensions: &Extensions) {
+        for (name, _) in &extensions.attributes {
+            self.attribute(element, name);
+        }
+        self.nodes(element, extensions);
+    }
+
+    fn body(&mut self, body: &[BodyItem], element: &'static str) {
+        for item in body {
+            match item {
+                BodyItem::Unit(unit) => self.unit(unit),
+                BodyItem::Group(group) => {
+                    self.nodes("group", &group.extensions);
+                    self.annotations(
+                        &group.context_groups,
+                        &group.count_groups,
+                        &group.prop_groups,
+                        &group.notes,
+                    );
+                    self.body(&group.body, "group");
+                }
+                BodyItem::BinUnit(bin_unit) => {
+                    self.unit = Some(bin_unit.id.clone());
+                    self.lose(Unmapped::Element("bin-unit"));
+                    self.unit = None;
+                }
+                BodyItem::Extension(_) => self.lose(Unmapped::Extension(element)),
+            }
+        }
+    }
+
+    fn unit(&mut self, unit: &Unit) {
+        self.unit = Some(unit.id.clone());
+        self.nodes("trans-unit", &unit.extensions);
+        for (name, value) in &unit.source_extensions.attributes {
+            // the language of the source is that of the document
+            let same_language = self
+                .source_locale
+                .as_ref()
+                .map_or(false, |locale| locale.eq_ignore_ascii_case(value));
+            if name != "xml:lang" || !same_language {
+                self.attribute("source", name);
+            }
+        }
+        self.nodes("source", &unit.source_extensions);
+        self.extensions("target", &unit.target_extensions);
+        if let Some(source) = &unit.source {
+            self.content("source", &source.content);
+        }
+        if unit.seg_source.is_some() {
+            self.lose(Unmapped::Element("seg-source"));
+        }
+        if let Some(target) = &unit.target {
+            self.content("target", &target.content);
+        }
+        for _ in &unit.alternatives {
+            self.lose(Unmapped::Element("alt-trans"));
+        }
+        self.annotations(
+            &unit.context_groups,
+            &unit.count_groups,
+            &unit.prop_groups,
+            &unit.notes,
+        );
+        self.unit = None;
+    }
+
+    fn content(&mut self, element: &'static str, content: &[ContentPart]) {
+        for part in content {
+            match part {
+                ContentPart::Text(_) => (),
+                ContentPart::Code(code) => {
+                    let (_, unmapped) = code_attributes(code);
+                    for name in unmapped {
+                        self.attribute(code.kind.to_str(), name);
+                    }
+                    match has_native_code(code) {
+                        true => self.native_code(code),
+                        false => self.content(element, &code.content),
+                    }
+                }
+                ContentPart::Extension(markup) if is_comment_or_pi(markup) => (),
+                ContentPart::Extension(_) => self.lose(Unmapped::Extension(element)),
+            }
+        }
+    }
+
+    fn native_code(&mut self, code: &InlineCode) {
+        for part in &code.content {
+            match part {
+                ContentPart::Text(_) => (),
+                ContentPart::Code(_) => self.lose(Unmapped::Element("sub")),
+                ContentPart::Extension(_) => self.lose(Unmapped::Extension(code.kind.to_str())),
+            }
+        }
+    }
+
+    fn annotations(
+        &mut self,
+        context_groups: &[ContextGroup],
+        count_groups: &[CountGroup],
+        prop_groups: &[PropGroup],
+        notes: &[Note],
+    ) {
+        for context in context_groups.iter().flat_map(|group| &group.contexts) {
+            if context.match_mandatory {
+                self.attribute("context", "match-mandatory");
+            }
+        }
+        for count in count_groups.iter().flat_map(|group| &group.counts) {
+            if count.phase_name.is_some() {
+                self.attribute("count", "phase-name");
+            }
+        }
+        for prop in prop_groups.iter().flat_map(|group| &group.props) {
+            if prop.lang.is_some() {
+                self.attribute("prop", "xml:lang");
+            }
+        }
+        for note in notes {
+            if note.from.is_some() {
+                self.attribute("note", "from");
+            }
+            if note.lang.is_some() {
+                self.attribute("note", "xml:lang");
+            }
+            self.extensions("note", &note.extensions);
+        }
+    }
+}
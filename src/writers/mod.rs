@@ -1,4 +1,9 @@
 //! Defines output writers used throughout this library.
 
+pub mod conversion;
+pub mod options;
+mod output;
 pub mod traits;
+pub mod validation;
 pub mod xliff12;
+pub mod xliff20;
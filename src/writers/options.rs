@@ -0,0 +1,108 @@
+//! Defines the formatting options shared by the output writers.
+
+/// The characters written at the end of each line when the output is indented.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Newline {
+    /// `\n`, as used on Unix systems.
+    Lf,
+    /// `\r\n`, as used on Windows systems.
+    CrLf,
+}
+
+impl Newline {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Newline::Lf => "\n",
+            Newline::CrLf => "\r\n",
+        }
+    }
+}
+
+/// The quotation mark written around attribute values.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Quote {
+    /// `attribute="value"`
+    Double,
+    /// `attribute='value'`
+    Single,
+}
+
+impl Quote {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Quote::Double => "\"",
+            Quote::Single => "'",
+        }
+    }
+
+    pub(crate) fn as_char(self) -> char {
+        match self {
+            Quote::Double => '"',
+            Quote::Single => '\'',
+        }
+    }
+}
+
+/// Controls how an output writer formats the XML document.
+///
+/// The default options write the whole document on a single line, starting with an XML
+/// declaration and using double quotes around attribute values.
+///
+/// # Example
+/// ```rust
+/// use xliff::writers::options::{Newline, WriterOptions};
+///
+/// let options = WriterOptions {
+///     indent: Some(2),
+///     newline: Newline::CrLf,
+///     self_closing: true,
+///     ..WriterOptions::default()
+/// };
+/// assert!(options.xml_declaration);
+/// ```
+#[derive(PartialEq, Clone, Debug)]
+pub struct WriterOptions {
+    /// The number of spaces written for each level of nesting. When set, each structural element
+    /// is written on its own line. The content of elements holding text, such as `<source>`,
+    /// `<target>` or `<note>`, is never indented as white space is significant in it.
+    pub indent: Option<usize>,
+    /// The line ending written when the output is indented.
+    pub newline: Newline,
+    /// Whether the document starts with `<?xml version="1.0" encoding="UTF-8"?>`.
+    pub xml_declaration: bool,
+    /// The quotation mark written around attribute values.
+    pub quote: Quote,
+    /// Whether elements without content are written as `<element/>`. Otherwise they are
+    /// closed with an end tag, as in `<element></element>`.
+    pub self_closing: bool,
+    /// Whether quotation marks and apostrophes are escaped in text. Attribute values always
+    /// escape the quotation mark written around them.
+    pub escape_quotes: bool,
+}
+
+impl Default for WriterOptions {
+    fn default() -> Self {
+        WriterOptions {
+            indent: None,
+            newline: Newline::Lf,
+            xml_declaration: true,
+            quote: Quote::Double,
+            self_closing: false,
+            escape_quotes: true,
+        }
+    }
+}
+
+impl WriterOptions {
+    /// The formatting of the documents exported by Xcode with `xcodebuild -exportLocalizations`.
+    pub fn xcode() -> Self {
+        WriterOptions {
+            indent: Some(2),
+            newline: Newline::Lf,
+            xml_declaration: true,
+            quote: Quote::Double,
+            self_closing: true,
+            escape_quotes: false,
+        }
+    }
+}
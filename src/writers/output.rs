@@ -0,0 +1,199 @@
+//! Serializes the elements produced by the output writers into any `io::Write`.
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use super::options::WriterOptions;
+
+/// Writes XML markup straight into the inner output, formatted according to `WriterOptions`.
+///
+/// Only the start tag of the innermost element is held back, until it is known whether the
+/// element has any content.
+pub(crate) struct XmlOutput<'o, W: Write> {
+    inner: W,
+    options: &'o WriterOptions,
+    /// The elements whose content is written as is, without any indentation.
+    content_elements: &'static [&'static str],
+    /// The start tag of the innermost element, without its closing `>`.
+    pending: Option<String>,
+    /// For each open element, whether a child has been written on its own line.
+    open: Vec<bool>,
+    /// The number of open elements placed inside a content element, including itself.
+    content_depth: usize,
+    /// Whether anything has been written yet.
+    started: bool,
+}
+
+impl<'o, W: Write> XmlOutput<'o, W> {
+    pub(crate) fn new(
+        inner: W,
+        options: &'o WriterOptions,
+        content_elements: &'static [&'static str],
+    ) -> Self {
+        XmlOutput {
+            inner,
+            options,
+            content_elements,
+            pending: None,
+            open: vec![],
+            content_depth: 0,
+            started: false,
+        }
+    }
+
+    /// Writes the XML declaration, unless disabled by the options.
+    pub(crate) fn declaration(&mut self) -> io::Result<()> {
+        if self.options.xml_declaration {
+            self.inner
+                .write_all(br#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+            self.started = true;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn open(&mut self, tag: &str, attributes: &[(&str, &str)]) -> io::Result<()> {
+        self.start_node()?;
+        self.pending = Some(self.start_tag(tag, attributes));
+        self.open.push(false);
+        if self.content_depth > 0 || self.content_elements.contains(&tag) {
+            self.content_depth += 1;
+        }
+        Ok(())
+    }
+
+    /// Writes an element without content, which is self-closing when the options ask for it.
+    pub(crate) fn empty(&mut self, tag: &str, attributes: &[(&str, &str)]) -> io::Result<()> {
+        self.open(tag, attributes)?;
+        self.close(tag)
+    }
+
+    pub(crate) fn text(&mut self, text: &str) -> io::Result<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        self.flush_pending()?;
+        let quotes: &[char] = match self.options.escape_quotes {
+            true => &['"', '\''],
+            false => &[],
+        };
+        self.inner.write_all(escape(text, quotes).as_bytes())
+    }
+
+    /// Writes markup which has already been serialized, such as an extension element.
+    pub(crate) fn raw(&mut self, markup: &str) -> io::Result<()> {
+        self.start_node()?;
+        self.inner.write_all(markup.as_bytes())
+    }
+
+    pub(crate) fn close(&mut self, tag: &str) -> io::Result<()> {
+        let has_children = self.open.pop().unwrap_or_default();
+        if self.content_depth > 0 {
+            self.content_depth -= 1;
+        }
+
+        match self.pending.take() {
+            Some(start) if self.options.self_closing => {
+                self.inner.write_all(start.as_bytes())?;
+                self.inner.write_all(b"/>")
+            }
+            Some(start) => {
+                self.inner.write_all(start.as_bytes())?;
+                write!(self.inner, "></{}>", tag)
+            }
+            None => {
+                if has_children {
+                    self.line_break()?;
+                }
+                write!(self.inner, "</{}>", tag)
+            }
+        }
+    }
+
+    /// Ends the document, flushing the inner output.
+    pub(crate) fn finish(&mut self) -> io::Result<()> {
+        self.flush_pending()?;
+        if self.options.indent.is_some() {
+            self.inner
+                .write_all(self.options.newline.as_str().as_bytes())?;
+        }
+        self.inner.flush()
+    }
+
+    /// Prepares the output for an element or markup placed inside the current element.
+    fn start_node(&mut self) -> io::Result<()> {
+        self.flush_pending()?;
+        if self.content_depth == 0 {
+            if let Some(has_children) = self.open.last_mut() {
+                *has_children = true;
+            }
+            self.line_break()?;
+        }
+        self.started = true;
+        Ok(())
+    }
+
+    /// Starts a new line indented for the current depth, when the output is indented.
+    fn line_break(&mut self) -> io::Result<()> {
+        match self.options.indent {
+            Some(indent) if self.started && self.content_depth == 0 => {
+                self.inner
+                    .write_all(self.options.newline.as_str().as_bytes())?;
+                write!(
+                    self.inner,
+                    "{:width$}",
+                    "",
+                    width = indent * self.open.len()
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn flush_pending(&mut self) -> io::Result<()> {
+        if let Some(start) = self.pending.take() {
+            self.inner.write_all(start.as_bytes())?;
+            self.inner.write_all(b">")?;
+        }
+        Ok(())
+    }
+
+    fn start_tag(&self, tag: &str, attributes: &[(&str, &str)]) -> String {
+        let quote = self.options.quote.as_str();
+        let quotes: &[char] = match self.options.escape_quotes {
+            true => &['"', '\''],
+            false => &[self.options.quote.as_char()],
+        };
+        let mut start = format!("<{}", tag);
+        for (name, value) in attributes {
+            start.push_str(&format!(
+                " {}={}{}{}",
+                name,
+                quote,
+                escape(value, quotes),
+                quote
+            ));
+        }
+        start
+    }
+}
+
+/// Replaces the markup characters and the provided quotation marks with character references.
+fn escape<'t>(text: &'t str, quotes: &[char]) -> Cow<'t, str> {
+    let needs_escape = |c: char| matches!(c, '&' | '<' | '>') || quotes.contains(&c);
+    if !text.contains(needs_escape) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if quotes.contains(&c) => escaped.push_str("&quot;"),
+            '\'' if quotes.contains(&c) => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
@@ -2,9 +2,25 @@
 
 use crate::store::Store;
 use std::error::Error;
+use std::io::Write;
 
 /// Xliff writer able to convert a translation `Store` object into a compatible output file.
 pub trait XliffWriter {
-    /// Creates a bytes array from the contents of the provided `Store` instance.
-    fn write(store: &Store) -> Result<Vec<u8>, Box<dyn Error>>;
+    /// Writes the contents of the provided `Store` instance into `w`, formatted according to
+    /// the options of the writer.
+    ///
+    /// The document is written as it is produced, without being held in memory. Outputs which
+    /// are not buffered, such as a `File`, should be wrapped in a `BufWriter`.
+    fn write_to<W: Write>(&self, store: &Store, w: W) -> Result<(), Box<dyn Error>>;
+
+    /// Creates a bytes array from the contents of the provided `Store` instance,
+    /// using the default options.
+    fn write(store: &Store) -> Result<Vec<u8>, Box<dyn Error>>
+    where
+        Self: Default,
+    {
+        let mut bytes = Vec::new();
+        Self::default().write_to(store, &mut bytes)?;
+        Ok(bytes)
+    }
 }
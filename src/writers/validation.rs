@@ -0,0 +1,140 @@
+//! Checks that the contents of a `Store` can be written as a valid XLIFF 1.2 document.
+//!
+//! `WriterXliff12` validates the store before writing anything and fails with a
+//! `ValidationError` listing every violation. In lenient mode the document is written anyway
+//! and the violations are returned as warnings.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+use crate::store::{BodyItem, Store};
+
+/// A rule of the XLIFF 1.2 structure broken by the contents of a store.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    /// The `original` attribute of a `<file>` must not be empty.
+    EmptyOriginal,
+    /// A `<file>` must have a `source-language` attribute.
+    MissingSourceLanguage,
+    /// A `<trans-unit>` must have a `<source>`. Such units are not written.
+    MissingSource,
+    /// The `id` of a `<trans-unit>` or `<bin-unit>` must be unique among both within its
+    /// `<file>`.
+    DuplicateUnitId,
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rule::EmptyOriginal => write!(f, "the original attribute of the file is empty"),
+            Rule::MissingSourceLanguage => write!(f, "the file has no source-language"),
+            Rule::MissingSource => write!(f, "the translation unit has no source"),
+            Rule::DuplicateUnitId => {
+                write!(f, "the unit id is used more than once")
+            }
+        }
+    }
+}
+
+/// A violation of a rule found in a file of the store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// The position of the file within the `groups` of the store, starting at 0.
+    pub file_index: usize,
+    /// The `original` attribute of the file.
+    pub file: String,
+    /// The identifier of the `<trans-unit>` or `<bin-unit>` breaking the rule, if any.
+    pub unit: Option<String>,
+    /// The rule which is broken.
+    pub rule: Rule,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "file {} ({:?})", self.file_index, self.file)?;
+        if let Some(unit) = &self.unit {
+            write!(f, ", unit {:?}", unit)?;
+        }
+        write!(f, ": {}", self.rule)
+    }
+}
+
+/// The error returned when a store cannot be written as a valid document.
+#[derive(Debug)]
+pub struct ValidationError {
+    /// Every violation found, in document order.
+    pub violations: Vec<Violation>,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the store is not a valid XLIFF 1.2 document ({} problems)",
+            self.violations.len()
+        )?;
+        for violation in &self.violations {
+            write!(f, "\n- {}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationError {}
+
+/// Returns every violation of the XLIFF 1.2 structure found in the store, in document order.
+pub fn validate(store: &Store) -> Vec<Violation> {
+    let mut violations = vec![];
+
+    for (file_index, file) in store.groups.iter().enumerate() {
+        let violation = |unit: Option<&str>, rule: Rule| Violation {
+            file_index,
+            file: file.address.clone(),
+            unit: unit.map(String::from),
+            rule,
+        };
+
+        if file.address.is_empty() {
+            violations.push(violation(None, Rule::EmptyOriginal));
+        }
+        if file.source_locale.is_none() {
+            violations.push(violation(None, Rule::MissingSourceLanguage));
+        }
+
+        let mut units = vec![];
+        collect_units(&file.body, &mut units);
+
+        let mut ids = HashSet::new();
+        let mut duplicates = HashSet::new();
+        for item in units {
+            let id = match item {
+                BodyItem::Unit(unit) => {
+                    if unit.source.is_none() {
+                        violations.push(violation(Some(&unit.id), Rule::MissingSource));
+                    }
+                    unit.id.as_str()
+                }
+                BodyItem::BinUnit(bin_unit) => bin_unit.id.as_str(),
+                _ => continue,
+            };
+            if !ids.insert(id) && duplicates.insert(id) {
+                violations.push(violation(Some(id), Rule::DuplicateUnitId));
+            }
+        }
+    }
+
+    violations
+}
+
+/// Collects the translation and binary units of the body and its nested groups,
+/// in document order.
+fn collect_units<'a>(body: &'a [BodyItem], units: &mut Vec<&'a BodyItem>) {
+    for item in body {
+        match item {
+            BodyItem::Unit(_) | BodyItem::BinUnit(_) => units.push(item),
+            BodyItem::Group(group) => collect_units(&group.body, units),
+            BodyItem::Extension(_) => (),
+        }
+    }
+}
@@ -1,166 +1,868 @@
 //! Defines XLIFF 1.2 compatible output writer
 
 use std::error::Error;
-use std::io::Cursor;
-
-use quick_xml::Writer;
+use std::io::Write;
 
+use super::options::WriterOptions;
+use super::output::XmlOutput;
 pub use super::traits::XliffWriter;
+use super::validation::{validate, ValidationError, Violation};
 
-use crate::store::{Store, TagCtx, TranslationFile, Unit};
-use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use crate::store::{
+    AltTrans, BinFile, BinUnit, BodyItem, ContentPart, ContextGroup, CountGroup, Extensions, Group,
+    Locale, Note, PropGroup, ReferenceFile, Store, TagCtx, TranslationFile, Unit, UnitValue,
+    BASE64_FORM,
+};
 
 type WriterResult = Result<(), Box<dyn Error>>;
 
+/// Writes the unrecognised nodes of an element in place, between its recognised children.
+struct ExtensionNodes<'a> {
+    /// The nodes which have not been written yet.
+    nodes: &'a [(usize, String)],
+    /// The number of recognised children written so far.
+    children: usize,
+}
+
+impl<'a> ExtensionNodes<'a> {
+    fn new(extensions: &'a Extensions) -> Self {
+        ExtensionNodes {
+            nodes: &extensions.nodes,
+            children: 0,
+        }
+    }
+
+    /// Writes the nodes placed before the recognised child which is about to be written.
+    fn child<W: Write>(&mut self, writer: &mut XmlOutput<W>) -> WriterResult {
+        self.write_until(writer, self.children)?;
+        self.children += 1;
+        Ok(())
+    }
+
+    /// Writes the remaining nodes, placed after the last recognised child.
+    fn finish<W: Write>(mut self, writer: &mut XmlOutput<W>) -> WriterResult {
+        self.write_until(writer, usize::MAX)
+    }
+
+    fn write_until<W: Write>(
+        &mut self,
+        writer: &mut XmlOutput<W>,
+        position: usize,
+    ) -> WriterResult {
+        while let Some(((node_position, markup), rest)) = self.nodes.split_first() {
+            if *node_position > position {
+                break;
+            }
+            writer.raw(markup)?;
+            self.nodes = rest;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the recognised children of an element along with its unrecognised nodes, in the
+/// order the children have been read in. Children added since are written at their place in the
+/// order of the schema: after the last child of the same kind which has been read, or else before
+/// the first child of a later kind. `counts` gives the number of children of each kind in the
+/// order of the schema, and `write_child` writes the child of the given kind and index.
+fn write_children<W, F>(
+    writer: &mut XmlOutput<W>,
+    extensions: &Extensions,
+    counts: &[(TagCtx, usize)],
+    mut write_child: F,
+) -> WriterResult
+where
+    W: Write,
+    F: FnMut(&mut XmlOutput<W>, TagCtx, usize) -> WriterResult,
+{
+    let read: Vec<usize> = extensions
+        .order
+        .iter()
+        .filter_map(|name| counts.iter().position(|(tag, _)| tag.to_str() == name))
+        .collect();
+    // the number of children of each kind still to be written in the order they have been read
+    let mut pending: Vec<usize> = counts
+        .iter()
+        .enumerate()
+        .map(|(kind, (_, count))| (*count).min(read.iter().filter(|k| **k == kind).count()))
+        .collect();
+    let mut written = vec![0; counts.len()];
+    let mut nodes = ExtensionNodes::new(extensions);
+    // writes the next child of the given kind, or all the remaining ones
+    let mut write_kind = |writer: &mut XmlOutput<W>, kind: usize, all: bool| -> WriterResult {
+        let until = match all {
+            true => counts[kind].1,
+            false => counts[kind].1.min(written[kind] + 1),
+        };
+        while written[kind] < until {
+            nodes.child(writer)?;
+            write_child(writer, counts[kind].0, written[kind])?;
+            written[kind] += 1;
+        }
+        Ok(())
+    };
+
+    for kind in read {
+        if pending[kind] == 0 {
+            continue;
+        }
+        let earlier: Vec<usize> = (0..kind).filter(|k| pending[*k] == 0).collect();
+        for earlier in earlier {
+            write_kind(writer, earlier, true)?;
+        }
+        pending[kind] -= 1;
+        write_kind(writer, kind, pending[kind] == 0)?;
+    }
+    for kind in 0..counts.len() {
+        write_kind(writer, kind, true)?;
+    }
+    nodes.finish(writer)
+}
+
+/// Elements whose content is written without indentation.
+const CONTENT_ELEMENTS: &[&str] = &[
+    "source",
+    "seg-source",
+    "target",
+    "note",
+    "context",
+    "count",
+    "prop",
+    "internal-file",
+];
+
+/// The conventions followed when choosing and ordering the attributes of the elements.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Profile {
+    /// Every attribute of the model is written, including `translate` and `xml:space` on each
+    /// translation unit.
+    Standard,
+    /// Follows the documents exported by Xcode: `<file>` attributes are written in Xcode's order
+    /// and `translate` is only written when a unit is not to be translated.
+    Xcode,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Profile::Standard
+    }
+}
+
 /// XLIFF 1.2 compatible output writer
-pub struct WriterXliff12;
+#[derive(Default)]
+pub struct WriterXliff12 {
+    /// Controls how the document is formatted.
+    pub options: WriterOptions,
+    /// Controls which attributes are written.
+    pub profile: Profile,
+    /// Whether a store which is not a valid XLIFF 1.2 document is written anyway.
+    /// See `write_checked`.
+    pub lenient: bool,
+}
+
+impl WriterXliff12 {
+    /// Creates a writer formatting documents according to the provided options.
+    pub fn new(options: WriterOptions) -> Self {
+        WriterXliff12 {
+            options,
+            profile: Profile::Standard,
+            lenient: false,
+        }
+    }
+
+    /// Creates a writer reproducing the layout of the documents exported by Xcode with
+    /// `xcodebuild -exportLocalizations`, so a document which has been read and written back
+    /// without changes is identical to the original.
+    pub fn xcode() -> Self {
+        WriterXliff12 {
+            options: WriterOptions::xcode(),
+            profile: Profile::Xcode,
+            lenient: false,
+        }
+    }
+
+    /// Validates the store, then writes it into `w`.
+    ///
+    /// # Errors
+    /// Returns a `ValidationError` listing every violation found before anything is written,
+    /// unless the writer is lenient. A lenient writer writes the document anyway and returns the
+    /// violations as warnings. Translation units without a source are never written.
+    pub fn write_checked<W: Write>(
+        &self,
+        store: &Store,
+        w: W,
+    ) -> Result<Vec<Violation>, Box<dyn Error>> {
+        let violations = validate(store);
+        if !violations.is_empty() && !self.lenient {
+            return Err(Box::new(ValidationError { violations }));
+        }
+
+        self.write_document(store, w)?;
+        Ok(violations)
+    }
+}
 
 impl XliffWriter for WriterXliff12 {
-    fn write(store: &Store) -> Result<Vec<u8>, Box<dyn Error>> {
-        let mut writer = Writer::new(Cursor::new(Vec::new()));
+    /// Validates the store, then writes it into `w`. See `write_checked`.
+    fn write_to<W: Write>(&self, store: &Store, w: W) -> Result<(), Box<dyn Error>> {
+        self.write_checked(store, w)?;
+        Ok(())
+    }
+}
 
-        WriterXliff12::print_envelope(&mut writer)?;
+impl WriterXliff12 {
+    fn write_document<W: Write>(&self, store: &Store, w: W) -> WriterResult {
+        let mut writer = XmlOutput::new(w, &self.options, CONTENT_ELEMENTS);
 
-        for file in &store.groups {
-            Self::open_tag(&mut writer, TagCtx::File.to_str(), Some(file.attributes()))?;
+        WriterXliff12::print_envelope(&mut writer, store)?;
 
-            Self::write_header(&mut writer, &file)?;
-            Self::write_body(&mut writer, &file)?;
+        let files = [(TagCtx::File, store.groups.len())];
+        write_children(
+            &mut writer,
+            &store.extensions,
+            &files,
+            |writer, _, index| {
+                let file = &store.groups[index];
+                let mut attributes = file.attributes();
+                if self.profile == Profile::Xcode {
+                    attributes.sort_by_key(|(name, _)| *name == "datatype");
+                }
+                let attributes = Self::with_extensions(attributes, &file.extensions);
+                Self::open_tag(writer, TagCtx::File.to_str(), Some(attributes))?;
 
-            Self::close_tag(&mut writer, TagCtx::File.to_str())?;
-        }
+                let children = [
+                    (TagCtx::Header, file.header.iter().count()),
+                    (TagCtx::Body, 1),
+                ];
+                write_children(
+                    writer,
+                    &file.extensions,
+                    &children,
+                    |writer, tag, _| match tag {
+                        TagCtx::Header => Self::write_header(writer, file),
+                        _ => Self::write_body(writer, file, self.profile),
+                    },
+                )?;
+
+                Self::close_tag(writer, TagCtx::File.to_str())
+            },
+        )?;
 
         WriterXliff12::print_envelope_end(&mut writer)?;
 
-        return Ok(writer.into_inner().into_inner());
+        writer.finish()?;
+        Ok(())
     }
 }
 
 impl WriterXliff12 {
-    fn print_envelope(writer: &mut Writer<Cursor<Vec<u8>>>) -> WriterResult {
+    fn print_envelope<W: Write>(writer: &mut XmlOutput<W>, store: &Store) -> WriterResult {
         // header <?xml version="1.0" encoding="UTF-8"?>
-        writer.write_event(Event::Decl(BytesDecl::new(b"1.0", Some(b"UTF-8"), None)))?;
-        Self::open_tag(writer, TagCtx::Xliff.to_str(), Some(vec![
+        writer.declaration()?;
+        Self::open_tag(writer, TagCtx::Xliff.to_str(), Some(Self::with_extensions(vec![
             ("xmlns", "urn:oasis:names:tc:xliff:document:1.2"),
             ("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance"),
             ("version", "1.2"),
             ("xsi:schemaLocation", "urn:oasis:names:tc:xliff:document:1.2 http://docs.oasis-open.org/xliff/v1.2/os/xliff-core-1.2-strict.xsd")
-        ]))
+        ], &store.extensions)))
     }
 
-    fn print_envelope_end(writer: &mut Writer<Cursor<Vec<u8>>>) -> WriterResult {
+    fn print_envelope_end<W: Write>(writer: &mut XmlOutput<W>) -> WriterResult {
         Self::close_tag(writer, TagCtx::Xliff.to_str())
     }
 }
 
 impl WriterXliff12 {
-    fn open_tag(
-        writer: &mut Writer<Cursor<Vec<u8>>>,
+    fn open_tag<W: Write>(
+        writer: &mut XmlOutput<W>,
         tag: &str,
         attributes: Option<Vec<(&str, &str)>>,
     ) -> WriterResult {
-        let mut elem = BytesStart::owned(tag.as_bytes(), tag.len());
-        if let Some(attributes) = attributes {
-            for attribute in attributes.into_iter() {
-                elem.push_attribute(attribute)
+        writer.open(tag, &attributes.unwrap_or_default())?;
+        Ok(())
+    }
+
+    fn write_text<W: Write>(writer: &mut XmlOutput<W>, text: &str) -> WriterResult {
+        writer.text(text)?;
+        Ok(())
+    }
+
+    fn empty_tag<W: Write>(
+        writer: &mut XmlOutput<W>,
+        tag: &str,
+        attributes: Vec<(&str, &str)>,
+    ) -> WriterResult {
+        writer.empty(tag, &attributes)?;
+        Ok(())
+    }
+
+    /// Writes text and inline codes, inline codes without content are written as empty elements.
+    fn write_content<W: Write>(writer: &mut XmlOutput<W>, content: &[ContentPart]) -> WriterResult {
+        for part in content {
+            match part {
+                ContentPart::Text(text) => Self::write_text(writer, text.as_str())?,
+                ContentPart::Code(code) if code.content.is_empty() => {
+                    Self::empty_tag(writer, code.kind.to_str(), code.attributes())?
+                }
+                ContentPart::Code(code) => {
+                    Self::open_tag(writer, code.kind.to_str(), Some(code.attributes()))?;
+                    Self::write_content(writer, &code.content)?;
+                    Self::close_tag(writer, code.kind.to_str())?;
+                }
+                ContentPart::Extension(markup) => writer.raw(markup)?,
             }
         }
-        writer.write_event(Event::Start(elem))?;
         Ok(())
     }
 
-    fn write_text(writer: &mut Writer<Cursor<Vec<u8>>>, text: &str) -> WriterResult {
-        let elem = BytesText::from_plain_str(text);
-        writer.write_event(Event::Text(elem))?;
-        Ok(())
+    fn write_note<W: Write>(writer: &mut XmlOutput<W>, note: &Note) -> WriterResult {
+        let attributes = note.attributes();
+        let attributes = attributes.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        let attributes = Self::with_extensions(attributes, &note.extensions);
+        Self::open_tag(writer, TagCtx::Note.to_str(), Some(attributes))?;
+        // nodes placed at a position which is not within the text are written where they are met
+        let mut written = 0;
+        for (position, markup) in &note.extensions.nodes {
+            if let Some(text) = note.text.get(written..*position) {
+                Self::write_text(writer, text)?;
+                written = *position;
+            }
+            writer.raw(markup)?;
+        }
+        Self::write_text(writer, &note.text[written..])?;
+        Self::close_tag(writer, TagCtx::Note.to_str())
     }
 
-    fn close_tag(writer: &mut Writer<Cursor<Vec<u8>>>, tag: &str) -> WriterResult {
-        let elem = BytesEnd::owned(tag.as_bytes().to_vec());
-        writer.write_event(Event::End(elem))?;
-        writer.write_event(Event::Eof)?;
+    /// Appends the unrecognised attributes which were read with an element.
+    fn with_extensions<'a>(
+        mut attributes: Vec<(&'a str, &'a str)>,
+        extensions: &'a Extensions,
+    ) -> Vec<(&'a str, &'a str)> {
+        attributes.extend(
+            extensions
+                .attributes
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str())),
+        );
+        attributes
+    }
+
+    fn close_tag<W: Write>(writer: &mut XmlOutput<W>, tag: &str) -> WriterResult {
+        writer.close(tag)?;
         Ok(())
     }
 }
 
 impl WriterXliff12 {
-    fn write_body(writer: &mut Writer<Cursor<Vec<u8>>>, file: &TranslationFile) -> WriterResult {
+    fn write_body<W: Write>(
+        writer: &mut XmlOutput<W>,
+        file: &TranslationFile,
+        profile: Profile,
+    ) -> WriterResult {
         Self::open_tag(writer, TagCtx::Body.to_str(), None)?;
+        let target_locale = file.target_locale.as_ref();
+        Self::write_body_items(writer, &file.body, target_locale, profile)?;
+        Self::close_tag(writer, TagCtx::Body.to_str())?;
+        Ok(())
+    }
 
-        for unit in &file.units {
-            match &unit.source {
-                None => (),
-                Some(unit_source) => {
-                    Self::open_tag(
-                        writer,
-                        TagCtx::Unit.to_str(),
-                        Some(Self::unit_attributes(unit)),
-                    )?;
-
-                    Self::open_tag(writer, TagCtx::Source.to_str(), None)?;
-                    Self::write_text(writer, unit_source.text.as_str())?;
-                    Self::close_tag(writer, TagCtx::Source.to_str())?;
-
-                    match &unit.target {
-                        None => (),
-                        Some(unit_target) => {
-                            Self::open_tag(writer, TagCtx::Target.to_str(), None)?;
-                            Self::write_text(writer, unit_target.text.as_str())?;
-                            Self::close_tag(writer, TagCtx::Target.to_str())?;
-                        }
-                    }
+    fn write_body_items<W: Write>(
+        writer: &mut XmlOutput<W>,
+        body: &[BodyItem],
+        target_locale: Option<&Locale>,
+        profile: Profile,
+    ) -> WriterResult {
+        for item in body {
+            match item {
+                BodyItem::Unit(unit) => Self::write_unit(writer, unit, target_locale, profile)?,
+                BodyItem::Group(group) => Self::write_group(writer, group, target_locale, profile)?,
+                BodyItem::BinUnit(bin_unit) => Self::write_bin_unit(writer, bin_unit, profile)?,
+                BodyItem::Extension(markup) => {
+                    writer.raw(markup)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_group<W: Write>(
+        writer: &mut XmlOutput<W>,
+        group: &Group,
+        target_locale: Option<&Locale>,
+        profile: Profile,
+    ) -> WriterResult {
+        let mut attributes = group.attributes();
+        if let Some(translate) = Self::schema_translate_attribute(group.translate, profile) {
+            attributes.push(translate);
+        }
+        let attributes = Self::with_extensions(attributes, &group.extensions);
+        Self::open_tag(writer, TagCtx::Group.to_str(), Some(attributes))?;
 
-                    match &unit.note {
-                        None => (),
-                        Some(unit_note) => {
-                            Self::open_tag(writer, TagCtx::Note.to_str(), None)?;
-                            Self::write_text(writer, unit_note.text.as_str())?;
-                            Self::close_tag(writer, TagCtx::Note.to_str())?;
+        // the unrecognised nodes read among the items of the group are kept in its body
+        let children = [
+            (TagCtx::ContextGroup, group.context_groups.len()),
+            (TagCtx::CountGroup, group.count_groups.len()),
+            (TagCtx::PropGroup, group.prop_groups.len()),
+            (TagCtx::Note, group.notes.len()),
+        ];
+        write_children(
+            writer,
+            &group.extensions,
+            &children,
+            |writer, tag, index| {
+                Self::write_group_child(
+                    writer,
+                    tag,
+                    index,
+                    &group.context_groups,
+                    &group.count_groups,
+                    &group.prop_groups,
+                    &group.notes,
+                )
+            },
+        )?;
+        Self::write_body_items(writer, &group.body, target_locale, profile)?;
+
+        Self::close_tag(writer, TagCtx::Group.to_str())
+    }
+
+    fn write_unit<W: Write>(
+        writer: &mut XmlOutput<W>,
+        unit: &Unit,
+        target_locale: Option<&Locale>,
+        profile: Profile,
+    ) -> WriterResult {
+        match &unit.source {
+            None => (),
+            Some(unit_source) => {
+                let attributes = Self::unit_attributes(unit, profile);
+                let attributes = attributes.iter().map(|(k, v)| (*k, v.as_str())).collect();
+                let attributes = Self::with_extensions(attributes, &unit.extensions);
+                Self::open_tag(writer, TagCtx::Unit.to_str(), Some(attributes))?;
+                let children = [
+                    (TagCtx::Source, 1),
+                    (TagCtx::SegSource, unit.seg_source.iter().count()),
+                    (TagCtx::Target, unit.target.iter().count()),
+                    (TagCtx::ContextGroup, unit.context_groups.len()),
+                    (TagCtx::CountGroup, unit.count_groups.len()),
+                    (TagCtx::PropGroup, unit.prop_groups.len()),
+                    (TagCtx::Note, unit.notes.len()),
+                    (TagCtx::AltTrans, unit.alternatives.len()),
+                ];
+                write_children(
+                    writer,
+                    &unit.extensions,
+                    &children,
+                    |writer, tag, index| match (tag, &unit.seg_source, &unit.target) {
+                        (TagCtx::Source, _, _) => {
+                            let attributes = Self::with_extensions(vec![], &unit.source_extensions);
+                            Self::write_value(writer, TagCtx::Source, attributes, unit_source)
                         }
-                    }
+                        (TagCtx::SegSource, Some(seg_source), _) => {
+                            Self::write_value(writer, TagCtx::SegSource, vec![], seg_source)
+                        }
+                        (TagCtx::Target, _, Some(unit_target)) => {
+                            let attributes = Self::with_extensions(
+                                Self::target_attributes(unit, target_locale),
+                                &unit.target_extensions,
+                            );
+                            Self::write_value(writer, TagCtx::Target, attributes, unit_target)
+                        }
+                        (TagCtx::AltTrans, _, _) => {
+                            Self::write_alt_trans(writer, &unit.alternatives[index])
+                        }
+                        _ => Self::write_group_child(
+                            writer,
+                            tag,
+                            index,
+                            &unit.context_groups,
+                            &unit.count_groups,
+                            &unit.prop_groups,
+                            &unit.notes,
+                        ),
+                    },
+                )?;
+
+                Self::close_tag(writer, TagCtx::Unit.to_str())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_bin_unit<W: Write>(
+        writer: &mut XmlOutput<W>,
+        bin_unit: &BinUnit,
+        profile: Profile,
+    ) -> WriterResult {
+        let mut attributes = bin_unit.attributes();
+        if let Some(translate) = Self::schema_translate_attribute(bin_unit.translate, profile) {
+            attributes.push(translate);
+        }
+        let attributes = Self::with_extensions(attributes, &bin_unit.extensions);
+        Self::open_tag(writer, TagCtx::BinUnit.to_str(), Some(attributes))?;
+        let children = [
+            (TagCtx::BinSource, 1),
+            (TagCtx::BinTarget, bin_unit.target.iter().count()),
+            (TagCtx::ContextGroup, bin_unit.context_groups.len()),
+            (TagCtx::CountGroup, bin_unit.count_groups.len()),
+            (TagCtx::PropGroup, bin_unit.prop_groups.len()),
+            (TagCtx::Note, bin_unit.notes.len()),
+        ];
+        write_children(
+            writer,
+            &bin_unit.extensions,
+            &children,
+            |writer, tag, index| match (tag, &bin_unit.target) {
+                (TagCtx::BinSource, _) => {
+                    Self::open_tag(writer, TagCtx::BinSource.to_str(), None)?;
+                    Self::write_bin_file(writer, &bin_unit.source)?;
+                    Self::close_tag(writer, TagCtx::BinSource.to_str())
+                }
+                (TagCtx::BinTarget, Some(bin_target)) => {
+                    Self::open_tag(writer, TagCtx::BinTarget.to_str(), None)?;
+                    Self::write_bin_file(writer, bin_target)?;
+                    Self::close_tag(writer, TagCtx::BinTarget.to_str())
+                }
+                _ => Self::write_group_child(
+                    writer,
+                    tag,
+                    index,
+                    &bin_unit.context_groups,
+                    &bin_unit.count_groups,
+                    &bin_unit.prop_groups,
+                    &bin_unit.notes,
+                ),
+            },
+        )?;
 
-                    Self::close_tag(writer, TagCtx::Unit.to_str())?;
+        Self::close_tag(writer, TagCtx::BinUnit.to_str())
+    }
+
+    fn write_reference_file<W: Write>(
+        writer: &mut XmlOutput<W>,
+        tag: TagCtx,
+        reference: &ReferenceFile,
+    ) -> WriterResult {
+        Self::open_tag(writer, tag.to_str(), None)?;
+        Self::write_bin_file(writer, &reference.file)?;
+        if let Some(note) = &reference.note {
+            Self::write_note(writer, note)?;
+        }
+        Self::close_tag(writer, tag.to_str())
+    }
+
+    /// Writes the data of a `<bin-source>`, `<bin-target>`, `<skl>`, `<glossary>` or
+    /// `<reference>`, encoding it when its form is `base64`. Data which is not valid UTF-8
+    /// text cannot be written as is, so it is encoded in the `base64` form instead.
+    fn write_bin_file<W: Write>(writer: &mut XmlOutput<W>, bin_file: &BinFile) -> WriterResult {
+        match bin_file {
+            BinFile::Internal { form, data } => {
+                let (form, text) = match std::str::from_utf8(data) {
+                    Ok(text) if !bin_file.is_base64() => (form.as_deref(), String::from(text)),
+                    _ => (Some(BASE64_FORM), base64::encode(data)),
+                };
+                let mut attributes = vec![];
+                if let Some(form) = form {
+                    attributes.push(("form", form));
                 }
+                Self::open_tag(writer, TagCtx::InternalFile.to_str(), Some(attributes))?;
+                Self::write_text(writer, text.as_str())?;
+                Self::close_tag(writer, TagCtx::InternalFile.to_str())
             }
+            BinFile::External { href } => Self::empty_tag(
+                writer,
+                TagCtx::ExternalFile.to_str(),
+                vec![("href", href.as_str())],
+            ),
         }
+    }
 
-        Self::close_tag(writer, TagCtx::Body.to_str())?;
-        Ok(())
+    /// Writes the context group, count group, prop group or note of the given index
+    /// among the children of a group or unit.
+    fn write_group_child<W: Write>(
+        writer: &mut XmlOutput<W>,
+        tag: TagCtx,
+        index: usize,
+        context_groups: &[ContextGroup],
+        count_groups: &[CountGroup],
+        prop_groups: &[PropGroup],
+        notes: &[Note],
+    ) -> WriterResult {
+        match tag {
+            TagCtx::ContextGroup => Self::write_context_group(writer, &context_groups[index]),
+            TagCtx::CountGroup => Self::write_count_group(writer, &count_groups[index]),
+            TagCtx::PropGroup => Self::write_prop_group(writer, &prop_groups[index]),
+            TagCtx::Note => Self::write_note(writer, &notes[index]),
+            _ => Ok(()),
+        }
     }
 
-    fn unit_attributes(unit: &Unit) -> Vec<(&str, &str)> {
-        vec![
-            ("id", unit.id.as_str()),
-            ("translate", Self::unit_translate_value(unit)),
-            ("xml:space", "preserve"),
-        ]
+    fn write_context_group<W: Write>(
+        writer: &mut XmlOutput<W>,
+        group: &ContextGroup,
+    ) -> WriterResult {
+        Self::open_tag(
+            writer,
+            TagCtx::ContextGroup.to_str(),
+            Some(group.attributes()),
+        )?;
+        for context in &group.contexts {
+            Self::open_tag(writer, TagCtx::Context.to_str(), Some(context.attributes()))?;
+            Self::write_text(writer, context.value.as_str())?;
+            Self::close_tag(writer, TagCtx::Context.to_str())?;
+        }
+        Self::close_tag(writer, TagCtx::ContextGroup.to_str())
     }
 
-    fn unit_translate_value(unit: &Unit) -> &str {
-        match unit.translate {
-            true => "true",
-            false => "false",
+    fn write_count_group<W: Write>(writer: &mut XmlOutput<W>, group: &CountGroup) -> WriterResult {
+        Self::open_tag(
+            writer,
+            TagCtx::CountGroup.to_str(),
+            Some(group.attributes()),
+        )?;
+        for count in &group.counts {
+            Self::open_tag(writer, TagCtx::Count.to_str(), Some(count.attributes()))?;
+            Self::write_text(writer, count.value.to_string().as_str())?;
+            Self::close_tag(writer, TagCtx::Count.to_str())?;
         }
+        Self::close_tag(writer, TagCtx::CountGroup.to_str())
     }
-}
 
-impl WriterXliff12 {
-    fn write_header(
-        mut writer: &mut Writer<Cursor<Vec<u8>>>,
-        file: &TranslationFile,
+    fn write_prop_group<W: Write>(writer: &mut XmlOutput<W>, group: &PropGroup) -> WriterResult {
+        Self::open_tag(writer, TagCtx::PropGroup.to_str(), Some(group.attributes()))?;
+        for prop in &group.props {
+            Self::open_tag(writer, TagCtx::Prop.to_str(), Some(prop.attributes()))?;
+            Self::write_text(writer, prop.value.as_str())?;
+            Self::close_tag(writer, TagCtx::Prop.to_str())?;
+        }
+        Self::close_tag(writer, TagCtx::PropGroup.to_str())
+    }
+
+    fn write_alt_trans<W: Write>(
+        writer: &mut XmlOutput<W>,
+        alternative: &AltTrans,
     ) -> WriterResult {
-        if let Some(file_header) = &file.header {
-            Self::open_tag(&mut writer, TagCtx::Header.to_str(), None)?;
-            for tool in &file_header.tools {
-                Self::open_tag(&mut writer, TagCtx::Tool.to_str(), Some(tool.attributes()))?;
-                Self::close_tag(&mut writer, TagCtx::Tool.to_str())?;
+        let attributes = Self::with_extensions(alternative.attributes(), &alternative.extensions);
+        Self::open_tag(writer, TagCtx::AltTrans.to_str(), Some(attributes))?;
+        let children = [
+            (TagCtx::Source, alternative.source.iter().count()),
+            (TagCtx::SegSource, alternative.seg_source.iter().count()),
+            (TagCtx::Target, 1),
+            (TagCtx::ContextGroup, alternative.context_groups.len()),
+            (TagCtx::PropGroup, alternative.prop_groups.len()),
+            (TagCtx::Note, alternative.notes.len()),
+        ];
+        write_children(
+            writer,
+            &alternative.extensions,
+            &children,
+            |writer, tag, index| match (tag, &alternative.source, &alternative.seg_source) {
+                (TagCtx::Source, Some(alt_source), _) => {
+                    let attributes = Self::with_extensions(vec![], &alternative.source_extensions);
+                    Self::write_value(writer, TagCtx::Source, attributes, alt_source)
+                }
+                (TagCtx::SegSource, _, Some(seg_source)) => {
+                    Self::write_value(writer, TagCtx::SegSource, vec![], seg_source)
+                }
+                (TagCtx::Target, _, _) => {
+                    let mut attributes = vec![];
+                    if let Some(locale) = &alternative.target_locale {
+                        attributes.push(("xml:lang", locale.identifier.as_str()));
+                    }
+                    if let Some(state) = &alternative.state {
+                        attributes.push(("state", state.as_str()));
+                    }
+                    let attributes =
+                        Self::with_extensions(attributes, &alternative.target_extensions);
+                    Self::write_value(writer, TagCtx::Target, attributes, &alternative.target)
+                }
+                _ => Self::write_group_child(
+                    writer,
+                    tag,
+                    index,
+                    &alternative.context_groups,
+                    &[],
+                    &alternative.prop_groups,
+                    &alternative.notes,
+                ),
+            },
+        )?;
+
+        Self::close_tag(writer, TagCtx::AltTrans.to_str())
+    }
+
+    /// Writes a `<source>`, `<seg-source>` or `<target>` element holding the given value.
+    fn write_value<W: Write>(
+        writer: &mut XmlOutput<W>,
+        tag: TagCtx,
+        attributes: Vec<(&str, &str)>,
+        value: &UnitValue,
+    ) -> WriterResult {
+        Self::open_tag(writer, tag.to_str(), Some(attributes))?;
+        Self::write_content(writer, &value.content)?;
+        Self::close_tag(writer, tag.to_str())
+    }
+
+    fn unit_attributes(unit: &Unit, profile: Profile) -> Vec<(&str, String)> {
+        let mut attributes = vec![("id", unit.id.clone())];
+        let translate = Self::translate_attribute(unit.translate, profile)
+            .map(|(name, value)| (name, String::from(value)));
+        if profile == Profile::Standard {
+            attributes.extend(translate.clone());
+        }
+        attributes.push(("xml:space", String::from("preserve")));
+        if profile == Profile::Xcode {
+            attributes.extend(translate);
+        }
+        if unit.approved {
+            attributes.push(("approved", String::from("yes")));
+        }
+        let optional = [
+            ("resname", unit.resname.clone()),
+            ("restype", unit.restype.clone()),
+            ("datatype", unit.data_type.clone()),
+            ("maxwidth", unit.max_width.map(|width| width.to_string())),
+            ("minwidth", unit.min_width.map(|width| width.to_string())),
+            ("size-unit", unit.size_unit.clone()),
+            ("charclass", unit.char_class.clone()),
+        ];
+        for (name, value) in optional.iter() {
+            if let Some(value) = value {
+                attributes.push((*name, value.clone()));
             }
-            for note in &file_header.notes {
-                Self::open_tag(&mut writer, TagCtx::Note.to_str(), None)?;
-                Self::write_text(&mut writer, note.text.as_str())?;
-                Self::close_tag(&mut writer, TagCtx::Note.to_str())?;
+        }
+        attributes
+    }
+
+    /// The attributes of the `<target>` of a unit, including its language when it differs from
+    /// the `target-language` of the file.
+    fn target_attributes<'a>(
+        unit: &'a Unit,
+        file_locale: Option<&Locale>,
+    ) -> Vec<(&'a str, &'a str)> {
+        let mut attributes = vec![];
+        if let Some(locale) = &unit.target_locale {
+            if file_locale != Some(locale) {
+                attributes.push(("xml:lang", locale.identifier.as_str()));
             }
-            Self::close_tag(&mut writer, TagCtx::Header.to_str())?;
+        }
+        if let Some(state) = &unit.state {
+            attributes.push(("state", state.as_str()));
+        }
+        if let Some(state_qualifier) = &unit.state_qualifier {
+            attributes.push(("state-qualifier", state_qualifier.as_str()));
+        }
+        if let Some(phase_name) = &unit.phase_name {
+            attributes.push(("phase-name", phase_name.as_str()));
+        }
+        attributes
+    }
+
+    /// The `translate` attribute of a `<group>` or `<bin-unit>`, written with the `yes` and `no`
+    /// values of the schema. Xcode only writes it as `no` for elements which are not to be
+    /// translated.
+    fn schema_translate_attribute(
+        translate: bool,
+        profile: Profile,
+    ) -> Option<(&'static str, &'static str)> {
+        match (translate, profile) {
+            (true, Profile::Xcode) => None,
+            (true, Profile::Standard) => Some(("translate", "yes")),
+            (false, _) => Some(("translate", "no")),
+        }
+    }
+
+    /// The `translate` attribute of a `<trans-unit>`, which the standard profile has always
+    /// written as `true` or `false`. Xcode only writes it as `no` for units which are not to be
+    /// translated.
+    fn translate_attribute(
+        translate: bool,
+        profile: Profile,
+    ) -> Option<(&'static str, &'static str)> {
+        match (translate, profile) {
+            (true, Profile::Xcode) => None,
+            (false, Profile::Xcode) => Some(("translate", "no")),
+            (true, Profile::Standard) => Some(("translate", "true")),
+            (false, Profile::Standard) => Some(("translate", "false")),
+        }
+    }
+}
+
+impl WriterXliff12 {
+    /// Writes the header of a file, which is the first of its recognised children.
+    fn write_header<W: Write>(writer: &mut XmlOutput<W>, file: &TranslationFile) -> WriterResult {
+        if let Some(file_header) = &file.header {
+            let attributes = Self::with_extensions(vec![], &file_header.extensions);
+            Self::open_tag(writer, TagCtx::Header.to_str(), Some(attributes))?;
+            let children = [
+                (TagCtx::Skl, file_header.skeleton.iter().count()),
+                (
+                    TagCtx::PhaseGroup,
+                    usize::from(!file_header.phases.is_empty()),
+                ),
+                (TagCtx::Glossary, file_header.glossaries.len()),
+                (TagCtx::Reference, file_header.references.len()),
+                (TagCtx::Tool, file_header.tools.len()),
+                (TagCtx::CountGroup, file_header.count_groups.len()),
+                (TagCtx::PropGroup, file_header.prop_groups.len()),
+                (TagCtx::Note, file_header.notes.len()),
+            ];
+            write_children(
+                writer,
+                &file_header.extensions,
+                &children,
+                |writer, tag, index| match (tag, &file_header.skeleton) {
+                    (TagCtx::Skl, Some(skeleton)) => {
+                        Self::open_tag(writer, TagCtx::Skl.to_str(), None)?;
+                        Self::write_bin_file(writer, skeleton)?;
+                        Self::close_tag(writer, TagCtx::Skl.to_str())
+                    }
+                    (TagCtx::PhaseGroup, _) => {
+                        Self::open_tag(writer, TagCtx::PhaseGroup.to_str(), None)?;
+                        for phase in &file_header.phases {
+                            let attributes =
+                                Self::with_extensions(phase.attributes(), &phase.extensions);
+                            Self::open_tag(writer, TagCtx::Phase.to_str(), Some(attributes))?;
+                            let notes = [(TagCtx::Note, phase.notes.len())];
+                            write_children(
+                                writer,
+                                &phase.extensions,
+                                &notes,
+                                |writer, _, index| Self::write_note(writer, &phase.notes[index]),
+                            )?;
+                            Self::close_tag(writer, TagCtx::Phase.to_str())?;
+                        }
+                        Self::close_tag(writer, TagCtx::PhaseGroup.to_str())
+                    }
+                    (TagCtx::Glossary, _) => Self::write_reference_file(
+                        writer,
+                        TagCtx::Glossary,
+                        &file_header.glossaries[index],
+                    ),
+                    (TagCtx::Reference, _) => Self::write_reference_file(
+                        writer,
+                        TagCtx::Reference,
+                        &file_header.references[index],
+                    ),
+                    (TagCtx::Tool, _) => {
+                        let tool = &file_header.tools[index];
+                        let attributes = Self::with_extensions(tool.attributes(), &tool.extensions);
+                        Self::open_tag(writer, TagCtx::Tool.to_str(), Some(attributes))?;
+                        ExtensionNodes::new(&tool.extensions).finish(writer)?;
+                        Self::close_tag(writer, TagCtx::Tool.to_str())
+                    }
+                    _ => Self::write_group_child(
+                        writer,
+                        tag,
+                        index,
+                        &[],
+                        &file_header.count_groups,
+                        &file_header.prop_groups,
+                        &file_header.notes,
+                    ),
+                },
+            )?;
+            Self::close_tag(writer, TagCtx::Header.to_str())?;
         }
         Ok(())
     }
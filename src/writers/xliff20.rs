@@ -0,0 +1,856 @@
+//! Defines XLIFF 2.0 compatible output writer
+//!
+//! XLIFF 1.2 concepts held in a `Store` are written to their XLIFF 2.0 equivalent when there is
+//! one. Data without an equivalent is written to `<mda:metaGroup>` elements of the
+//! [Metadata module](http://docs.oasis-open.org/xliff/xliff-core/v2.0/os/xliff-core-v2.0-os.html#metadata_module)
+//! placed in the `<file>`, `<group>` or `<unit>` it belongs to, so it is not lost. Each
+//! `<mda:meta>` is named after the XLIFF 1.2 attribute, or after the type of the child element,
+//! it holds. `ReaderXliff20` reads these elements back into the store.
+//!
+//! | XLIFF 1.2                         | XLIFF 2.0                                                     |
+//! |-----------------------------------|---------------------------------------------------------------|
+//! | `<file original>`                 | `original` attribute of the `<file>`                          |
+//! | `<file datatype>`                 | `<mda:metaGroup category="datatype">` with a `datatype` meta  |
+//! | `<header>/<tool>`                 | `<mda:metaGroup category="tool">` with `tool-id`, `tool-name`, `tool-version`, `tool-company` and unrecognised attribute metas |
+//! | `<header>/<note>`                 | `<notes>/<note>` of the `<file>`                              |
+//! | `<group id resname translate>`    | `<group id name translate>`, the first of `g1`, `g2`, ... which is not the `id` of another group or unit when it has no `id` |
+//! | `<group restype>`                 | `<mda:metaGroup category="group">` with a `restype` meta      |
+//! | `<trans-unit resname translate>`  | `<unit name translate>`, `translate="no"` when disabled       |
+//! | `restype`, `datatype`, `maxwidth`, `minwidth`, `size-unit`, `charclass`, `approved` of a `<trans-unit>` | `<mda:metaGroup category="trans-unit">` with a meta for each attribute |
+//! | `<target state>`                  | `state` of the `<segment>`: `initial`, `translated`, `reviewed` or `final` |
+//! | `<target state state-qualifier phase-name>` | `<mda:metaGroup category="target">`, `state` only when the `<segment>` state does not match it |
+//! | `<target xml:lang>`               | `trgLang` of the `<xliff>`, an error is returned when they differ |
+//! | `<note priority annotates>`       | `priority` and `appliesTo` of the `<note>`                    |
+//! | `<context-group>`                 | `<mda:metaGroup category="context-group">` with `name` and `purpose` metas and a meta for each `context-type` |
+//! | `<count-group>`                   | `<mda:metaGroup category="count-group">` with a `name` meta and a `count-type:unit` meta for each count |
+//! | `<prop-group>`                    | `<mda:metaGroup category="prop-group">` with a `name` meta and a meta for each `prop-type` |
+//! | unrecognised attributes of `<file>`, `<group>` and `<trans-unit>` | `<mda:metaGroup category="attributes">` with a meta for each attribute |
+//! | namespace declarations of `<xliff>` | namespace declarations of `<xliff>`                         |
+//! | `<g>`                             | `<pc>`                                                        |
+//! | `<x/>`, `<bx/>`, `<ex/>`          | `<ph/>`, `<sc/>`, `<ec/>`                                     |
+//! | `<ph>`, `<bpt>`, `<ept>`, `<it>`  | `<ph/>`, `<sc/>`, `<ec/>`, isolated for `<it>`, referring to their native code in the `<originalData>` of the unit |
+//! | `<ex/>`, `<ept>` ending a code    | `<ec startRef>` referring to the `id` of the code with the same `rid`, or else the same `id`, isolated when there is none |
+//! | `<mrk mid mtype comment>`         | `<mrk id type translate value>`: `term` type, `translate="no"` when `protected`, `comment` type with its `value` |
+//! | `ctype` of inline codes           | `type` and `subType`: `fmt` with `xlf:b`, `xlf:i`, `xlf:u` or `xlf:lb`, `link` or `image` |
+//! | `equiv-text` of inline codes      | `equiv`                                                       |
+//! | comments and processing instructions within content | written as is                               |
+//! | `<note from xml:lang>`            | not written                                                   |
+//! | `<context match-mandatory>`, `<count phase-name>`, `<prop xml:lang>` | not written            |
+//! | `<alt-trans>`                     | not written                                                   |
+//! | `<bin-unit>`                      | not written                                                   |
+//! | `<skl>`                           | not written                                                   |
+//! | `<phase-group>`                   | not written                                                   |
+//! | `<glossary>`, `<reference>`       | not written                                                   |
+//! | `<seg-source>`                    | not written, `<target>` holds the content of all its segments |
+//! | `<sub>` within native code, other `ctype` and `mtype` values and attributes of inline codes | not written |
+//! | other unrecognised elements, attributes and comments | not written                              |
+//!
+//! Data which is not written is reported before anything is written, see `write_checked` and
+//! `conversion::losses`.
+//!
+//! The `source-language` and `target-language` of each file become the `srcLang` and `trgLang`
+//! attributes of the `<xliff>` element, as XLIFF 2.0 documents contain a single language pair.
+//! Writing fails when the files do not share a single language pair, or when no file has a
+//! `source-language` as `srcLang` is required.
+//! Files without an identifier are given one based on their position in the store.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::Write;
+
+use super::conversion::{
+    code_attributes, has_native_code, is_comment_or_pi, is_namespace_declaration, losses,
+    native_code, ConversionError, Loss,
+};
+use super::options::WriterOptions;
+use super::output::XmlOutput;
+pub use super::traits::XliffWriter;
+
+use crate::store::{
+    Annotates, BodyItem, ContentPart, ContextGroup, CountGroup, Extensions, Group, InlineCode,
+    InlineKind, Locale, Note, PropGroup, Store, TagCtx, TargetState, TranslationFile, Unit,
+};
+
+type WriterResult = Result<(), Box<dyn Error>>;
+
+/// The category and the metas of an `<mda:metaGroup>` element.
+type MetaGroup = (&'static str, Vec<(String, String)>);
+
+/// Namespace of the XLIFF 2.0 Metadata module.
+const METADATA_NAMESPACE: &str = "urn:oasis:names:tc:xliff:metadata:2.0";
+
+/// Elements whose content is written without indentation.
+const CONTENT_ELEMENTS: &[&str] = &["source", "target", "note", "mda:meta", "data"];
+
+/// XLIFF 2.0 compatible output writer
+#[derive(Default)]
+pub struct WriterXliff20 {
+    /// Controls how the document is formatted.
+    pub options: WriterOptions,
+    /// Whether a store holding data which cannot be written as XLIFF 2.0 is written anyway.
+    /// See `write_checked`.
+    pub lenient: bool,
+}
+
+impl WriterXliff20 {
+    /// Creates a writer formatting documents according to the provided options.
+    pub fn new(options: WriterOptions) -> Self {
+        WriterXliff20 {
+            options,
+            lenient: false,
+        }
+    }
+
+    /// Checks that the store can be written as XLIFF 2.0, then writes it into `w`.
+    ///
+    /// # Errors
+    /// Returns an error when the files do not share a single language pair. Returns a
+    /// `ConversionError` listing the data which has no XLIFF 2.0 equivalent before anything is
+    /// written, unless the writer is lenient. A lenient writer writes the document without this
+    /// data and returns the losses as warnings.
+    pub fn write_checked<W: Write>(
+        &self,
+        store: &Store,
+        w: W,
+    ) -> Result<Vec<Loss>, Box<dyn Error>> {
+        let source_locale = Self::language(store, |f| &f.source_locale, "source-language")?
+            .ok_or("XLIFF 2.0 requires a srcLang, but no file has a source-language")?;
+        let target_locale = Self::language(store, |f| &f.target_locale, "target-language")?;
+        Self::check_target_languages(store, target_locale)?;
+
+        let losses = losses(store);
+        if !losses.is_empty() && !self.lenient {
+            return Err(Box::new(ConversionError { losses }));
+        }
+
+        let mut writer = XmlOutput::new(w, &self.options, CONTENT_ELEMENTS);
+        Self::print_envelope(&mut writer, store, source_locale, target_locale)?;
+
+        for (ix, file) in store.groups.iter().enumerate() {
+            let id = match &file.id {
+                Some(id) => id.clone(),
+                None => format!("f{}", ix + 1),
+            };
+            let mut attributes = vec![("id", id.as_str())];
+            if !file.address.is_empty() && file.address != id {
+                attributes.push(("original", file.address.as_str()));
+            }
+
+            Self::open_tag(&mut writer, TagCtx::File.to_str_v20(), Some(attributes))?;
+            Self::write_metadata(&mut writer, Self::file_meta_groups(file))?;
+            Self::write_file_notes(&mut writer, file)?;
+            let mut group_ids = GroupIds::new(&file.body);
+            Self::write_body_items(&mut writer, &file.body, &mut group_ids)?;
+            Self::close_tag(&mut writer, TagCtx::File.to_str_v20())?;
+        }
+
+        Self::close_tag(&mut writer, TagCtx::Xliff.to_str_v20())?;
+
+        writer.finish()?;
+        Ok(losses)
+    }
+}
+
+impl XliffWriter for WriterXliff20 {
+    /// Checks the store, then writes it into `w`. See `write_checked`.
+    fn write_to<W: Write>(&self, store: &Store, w: W) -> Result<(), Box<dyn Error>> {
+        self.write_checked(store, w)?;
+        Ok(())
+    }
+}
+
+/// Identifies the groups of a file which have no `id`.
+struct GroupIds {
+    /// The identifiers of the groups and units of the file, which share a single scope in
+    /// XLIFF 2.0, along with those generated so far.
+    used: HashSet<String>,
+    generated: usize,
+}
+
+impl GroupIds {
+    fn new(body: &[BodyItem]) -> Self {
+        let mut ids = GroupIds {
+            used: HashSet::new(),
+            generated: 0,
+        };
+        ids.collect(body);
+        ids
+    }
+
+    fn collect(&mut self, body: &[BodyItem]) {
+        for item in body {
+            match item {
+                BodyItem::Unit(unit) => {
+                    self.used.insert(unit.id.clone());
+                }
+                BodyItem::Group(group) => {
+                    self.used.extend(group.id.clone());
+                    self.collect(&group.body);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Returns the first of `g1`, `g2`, ... which is not used in the file yet.
+    fn next(&mut self) -> String {
+        loop {
+            self.generated += 1;
+            let id = format!("g{}", self.generated);
+            if self.used.insert(id.clone()) {
+                return id;
+            }
+        }
+    }
+}
+
+/// The state of the `<source>` or `<target>` of a unit being written.
+struct ContentState<'a> {
+    /// The native code of the inline codes of the unit, identified by their position.
+    data: &'a [String],
+    /// The codes starting and ending a paired sequence, with their `id` and `rid`.
+    starts: Vec<(Option<&'a str>, Option<&'a str>)>,
+    ends: Vec<(Option<&'a str>, Option<&'a str>)>,
+    /// The identifiers of the inline codes of the unit.
+    ids: &'a HashSet<&'a str>,
+    generated: usize,
+}
+
+impl<'a> ContentState<'a> {
+    fn new(content: &'a [ContentPart], data: &'a [String], ids: &'a HashSet<&'a str>) -> Self {
+        let mut state = ContentState {
+            data,
+            starts: vec![],
+            ends: vec![],
+            ids,
+            generated: 0,
+        };
+        state.collect_pairs(content);
+        state
+    }
+
+    fn collect_pairs(&mut self, content: &'a [ContentPart]) {
+        for part in content {
+            if let ContentPart::Code(code) = part {
+                let ids = (code.id.as_deref(), code.attribute("rid"));
+                match code.kind {
+                    InlineKind::Bx | InlineKind::Bpt => self.starts.push(ids),
+                    InlineKind::Ex | InlineKind::Ept => self.ends.push(ids),
+                    InlineKind::G | InlineKind::Mrk => self.collect_pairs(&code.content),
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    /// The identifier of the code starting the sequence ended by the given code, if any.
+    fn start_of(&self, end: &InlineCode) -> Option<&'a str> {
+        let (id, rid) = (end.id.as_deref(), end.attribute("rid"));
+        self.starts
+            .iter()
+            .find(|start| is_pair(**start, (id, rid)))
+            .and_then(|(start_id, _)| *start_id)
+    }
+
+    /// Whether the code starting a sequence is ended within the same content.
+    fn is_ended(&self, start: &InlineCode) -> bool {
+        let (id, rid) = (start.id.as_deref(), start.attribute("rid"));
+        self.ends.iter().any(|end| is_pair((id, rid), *end))
+    }
+
+    /// The identifier of a code, generated when it has none. The codes of the `<source>` and
+    /// `<target>` are given the same identifiers in the same order.
+    fn id(&mut self, id: Option<&str>) -> String {
+        if let Some(id) = id {
+            return String::from(id);
+        }
+        loop {
+            self.generated += 1;
+            let id = self.generated.to_string();
+            if !self.ids.contains(id.as_str()) {
+                return id;
+            }
+        }
+    }
+
+    /// The identifier of the `<data>` holding the native code of the given code.
+    fn data_ref(&self, code: &InlineCode) -> Option<String> {
+        let native = native_code(code);
+        self.data
+            .iter()
+            .position(|data| *data == native)
+            .map(|ix| format!("d{}", ix + 1))
+    }
+}
+
+/// Whether a code starting a sequence and a code ending one, given with their `id` and `rid`,
+/// are paired: by their `rid` when both have one, or else by their `id`.
+fn is_pair(start: (Option<&str>, Option<&str>), end: (Option<&str>, Option<&str>)) -> bool {
+    match (start.1, end.1) {
+        (Some(start_rid), Some(end_rid)) => start_rid == end_rid,
+        _ => start.0.is_some() && start.0 == end.0,
+    }
+}
+
+impl WriterXliff20 {
+    /// Returns the language shared by all files in the store.
+    fn language<'a, F>(
+        store: &'a Store,
+        locale: F,
+        attribute: &str,
+    ) -> Result<Option<&'a str>, Box<dyn Error>>
+    where
+        F: Fn(&'a TranslationFile) -> &'a Option<Locale>,
+    {
+        let mut language: Option<&str> = None;
+
+        for file in &store.groups {
+            if let Some(file_locale) = locale(file) {
+                match language {
+                    Some(l) if l != file_locale.identifier => {
+                        return Err(format!(
+                            "XLIFF 2.0 requires all files to have the same {}, found `{}` and `{}`",
+                            attribute, l, file_locale.identifier
+                        )
+                        .into());
+                    }
+                    _ => language = Some(file_locale.identifier.as_str()),
+                }
+            }
+        }
+
+        Ok(language)
+    }
+
+    /// Checks that the translated units have no target language other than `trgLang`.
+    fn check_target_languages(store: &Store, target_locale: Option<&str>) -> WriterResult {
+        for unit in store.groups.iter().flat_map(|file| file.units()) {
+            match &unit.target_locale {
+                Some(locale)
+                    if unit.target.is_some() && target_locale != Some(&locale.identifier) =>
+                {
+                    return Err(format!(
+                        "XLIFF 2.0 requires all targets to be in the trgLang `{}`, found `{}` in unit `{}`",
+                        target_locale.unwrap_or_default(),
+                        locale.identifier,
+                        unit.id
+                    )
+                    .into());
+                }
+                _ => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn print_envelope<W: Write>(
+        writer: &mut XmlOutput<W>,
+        store: &Store,
+        source_locale: &str,
+        target_locale: Option<&str>,
+    ) -> WriterResult {
+        // header <?xml version="1.0" encoding="UTF-8"?>
+        writer.declaration()?;
+
+        let mut attributes = vec![
+            ("xmlns", "urn:oasis:names:tc:xliff:document:2.0"),
+            ("xmlns:mda", METADATA_NAMESPACE),
+            ("version", "2.0"),
+            ("srcLang", source_locale),
+        ];
+        if let Some(target_locale) = target_locale {
+            attributes.push(("trgLang", target_locale));
+        }
+        // the prefixes declared in the original document may be used by the attribute metas
+        for (name, value) in &store.extensions.attributes {
+            if is_namespace_declaration(name) && name != "xmlns:mda" {
+                attributes.push((name.as_str(), value.as_str()));
+            }
+        }
+
+        Self::open_tag(writer, TagCtx::Xliff.to_str_v20(), Some(attributes))
+    }
+}
+
+impl WriterXliff20 {
+    fn open_tag<W: Write>(
+        writer: &mut XmlOutput<W>,
+        tag: &str,
+        attributes: Option<Vec<(&str, &str)>>,
+    ) -> WriterResult {
+        writer.open(tag, &attributes.unwrap_or_default())?;
+        Ok(())
+    }
+
+    fn write_text<W: Write>(writer: &mut XmlOutput<W>, text: &str) -> WriterResult {
+        writer.text(text)?;
+        Ok(())
+    }
+
+    fn close_tag<W: Write>(writer: &mut XmlOutput<W>, tag: &str) -> WriterResult {
+        writer.close(tag)?;
+        Ok(())
+    }
+
+    fn write_meta<W: Write>(
+        writer: &mut XmlOutput<W>,
+        meta_type: &str,
+        value: &str,
+    ) -> WriterResult {
+        Self::open_tag(
+            writer,
+            TagCtx::Meta.to_str_v20(),
+            Some(vec![("type", meta_type)]),
+        )?;
+        Self::write_text(writer, value)?;
+        Self::close_tag(writer, TagCtx::Meta.to_str_v20())
+    }
+}
+
+impl WriterXliff20 {
+    fn write_metadata<W: Write>(writer: &mut XmlOutput<W>, groups: Vec<MetaGroup>) -> WriterResult {
+        if groups.is_empty() {
+            return Ok(());
+        }
+
+        Self::open_tag(writer, TagCtx::Metadata.to_str_v20(), None)?;
+        for (category, metas) in groups {
+            Self::open_tag(
+                writer,
+                TagCtx::MetaGroup.to_str_v20(),
+                Some(vec![("category", category)]),
+            )?;
+            for (meta_type, value) in &metas {
+                Self::write_meta(writer, meta_type, value)?;
+            }
+            Self::close_tag(writer, TagCtx::MetaGroup.to_str_v20())?;
+        }
+        Self::close_tag(writer, TagCtx::Metadata.to_str_v20())
+    }
+
+    fn file_meta_groups(file: &TranslationFile) -> Vec<MetaGroup> {
+        let mut groups = vec![];
+        if !file.data_type.is_empty() {
+            groups.push(("datatype", metas(&[("datatype", Some(&file.data_type))])));
+        }
+        groups.extend(Self::attribute_meta_group(&file.extensions));
+        if let Some(header) = &file.header {
+            for tool in &header.tools {
+                let mut tool_metas: Vec<(String, String)> = tool
+                    .attributes()
+                    .into_iter()
+                    .map(|(meta_type, value)| (String::from(meta_type), String::from(value)))
+                    .collect();
+                tool_metas.extend(tool.extensions.attributes.iter().cloned());
+                groups.push(("tool", tool_metas));
+            }
+            groups.extend(Self::annotation_meta_groups(
+                &[],
+                &header.count_groups,
+                &header.prop_groups,
+            ));
+        }
+        groups
+    }
+
+    fn group_meta_groups(group: &Group) -> Vec<MetaGroup> {
+        let mut groups = vec![];
+        if group.restype.is_some() {
+            groups.push(("group", metas(&[("restype", group.restype.as_ref())])));
+        }
+        groups.extend(Self::attribute_meta_group(&group.extensions));
+        groups.extend(Self::annotation_meta_groups(
+            &group.context_groups,
+            &group.count_groups,
+            &group.prop_groups,
+        ));
+        groups
+    }
+
+    fn unit_meta_groups(unit: &Unit) -> Vec<MetaGroup> {
+        let mut groups = vec![];
+        let max_width = unit.max_width.map(|width| width.to_string());
+        let min_width = unit.min_width.map(|width| width.to_string());
+        let approved = match unit.approved {
+            true => Some(String::from("yes")),
+            false => None,
+        };
+        let unit_metas = metas(&[
+            ("restype", unit.restype.as_ref()),
+            ("datatype", unit.data_type.as_ref()),
+            ("maxwidth", max_width.as_ref()),
+            ("minwidth", min_width.as_ref()),
+            ("size-unit", unit.size_unit.as_ref()),
+            ("charclass", unit.char_class.as_ref()),
+            ("approved", approved.as_ref()),
+        ]);
+        if !unit_metas.is_empty() {
+            groups.push(("trans-unit", unit_metas));
+        }
+        groups.extend(Self::attribute_meta_group(&unit.extensions));
+
+        if unit.target.is_some() {
+            // the exact state is only needed when the state of the segment does not match it
+            let state = unit
+                .state
+                .as_ref()
+                .filter(|state| {
+                    state.to_str_v20().and_then(TargetState::from_v20).as_ref() != Some(state)
+                })
+                .map(|state| String::from(state.as_str()));
+            let state_qualifier = unit
+                .state_qualifier
+                .as_ref()
+                .map(|qualifier| String::from(qualifier.as_str()));
+            let target_metas = metas(&[
+                ("state", state.as_ref()),
+                ("state-qualifier", state_qualifier.as_ref()),
+                ("phase-name", unit.phase_name.as_ref()),
+            ]);
+            if !target_metas.is_empty() {
+                groups.push(("target", target_metas));
+            }
+        }
+
+        groups.extend(Self::annotation_meta_groups(
+            &unit.context_groups,
+            &unit.count_groups,
+            &unit.prop_groups,
+        ));
+        groups
+    }
+
+    /// The metadata group holding the unrecognised attributes of an element, if it has any.
+    fn attribute_meta_group(extensions: &Extensions) -> Option<MetaGroup> {
+        match extensions.attributes.is_empty() {
+            true => None,
+            false => Some(("attributes", extensions.attributes.clone())),
+        }
+    }
+
+    /// The metadata groups holding the context, count and property groups of an element.
+    fn annotation_meta_groups(
+        context_groups: &[ContextGroup],
+        count_groups: &[CountGroup],
+        prop_groups: &[PropGroup],
+    ) -> Vec<MetaGroup> {
+        let mut groups = vec![];
+        for group in context_groups {
+            let mut group_metas = metas(&[
+                ("name", group.name.as_ref()),
+                ("purpose", group.purpose.as_ref()),
+            ]);
+            for context in &group.contexts {
+                group_metas.push((context.context_type.clone(), context.value.clone()));
+            }
+            groups.push(("context-group", group_metas));
+        }
+        for group in count_groups {
+            let mut group_metas = metas(&[("name", Some(&group.name))]);
+            for count in &group.counts {
+                let mut count_type = count.count_type.clone().unwrap_or_default();
+                if let Some(unit) = &count.unit {
+                    count_type = format!("{}:{}", count_type, unit);
+                }
+                group_metas.push((count_type, count.value.to_string()));
+            }
+            groups.push(("count-group", group_metas));
+        }
+        for group in prop_groups {
+            let mut group_metas = metas(&[("name", group.name.as_ref())]);
+            for prop in &group.props {
+                group_metas.push((prop.prop_type.clone(), prop.value.clone()));
+            }
+            groups.push(("prop-group", group_metas));
+        }
+        groups
+    }
+
+    fn write_file_notes<W: Write>(
+        writer: &mut XmlOutput<W>,
+        file: &TranslationFile,
+    ) -> WriterResult {
+        match &file.header {
+            Some(header) => Self::write_notes(writer, &header.notes),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes a `<notes>` element, `from` and `xml:lang` have no equivalent in XLIFF 2.0.
+    fn write_notes<W: Write>(writer: &mut XmlOutput<W>, notes: &[Note]) -> WriterResult {
+        if notes.is_empty() {
+            return Ok(());
+        }
+
+        Self::open_tag(writer, TagCtx::Notes.to_str_v20(), None)?;
+        for note in notes {
+            let priority = note.priority.map(|priority| priority.to_string());
+            let mut attributes = vec![];
+            if let Some(priority) = &priority {
+                attributes.push(("priority", priority.as_str()));
+            }
+            match note.annotates {
+                Some(Annotates::Source) => attributes.push(("appliesTo", "source")),
+                Some(Annotates::Target) => attributes.push(("appliesTo", "target")),
+                _ => (),
+            }
+
+            Self::open_tag(writer, TagCtx::Note.to_str_v20(), Some(attributes))?;
+            Self::write_text(writer, note.text.as_str())?;
+            Self::close_tag(writer, TagCtx::Note.to_str_v20())?;
+        }
+        Self::close_tag(writer, TagCtx::Notes.to_str_v20())
+    }
+
+    /// Writes the units and groups of a body. Binary units and unrecognised nodes are reported
+    /// by `conversion::losses`.
+    fn write_body_items<W: Write>(
+        writer: &mut XmlOutput<W>,
+        body: &[BodyItem],
+        group_ids: &mut GroupIds,
+    ) -> WriterResult {
+        for item in body {
+            match item {
+                BodyItem::Unit(unit) => Self::write_unit(writer, unit)?,
+                BodyItem::Group(group) => Self::write_group(writer, group, group_ids)?,
+                BodyItem::BinUnit(_) | BodyItem::Extension(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn write_group<W: Write>(
+        writer: &mut XmlOutput<W>,
+        group: &Group,
+        group_ids: &mut GroupIds,
+    ) -> WriterResult {
+        let id = match &group.id {
+            Some(id) => id.clone(),
+            None => group_ids.next(),
+        };
+        let mut attributes = vec![("id", id.as_str())];
+        if let Some(resname) = &group.resname {
+            attributes.push(("name", resname.as_str()));
+        }
+        if !group.translate {
+            attributes.push(("translate", "no"));
+        }
+
+        Self::open_tag(writer, TagCtx::Group.to_str_v20(), Some(attributes))?;
+        Self::write_metadata(writer, Self::group_meta_groups(group))?;
+        Self::write_notes(writer, &group.notes)?;
+        Self::write_body_items(writer, &group.body, group_ids)?;
+        Self::close_tag(writer, TagCtx::Group.to_str_v20())
+    }
+
+    fn write_unit<W: Write>(writer: &mut XmlOutput<W>, unit: &Unit) -> WriterResult {
+        let unit_source = match &unit.source {
+            None => return Ok(()),
+            Some(unit_source) => unit_source,
+        };
+
+        Self::open_tag(
+            writer,
+            TagCtx::Unit.to_str_v20(),
+            Some(Self::unit_attributes(unit)),
+        )?;
+        Self::write_metadata(writer, Self::unit_meta_groups(unit))?;
+        Self::write_notes(writer, &unit.notes)?;
+
+        let mut data = vec![];
+        collect_native_code(&unit_source.content, &mut data);
+        if let Some(unit_target) = &unit.target {
+            collect_native_code(&unit_target.content, &mut data);
+        }
+        Self::write_original_data(writer, &data)?;
+
+        let mut ids = HashSet::new();
+        collect_code_ids(&unit_source.content, &mut ids);
+        if let Some(unit_target) = &unit.target {
+            collect_code_ids(&unit_target.content, &mut ids);
+        }
+
+        let state = match &unit.target {
+            Some(_) => unit.state.as_ref().and_then(TargetState::to_str_v20),
+            None => None,
+        };
+        let segment_attributes = state.map(|state| vec![("state", state)]);
+        Self::open_tag(writer, TagCtx::Segment.to_str_v20(), segment_attributes)?;
+        let values = [
+            (TagCtx::Source, Some(unit_source)),
+            (TagCtx::Target, unit.target.as_ref()),
+        ];
+        for (tag, value) in values.iter() {
+            if let Some(value) = value {
+                let mut state = ContentState::new(&value.content, &data, &ids);
+                Self::open_tag(writer, tag.to_str_v20(), None)?;
+                Self::write_content(writer, &value.content, &mut state)?;
+                Self::close_tag(writer, tag.to_str_v20())?;
+            }
+        }
+        Self::close_tag(writer, TagCtx::Segment.to_str_v20())?;
+
+        Self::close_tag(writer, TagCtx::Unit.to_str_v20())
+    }
+
+    /// Writes the native code of the inline codes of a unit, the `<data>` elements being
+    /// identified by their position.
+    fn write_original_data<W: Write>(writer: &mut XmlOutput<W>, data: &[String]) -> WriterResult {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        Self::open_tag(writer, TagCtx::OriginalData.to_str_v20(), None)?;
+        for (ix, native) in data.iter().enumerate() {
+            let id = format!("d{}", ix + 1);
+            let attributes = vec![("id", id.as_str())];
+            Self::open_tag(writer, TagCtx::Data.to_str_v20(), Some(attributes))?;
+            Self::write_text(writer, native)?;
+            Self::close_tag(writer, TagCtx::Data.to_str_v20())?;
+        }
+        Self::close_tag(writer, TagCtx::OriginalData.to_str_v20())
+    }
+
+    /// Writes the text and inline codes of a `<source>` or `<target>`.
+    fn write_content<'a, W: Write>(
+        writer: &mut XmlOutput<W>,
+        content: &'a [ContentPart],
+        state: &mut ContentState<'a>,
+    ) -> WriterResult {
+        for part in content {
+            match part {
+                ContentPart::Text(text) => Self::write_text(writer, text)?,
+                ContentPart::Code(code) => Self::write_code(writer, code, state)?,
+                ContentPart::Extension(markup) if is_comment_or_pi(markup) => writer.raw(markup)?,
+                ContentPart::Extension(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn write_code<'a, W: Write>(
+        writer: &mut XmlOutput<W>,
+        code: &'a InlineCode,
+        state: &mut ContentState<'a>,
+    ) -> WriterResult {
+        let (mapped, _) = code_attributes(code);
+        let data_ref = match has_native_code(code) {
+            true => state.data_ref(code),
+            false => None,
+        };
+        let id = match code.kind {
+            InlineKind::Mrk => state.id(code.attribute("mid")),
+            _ => state.id(code.id.as_deref()),
+        };
+
+        let mut attributes = vec![];
+        let tag = match code.kind {
+            InlineKind::G => {
+                attributes.push(("id", id));
+                "pc"
+            }
+            InlineKind::Mrk => {
+                attributes.push(("id", id));
+                "mrk"
+            }
+            InlineKind::X | InlineKind::Ph => {
+                attributes.push(("id", id));
+                "ph"
+            }
+            InlineKind::Bx | InlineKind::Bpt => {
+                attributes.push(("id", id));
+                if !state.is_ended(code) {
+                    attributes.push(("isolated", String::from("yes")));
+                }
+                "sc"
+            }
+            InlineKind::Ex | InlineKind::Ept => {
+                match state.start_of(code) {
+                    Some(start) => attributes.push(("startRef", String::from(start))),
+                    None => {
+                        attributes.push(("id", id));
+                        attributes.push(("isolated", String::from("yes")));
+                    }
+                }
+                "ec"
+            }
+            InlineKind::It => {
+                attributes.push(("id", id));
+                attributes.push(("isolated", String::from("yes")));
+                match code.attribute("pos") {
+                    Some("close") => "ec",
+                    _ => "sc",
+                }
+            }
+            // only found within native code, whose text is all there is to write
+            InlineKind::Sub => return Self::write_content(writer, &code.content, state),
+        };
+        if let Some(data_ref) = data_ref {
+            attributes.push(("dataRef", data_ref));
+        }
+        attributes.extend(mapped);
+        let attributes: Vec<(&str, &str)> = attributes
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+
+        match code.kind {
+            InlineKind::G | InlineKind::Mrk => {
+                Self::open_tag(writer, tag, Some(attributes))?;
+                Self::write_content(writer, &code.content, state)?;
+                Self::close_tag(writer, tag)
+            }
+            _ => {
+                writer.empty(tag, &attributes)?;
+                Ok(())
+            }
+        }
+    }
+
+    fn unit_attributes(unit: &Unit) -> Vec<(&str, &str)> {
+        let mut attributes = vec![("id", unit.id.as_str())];
+        if let Some(resname) = &unit.resname {
+            attributes.push(("name", resname.as_str()));
+        }
+        if !unit.translate {
+            attributes.push(("translate", "no"));
+        }
+        attributes
+    }
+}
+
+/// The metas for the provided values, leaving out those which are not set.
+fn metas(values: &[(&str, Option<&String>)]) -> Vec<(String, String)> {
+    values
+        .iter()
+        .filter_map(|(meta_type, value)| Some((String::from(*meta_type), (*value)?.clone())))
+        .collect()
+}
+
+/// Collects the native code of the inline codes of the content, each distinct code once.
+fn collect_native_code(content: &[ContentPart], data: &mut Vec<String>) {
+    for part in content {
+        if let ContentPart::Code(code) = part {
+            match has_native_code(code) {
+                true => {
+                    let native = native_code(code);
+                    if !data.contains(&native) {
+                        data.push(native);
+                    }
+                }
+                false => collect_native_code(&code.content, data),
+            }
+        }
+    }
+}
+
+/// Collects the identifiers of the inline codes and markers of the content.
+fn collect_code_ids<'a>(content: &'a [ContentPart], ids: &mut HashSet<&'a str>) {
+    for part in content {
+        if let ContentPart::Code(code) = part {
+            ids.extend(code.id.as_deref());
+            ids.extend(code.attribute("mid"));
+            collect_code_ids(&code.content, ids);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+extern crate xliff;
+
+use xliff::merge::{
+    merge_file, merge_file_with, merge_skeleton, merge_skeleton_with, MergeError, Placeholders,
+    OKAPI_PLACEHOLDERS,
+};
+use xliff::t::T;
+
+const SKELETON_SAMPLE: &str = r#"<xliff version="1.2"><file original="readme.txt" source-language="en" target-language="fr" datatype="plaintext"><header>
+<skl><internal-file form="base64">VGl0bGU6ICUlJXRpdGxlJSUlCgolJSVib2R5JSUlCg==</internal-file></skl>
+</header><body>
+<trans-unit id="title"><source>Hello</source><target>Bonjour</target></trans-unit>
+<trans-unit id="body"><source>Read <g id="1">me</g> first</source></trans-unit>
+</body></file></xliff>"#;
+
+#[test]
+fn test_merges_internal_skeleton() {
+    let t = T::load_str(SKELETON_SAMPLE).unwrap();
+
+    let merged = merge_file(&t.store.groups[0]).unwrap();
+
+    assert_eq!(merged, "Title: Bonjour\n\nRead me first\n");
+}
+
+#[test]
+fn test_merges_provided_skeleton() {
+    let t = T::load_str(SKELETON_SAMPLE).unwrap();
+
+    let merged = merge_skeleton(&t.store.groups[0], "title=%%%title%%%").unwrap();
+
+    assert_eq!(merged, "title=Bonjour");
+}
+
+#[test]
+fn test_merge_errors() {
+    let t = T::load_str(SKELETON_SAMPLE).unwrap();
+    let file = &t.store.groups[0];
+
+    match merge_skeleton(file, "%%%missing%%%") {
+        Err(MergeError::UnknownUnit(id)) => assert_eq!(id, "missing"),
+        _ => panic!("expected an unknown unit error"),
+    }
+    match merge_skeleton(file, "ok %%%title") {
+        Err(MergeError::UnclosedPlaceholder { offset }) => assert_eq!(offset, 3),
+        _ => panic!("expected an unclosed placeholder error"),
+    }
+
+    let src = r#"<xliff version="1.2"><file original="page.html" source-language="en" datatype="html"><header>
+<skl><external-file href="page.skl"/></skl>
+</header><body><trans-unit id="1"><source>Text</source></trans-unit></body></file></xliff>"#;
+    let t = T::load_str(src).unwrap();
+    let file = &t.store.groups[0];
+    match merge_file(file) {
+        Err(MergeError::ExternalSkeleton { href }) => assert_eq!(href, "page.skl"),
+        _ => panic!("expected an external skeleton error"),
+    }
+    assert_eq!(
+        merge_skeleton(file, "<p>%%%1%%%</p>").unwrap(),
+        "<p>Text</p>"
+    );
+}
+
+#[test]
+fn test_does_not_merge_documents_needing_escaping() {
+    let src = r#"<xliff version="1.2"><file original="strings.c" source-language="en" datatype="cstring"><body>
+<trans-unit id="1"><source>Say "hi"</source></trans-unit>
+</body></file></xliff>"#;
+    let t = T::load_str(src).unwrap();
+
+    match merge_skeleton(&t.store.groups[0], "char *s = \"%%%1%%%\";") {
+        Err(MergeError::UnsupportedDataType(data_type)) => assert_eq!(data_type, "cstring"),
+        _ => panic!("expected an unsupported data type error"),
+    }
+}
+
+#[test]
+fn test_merges_okapi_reference_markers() {
+    let src = r#"<xliff version="1.2"><file original="readme.txt" source-language="en" datatype="plaintext"><header>
+<skl><internal-file>Title: [#$tu1]
+[#$tu2]
+</internal-file></skl>
+</header><body>
+<trans-unit id="tu1"><source>Hello</source><target>Bonjour</target></trans-unit>
+<trans-unit id="tu2"><source>Bye</source></trans-unit>
+</body></file></xliff>"#;
+    let t = T::load_str(src).unwrap();
+
+    let merged = merge_file_with(&t.store.groups[0], OKAPI_PLACEHOLDERS).unwrap();
+
+    assert_eq!(merged, "Title: Bonjour\nBye\n");
+}
+
+#[test]
+fn test_merges_with_custom_placeholders() {
+    let t = T::load_str(SKELETON_SAMPLE).unwrap();
+    let find = |text: &str| {
+        let start = text.find("${")?;
+        let len = text[start..].find('}')?;
+        Some((
+            start..start + len + 1,
+            String::from(&text[start + 2..start + len]),
+        ))
+    };
+
+    let merged = merge_skeleton_with(
+        &t.store.groups[0],
+        "${title}, ${body}.",
+        Placeholders::Custom(&find),
+    )
+    .unwrap();
+
+    assert_eq!(merged, "Bonjour, Read me first.");
+
+    let empty = |_: &str| Some((0..0, String::from("title")));
+    match merge_skeleton_with(&t.store.groups[0], "x", Placeholders::Custom(&empty)) {
+        Err(MergeError::InvalidPlaceholder { offset }) => assert_eq!(offset, 0),
+        _ => panic!("expected an invalid placeholder error"),
+    }
+}
+
+#[test]
+fn test_escapes_text_of_markup_documents() {
+    let src = r#"<xliff version="1.2"><file original="page.html" source-language="en" datatype="html"><body>
+<trans-unit id="1"><source>Tom &amp; Jerry <ph id="1">&lt;br/&gt;</ph>say "&lt;hi&gt;"</source></trans-unit>
+<trans-unit id="2" datatype="plaintext"><source>a &lt; b</source></trans-unit>
+</body></file></xliff>"#;
+    let t = T::load_str(src).unwrap();
+
+    let merged = merge_skeleton(&t.store.groups[0], "<p>%%%1%%%</p>%%%2%%%").unwrap();
+
+    assert_eq!(
+        merged,
+        "<p>Tom &amp; Jerry <br/>say &quot;&lt;hi&gt;&quot;</p>a < b"
+    );
+}
+
+#[test]
+fn test_does_not_merge_codes_without_native_code() {
+    let src = r#"<xliff version="1.2"><file original="page.html" source-language="en" datatype="html"><body>
+<trans-unit id="1"><source>Read <g id="1">me</g></source></trans-unit>
+<trans-unit id="2"><source>Name: <x id="1"/></source></trans-unit>
+<trans-unit id="3" datatype="cstring"><source>Hi</source></trans-unit>
+</body></file></xliff>"#;
+    let t = T::load_str(src).unwrap();
+    let file = &t.store.groups[0];
+
+    match merge_skeleton(file, "%%%1%%%") {
+        Err(MergeError::MissingNativeCode(id)) => assert_eq!(id, "1"),
+        _ => panic!("expected a missing native code error"),
+    }
+    match merge_skeleton(file, "%%%2%%%") {
+        Err(MergeError::MissingNativeCode(id)) => assert_eq!(id, "2"),
+        _ => panic!("expected a missing native code error"),
+    }
+    match merge_skeleton(file, "%%%3%%%") {
+        Err(MergeError::UnsupportedDataType(data_type)) => assert_eq!(data_type, "cstring"),
+        _ => panic!("expected an unsupported data type error"),
+    }
+}
@@ -6,6 +6,7 @@ use quick_xml::Reader;
 use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
+use xliff::error::XliffError;
 use xliff::store::*;
 
 #[test]
@@ -31,7 +32,7 @@ fn test_sample_as_xml_count_all_tags() {
 fn test_sample_reads_file_tags() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
     assert_eq!(sut.groups.len(), 4);
 }
@@ -40,27 +41,27 @@ fn test_sample_reads_file_tags() {
 fn test_sample_reads_file_attributes() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
     assert_eq!(
         sut.groups[0].address,
         "SampleApp/Base.lproj/Main.storyboard"
     );
 
-    assert_eq!(sut.groups[0].source_locale.is_some(), true);
+    assert!(sut.groups[0].source_locale.is_some());
     match &sut.groups[0].source_locale {
         None => {
-            assert!(false, "source_locale must be set");
+            panic!("source_locale must be set");
         }
         Some(locale) => {
             assert_eq!(locale.identifier, "en");
         }
     }
 
-    assert_eq!(sut.groups[0].target_locale.is_some(), true);
+    assert!(sut.groups[0].target_locale.is_some());
     match &sut.groups[0].target_locale {
         None => {
-            assert!(false, "target_locale must be set");
+            panic!("target_locale must be set");
         }
         Some(locale) => {
             assert_eq!(locale.identifier, "bg");
@@ -74,35 +75,47 @@ fn test_sample_reads_file_attributes() {
 fn test_sample_reads_translation_units() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
-    assert_eq!(sut.groups[0].units.len(), 1);
-    assert_eq!(sut.groups[0].units[0].id, "fIC-hX-uRv.text");
-    assert_eq!(sut.groups[0].units[0].translate, true);
+    assert_eq!(sut.groups[0].units().len(), 1);
+    assert_eq!(sut.groups[0].units()[0].id, "fIC-hX-uRv.text");
+    assert!(sut.groups[0].units()[0].translate);
 
-    assert_eq!(sut.groups[1].units.len(), 3);
-    assert_eq!(sut.groups[1].units[0].id, "CFBundleName");
-    assert_eq!(sut.groups[1].units[0].translate, true);
-    assert_eq!(sut.groups[1].units[1].id, "2");
-    assert_eq!(sut.groups[1].units[1].translate, false);
+    assert_eq!(sut.groups[1].units().len(), 3);
+    assert_eq!(sut.groups[1].units()[0].id, "CFBundleName");
+    assert!(sut.groups[1].units()[0].translate);
+    assert_eq!(sut.groups[1].units()[1].id, "2");
+    assert!(!sut.groups[1].units()[1].translate);
 }
 
 #[test]
 fn test_sample_reads_translation_source() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
     assert_eq!(
-        sut.groups[0].units[0].source.clone().unwrap().text,
+        sut.groups[0].units()[0]
+            .source
+            .clone()
+            .unwrap()
+            .plain_text(),
         "Pet projects are awesome"
     );
     assert_eq!(
-        sut.groups[1].units[0].source.clone().unwrap().text,
+        sut.groups[1].units()[0]
+            .source
+            .clone()
+            .unwrap()
+            .plain_text(),
         "SampleApp"
     );
     assert_eq!(
-        sut.groups[1].units[1].source.clone().unwrap().text,
+        sut.groups[1].units()[1]
+            .source
+            .clone()
+            .unwrap()
+            .plain_text(),
         "Do not translate this"
     );
 }
@@ -111,36 +124,37 @@ fn test_sample_reads_translation_source() {
 fn test_sample_reads_translation_target() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
     assert_eq!(
-        sut.groups[0].units[0].target.clone().unwrap().text,
+        sut.groups[0].units()[0]
+            .target
+            .clone()
+            .unwrap()
+            .plain_text(),
         "Странични проекти"
     );
-    assert_eq!(sut.groups[1].units[0].target.is_none(), true);
-    assert_eq!(sut.groups[1].units[1].target.is_none(), true);
+    assert!(sut.groups[1].units()[0].target.is_none());
+    assert!(sut.groups[1].units()[1].target.is_none());
 }
 
 #[test]
 fn test_sample_reads_translation_note() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
     assert_eq!(
-        sut.groups[0].units[0].note.clone().unwrap().text,
+        sut.groups[0].units()[0].notes[0].text,
         r#"Class = "UILabel"; text = "Pet projects are awesome"; ObjectID = "fIC-hX-uRv";"#
     );
+    assert_eq!(sut.groups[1].units()[0].notes[0].text, r#"Bundle name"#);
     assert_eq!(
-        sut.groups[1].units[0].note.clone().unwrap().text,
-        r#"Bundle name"#
-    );
-    assert_eq!(
-        sut.groups[1].units[1].note.clone().unwrap().text,
+        sut.groups[1].units()[1].notes[0].text,
         r#"A note from the author"#
     );
     assert_eq!(
-        sut.groups[2].units[0].note.clone().unwrap().text,
+        sut.groups[2].units()[0].notes[0].text,
         r#"No comment provided by engineer."#
     );
 }
@@ -149,19 +163,19 @@ fn test_sample_reads_translation_note() {
 fn test_sample_source_target_retrievers() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
     assert_eq!(
-        sut.groups[0].units[0].source_text().unwrap(),
+        sut.groups[0].units()[0].source_text().unwrap(),
         "Pet projects are awesome"
     );
     assert_eq!(
-        sut.groups[0].units[0].target_text().unwrap(),
+        sut.groups[0].units()[0].target_text().unwrap(),
         "Странични проекти"
     );
-    assert_eq!(sut.groups[1].units[0].source_text().unwrap(), "SampleApp");
+    assert_eq!(sut.groups[1].units()[0].source_text().unwrap(), "SampleApp");
     assert_eq!(
-        sut.groups[1].units[1].source_text().unwrap(),
+        sut.groups[1].units()[1].source_text().unwrap(),
         "Do not translate this"
     );
 }
@@ -177,10 +191,14 @@ fn test_file_using_file_reader() {
     file.read_to_end(&mut buffer).expect("failed to read file");
 
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(buffer.iter().as_slice());
+    sut.load(buffer.iter().as_slice()).unwrap();
 
     assert_eq!(
-        sut.groups[0].units[0].source.clone().unwrap().text,
+        sut.groups[0].units()[0]
+            .source
+            .clone()
+            .unwrap()
+            .plain_text(),
         "Pet projects are awesome"
     );
 }
@@ -189,7 +207,7 @@ fn test_file_using_file_reader() {
 fn test_creates_header() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
     assert!(sut.groups[0].header.is_some());
     assert!(sut.groups[1].header.is_some());
@@ -202,7 +220,7 @@ fn test_creates_header() {
 fn test_creates_header_notes() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
     assert_eq!(sut.groups[0].header.as_ref().unwrap().notes.len(), 2);
     assert_eq!(
@@ -219,7 +237,7 @@ fn test_creates_header_notes() {
 fn test_creates_tools() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
     assert_eq!(sut.groups[0].header.as_ref().unwrap().tools.len(), 1);
     assert_eq!(
@@ -251,11 +269,11 @@ fn test_creates_tools() {
 fn test_reads_source_locale_for_each_file() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
     for file in sut.groups {
         match &file.source_locale {
-            None => assert!(false, "Missing source locale."),
+            None => panic!("Missing source locale."),
             Some(file_source_locale) => {
                 assert_eq!(file_source_locale.identifier, "en");
             }
@@ -267,12 +285,12 @@ fn test_reads_source_locale_for_each_file() {
 fn test_reads_source_locale_for_each_unit() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
     for file in sut.groups {
-        for unit in file.units {
+        for unit in file.units() {
             match &unit.source_locale {
-                None => assert!(false, "Missing source locale: {}", &unit.id),
+                None => panic!("Missing source locale: {}", &unit.id),
                 Some(unit_source_locale) => {
                     assert_eq!(unit_source_locale.identifier, "en");
                 }
@@ -285,14 +303,19 @@ fn test_reads_source_locale_for_each_unit() {
 fn test_reads_target_locale_for_each_unit() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
     for file in sut.groups {
-        for unit in file.units {
+        for unit in file.units() {
+            let expected = match unit.id.as_str() {
+                "Some text2" => "fr",
+                "9" => "de-at",
+                _ => "bg",
+            };
             match &unit.target_locale {
-                None => assert!(false, "Missing target locale: {}", &unit.id),
+                None => panic!("Missing target locale: {}", &unit.id),
                 Some(unit_target_locale) => {
-                    assert_eq!(unit_target_locale.identifier, "bg");
+                    assert_eq!(unit_target_locale.identifier, expected);
                 }
             }
         }
@@ -303,14 +326,1060 @@ fn test_reads_target_locale_for_each_unit() {
 fn test_reads_target_locale_for_each_file() {
     let src: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut sut: xliff::store::Store = Store::new();
-    sut.load(src);
+    sut.load(src).unwrap();
 
     for file in sut.groups {
         match &file.target_locale {
-            None => assert!(false, "Missing target locale {}.", &file.address),
+            None => panic!("Missing target locale {}.", &file.address),
             Some(file_target_locale) => {
                 assert_eq!(file_target_locale.identifier, "bg");
             }
         }
     }
 }
+
+#[test]
+fn test_malformed_xml_reports_position() {
+    let src = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<xliff version=\"1.2\">\n  <file original=\"a\" source-language=\"en\">\n  </body>\n</xliff>";
+    let mut sut: xliff::store::Store = Store::new();
+
+    match sut.load(src.as_bytes()) {
+        Err(XliffError::Xml { position, .. }) => {
+            assert_eq!(position.line, 4);
+            assert!(position.column > 1);
+            assert!(position.offset > 0);
+        }
+        _ => panic!("expected an XML syntax error"),
+    }
+}
+
+#[test]
+fn test_unit_outside_file_is_structure_error() {
+    let src = r#"<xliff version="1.2"><trans-unit id="1"><source>a</source></trans-unit></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+
+    match sut.load(src.as_bytes()) {
+        Err(XliffError::Structure { position, .. }) => {
+            assert_eq!(position.line, 1);
+        }
+        _ => panic!("expected a structure error"),
+    }
+}
+
+#[test]
+fn test_invalid_utf8_is_reported() {
+    let src: &[u8] =
+        b"<xliff version=\"1.2\"><file original=\"\xff\xfe\" source-language=\"en\"></file></xliff>";
+    let mut sut: xliff::store::Store = Store::new();
+
+    match sut.load(src) {
+        Err(XliffError::InvalidUtf8 { .. }) => (),
+        _ => panic!("expected an invalid UTF-8 error"),
+    }
+}
+
+#[test]
+fn test_reads_inline_codes() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Click <g id="1" ctype="bold">here</g> to <x id="2"/>continue<ph id="3">&lt;br/&gt;</ph></source></trans-unit>
+</body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src.as_bytes()).unwrap();
+
+    let source = sut.groups[0].units()[0].source.as_ref().unwrap();
+    assert_eq!(source.content.len(), 6);
+    assert!(source.content[0] == ContentPart::Text(String::from("Click ")));
+
+    match &source.content[1] {
+        ContentPart::Code(code) => {
+            assert!(code.kind == InlineKind::G);
+            assert_eq!(code.id.as_deref(), Some("1"));
+            assert_eq!(code.attribute("ctype"), Some("bold"));
+            assert!(code.content == vec![ContentPart::Text(String::from("here"))]);
+        }
+        _ => panic!("expected a <g> element"),
+    }
+
+    match &source.content[3] {
+        ContentPart::Code(code) => {
+            assert!(code.kind == InlineKind::X);
+            assert_eq!(code.id.as_deref(), Some("2"));
+            assert!(code.content.is_empty());
+        }
+        _ => panic!("expected an <x/> element"),
+    }
+
+    match &source.content[5] {
+        ContentPart::Code(code) => {
+            assert!(code.kind == InlineKind::Ph);
+            assert!(code.content == vec![ContentPart::Text(String::from("<br/>"))]);
+        }
+        _ => panic!("expected a <ph> element"),
+    }
+
+    assert_eq!(source.plain_text(), "Click here to continue");
+}
+
+#[test]
+fn test_accumulates_text_cdata_and_references() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1" xml:space="preserve"><source>Tom &amp; <![CDATA[<Jerry>]]><!-- a comment --> &#x263A;</source><target>Том &amp; <![CDATA[<Джери>]]></target><note>first<!-- split -->second</note></trans-unit>
+</body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src.as_bytes()).unwrap();
+
+    let unit = &sut.groups[0].units()[0];
+    assert_eq!(unit.source_text().unwrap(), "Tom & <Jerry> ☺");
+    assert_eq!(unit.target_text().unwrap(), "Том & <Джери>");
+    assert_eq!(unit.notes[0].text, "firstsecond");
+}
+
+#[test]
+fn test_normalizes_white_space_by_default() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>
+    Some   text
+    <g id="1">in  bold</g>
+</source><note>  A
+note </note></trans-unit>
+<trans-unit id="2" xml:space="preserve"><source>  Some   text </source></trans-unit>
+<trans-unit id="3" xml:space="preserve"><source xml:space="default">  Some   text </source></trans-unit>
+</body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src.as_bytes()).unwrap();
+
+    let units = sut.groups[0].units();
+    assert_eq!(units[0].source_text().unwrap(), "Some text in bold");
+    assert_eq!(units[0].notes[0].text, "A note");
+    assert_eq!(units[1].source_text().unwrap(), "  Some   text ");
+    assert_eq!(units[2].source_text().unwrap(), "Some text");
+}
+
+#[test]
+fn test_normalizes_white_space_across_inline_elements() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Click <g id="1"> here </g> now</source></trans-unit>
+<trans-unit id="2"><source>Line <x id="1"/> break</source></trans-unit>
+</body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src.as_bytes()).unwrap();
+
+    let units = sut.groups[0].units();
+    assert_eq!(units[0].source_text().unwrap(), "Click here now");
+    assert_eq!(units[1].source_text().unwrap(), "Line  break");
+}
+
+#[test]
+fn test_inherits_preserved_white_space() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext" xml:space="preserve"><body>
+<trans-unit id="1"><source> Some  text </source></trans-unit>
+</body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src.as_bytes()).unwrap();
+
+    assert_eq!(
+        sut.groups[0].units()[0].source_text().unwrap(),
+        " Some  text "
+    );
+}
+
+const GROUPS_SAMPLE: &str = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="top"><source>Top</source></trans-unit>
+<group id="menu" resname="MainMenu" restype="x-menu" translate="no">
+    <note>Menu strings</note>
+    <trans-unit id="file"><source>File</source></trans-unit>
+    <group id="edit" resname="EditMenu" translate="yes">
+        <trans-unit id="copy"><source>Copy</source><note>Copy the selection</note></trans-unit>
+    </group>
+    <group id="empty"/>
+</group>
+<trans-unit id="bottom"><source>Bottom</source></trans-unit>
+</body></file></xliff>"#;
+
+#[test]
+fn test_reads_nested_groups() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(GROUPS_SAMPLE.as_bytes()).unwrap();
+
+    let file = &sut.groups[0];
+    assert_eq!(file.body.len(), 3);
+    assert_eq!(file.groups().len(), 1);
+
+    let menu = file.groups()[0];
+    assert_eq!(menu.id.as_deref(), Some("menu"));
+    assert_eq!(menu.resname.as_deref(), Some("MainMenu"));
+    assert_eq!(menu.restype.as_deref(), Some("x-menu"));
+    assert!(!menu.translate);
+    assert_eq!(menu.notes.len(), 1);
+    assert_eq!(menu.notes[0].text, "Menu strings");
+    assert_eq!(menu.groups().len(), 2);
+
+    let ids: Vec<&str> = file.units().iter().map(|u| u.id.as_str()).collect();
+    assert_eq!(ids, vec!["top", "file", "copy", "bottom"]);
+
+    let copy = file.units()[2];
+    assert_eq!(copy.source_text().unwrap(), "Copy");
+    assert_eq!(copy.notes[0].text, "Copy the selection");
+    assert_eq!(copy.source_locale.as_ref().unwrap().identifier, "en");
+}
+
+#[test]
+fn test_units_inherit_group_translate() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(GROUPS_SAMPLE.as_bytes()).unwrap();
+
+    let translate: Vec<bool> = sut.groups[0].units().iter().map(|u| u.translate).collect();
+    assert_eq!(translate, vec![true, false, true, true]);
+}
+
+#[test]
+fn test_finds_group_by_path() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(GROUPS_SAMPLE.as_bytes()).unwrap();
+
+    let file = &sut.groups[0];
+    let edit = file.group(&["menu", "edit"]).unwrap();
+    assert_eq!(edit.units()[0].id, "copy");
+    assert!(file.group(&["MainMenu", "EditMenu"]) == Some(edit));
+    assert!(file.group(&["menu", "empty"]).unwrap().body.is_empty());
+    assert!(file.group(&["edit"]).is_none());
+    assert!(file.group(&[]).is_none());
+}
+
+const ALT_TRANS_SAMPLE: &str = r#"<xliff version="1.2"><file original="a" source-language="en" target-language="de" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open the <g id="1">file</g></source>
+    <alt-trans match-quality="87%" origin="tm" tool-id="tms" alttranstype="proposal">
+        <source>Open a file</source>
+        <target>Eine Datei öffnen</target>
+    </alt-trans>
+    <alt-trans origin="mt"><target>Öffnen Sie die <g id="1">Datei</g></target></alt-trans>
+    <alt-trans match-quality="100"><target>Die Datei öffnen</target></alt-trans>
+</trans-unit>
+<trans-unit id="2"><source>Close</source></trans-unit>
+</body></file></xliff>"#;
+
+#[test]
+fn test_reads_alternate_translations() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(ALT_TRANS_SAMPLE.as_bytes()).unwrap();
+
+    let unit = sut.groups[0].units()[0];
+    assert_eq!(unit.source_text().unwrap(), "Open the file");
+    assert_eq!(unit.alternatives.len(), 3);
+
+    let tm = &unit.alternatives[0];
+    assert_eq!(tm.source.as_ref().unwrap().plain_text(), "Open a file");
+    assert_eq!(tm.target.plain_text(), "Eine Datei öffnen");
+    assert_eq!(tm.match_quality.as_deref(), Some("87%"));
+    assert_eq!(tm.origin.as_deref(), Some("tm"));
+    assert_eq!(tm.tool_id.as_deref(), Some("tms"));
+    assert_eq!(tm.alt_trans_type.as_deref(), Some("proposal"));
+
+    let mt = &unit.alternatives[1];
+    assert!(mt.source.is_none());
+    assert!(mt.match_quality.is_none());
+    assert_eq!(mt.target.plain_text(), "Öffnen Sie die Datei");
+    assert_eq!(mt.target.content.len(), 2);
+
+    assert!(sut.groups[0].units()[1].alternatives.is_empty());
+}
+
+#[test]
+fn test_queries_alternate_translations() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(ALT_TRANS_SAMPLE.as_bytes()).unwrap();
+
+    let unit = sut.groups[0].units()[0];
+    assert_eq!(unit.alternatives[0].match_percent(), Some(87.0));
+    assert_eq!(unit.alternatives[1].match_percent(), None);
+    assert_eq!(unit.alternatives[2].match_percent(), Some(100.0));
+
+    let best = unit.best_alternative().unwrap();
+    assert_eq!(best.target.plain_text(), "Die Datei öffnen");
+
+    let above: Vec<String> = unit
+        .alternatives_above(80.0)
+        .iter()
+        .map(|a| a.target.plain_text())
+        .collect();
+    assert_eq!(above, vec!["Eine Datei öffnen", "Die Datei öffnen"]);
+    assert!(unit.alternatives_above(100.5).is_empty());
+    assert!(sut.groups[0].units()[1].best_alternative().is_none());
+}
+
+const SEGMENTED_SAMPLE: &str = r#"<xliff version="1.2"><file original="a" source-language="en" target-language="de" datatype="plaintext"><body>
+<trans-unit id="1">
+    <source>First sentence. Second <g id="1">sentence</g>.</source>
+    <seg-source><mrk mtype="seg" mid="1">First sentence.</mrk> <mrk mtype="seg" mid="2">Second <g id="1">sentence</g>.</mrk></seg-source>
+    <target><mrk mtype="seg" mid="1">Erster Satz.</mrk></target>
+</trans-unit>
+<trans-unit id="2"><source>Plain</source></trans-unit>
+</body></file></xliff>"#;
+
+#[test]
+fn test_reads_segments() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(SEGMENTED_SAMPLE.as_bytes()).unwrap();
+
+    let unit = sut.groups[0].units()[0];
+    assert_eq!(
+        unit.seg_source.as_ref().unwrap().plain_text(),
+        "First sentence. Second sentence."
+    );
+
+    let segments = unit.segments();
+    assert_eq!(segments.len(), 2);
+    assert_eq!(segments[0].mid, "1");
+    assert_eq!(segments[0].source.plain_text(), "First sentence.");
+    assert_eq!(
+        segments[0].target.as_ref().unwrap().plain_text(),
+        "Erster Satz."
+    );
+    assert_eq!(segments[1].mid, "2");
+    assert_eq!(segments[1].source.plain_text(), "Second sentence.");
+    assert!(segments[1].target.is_none());
+
+    assert!(sut.groups[0].units()[1].segments().is_empty());
+}
+
+#[test]
+fn test_updates_segment_targets() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(SEGMENTED_SAMPLE.as_bytes()).unwrap();
+
+    let mut units = sut.groups[0].units_mut();
+    let unit = &mut units[0];
+    assert!(unit.set_segment_target("2", UnitValue::new("Zweiter Satz.")));
+    assert!(unit.set_segment_target("1", UnitValue::new("Der erste Satz.")));
+    assert!(!unit.set_segment_target("3", UnitValue::new("Nein")));
+    assert_eq!(unit.target_text().unwrap(), "Der erste Satz.Zweiter Satz.");
+    assert_eq!(
+        unit.segments()[1].target.as_ref().unwrap().plain_text(),
+        "Zweiter Satz."
+    );
+
+    // a missing target takes the structure of the segmented source
+    unit.target = None;
+    assert!(unit.set_segment_target("2", UnitValue::new("Zweiter Satz.")));
+    assert_eq!(unit.target_text().unwrap(), " Zweiter Satz.");
+    assert_eq!(unit.segments()[0].target.as_ref().unwrap().plain_text(), "");
+}
+
+const STATE_SAMPLE: &str = r#"<xliff version="1.2"><file original="a" source-language="en" target-language="de" datatype="plaintext"><body>
+<trans-unit id="1" approved="yes"><source>One</source><target state="final">Eins</target></trans-unit>
+<trans-unit id="2"><source>Two</source><target state="needs-review-translation" state-qualifier="fuzzy-match">Zwo</target></trans-unit>
+<trans-unit id="3" approved="no"><source>Three</source><target state="x-pending" state-qualifier="x-vendor">Drei</target></trans-unit>
+<group id="g"><trans-unit id="4"><source>Four</source><target state="needs-review-l10n"/></trans-unit></group>
+</body></file>
+<file original="b" source-language="en" target-language="de" datatype="plaintext"><body>
+<trans-unit id="5"><source>Five</source><target state="needs-review-adaptation">Fünf</target></trans-unit>
+</body></file></xliff>"#;
+
+#[test]
+fn test_reads_workflow_state() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(STATE_SAMPLE.as_bytes()).unwrap();
+
+    let units = sut.groups[0].units();
+    assert!(units[0].approved);
+    assert!(units[0].state == Some(TargetState::Final));
+    assert!(units[0].state_qualifier.is_none());
+
+    assert!(!units[1].approved);
+    assert!(units[1].state == Some(TargetState::NeedsReviewTranslation));
+    assert!(units[1].state_qualifier == Some(StateQualifier::FuzzyMatch));
+
+    assert!(!units[2].approved);
+    assert!(units[2].state == Some(TargetState::Custom(String::from("x-pending"))));
+    assert_eq!(
+        units[2].state_qualifier.as_ref().unwrap().as_str(),
+        "x-vendor"
+    );
+
+    assert!(units[3].state == Some(TargetState::NeedsReviewL10n));
+}
+
+#[test]
+fn test_queries_units_by_state() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(STATE_SAMPLE.as_bytes()).unwrap();
+
+    let ids = |units: Vec<&Unit>| -> Vec<String> { units.iter().map(|u| u.id.clone()).collect() };
+    assert_eq!(ids(sut.units_needing_review(None)), vec!["2", "4", "5"]);
+    assert_eq!(ids(sut.units_needing_review(Some("a"))), vec!["2", "4"]);
+    assert_eq!(
+        ids(sut.units_in_state(None, &TargetState::Final)),
+        vec!["1"]
+    );
+    assert_eq!(ids(sut.unapproved_units(Some("b"))), vec!["5"]);
+    assert!(sut.units_needing_review(Some("missing")).is_empty());
+}
+
+#[test]
+fn test_reads_multiple_notes_with_attributes() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><header>
+<note from="pm" priority="2" annotates="general">Deadline is Friday</note>
+</header><body>
+<trans-unit id="1"><source>Save</source>
+    <note from="translator" annotates="target" xml:lang="de">Kurz halten</note>
+    <note>Button title</note>
+    <note priority="11" annotates="nowhere">Invalid attributes are ignored</note>
+</trans-unit>
+</body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src.as_bytes()).unwrap();
+
+    let header_note = &sut.groups[0].header.as_ref().unwrap().notes[0];
+    assert_eq!(header_note.text, "Deadline is Friday");
+    assert_eq!(header_note.from.as_deref(), Some("pm"));
+    assert_eq!(header_note.priority, Some(2));
+    assert!(header_note.annotates == Some(Annotates::General));
+
+    let unit = sut.groups[0].units()[0];
+    assert_eq!(unit.notes.len(), 3);
+    assert_eq!(unit.notes[0].text, "Kurz halten");
+    assert_eq!(unit.notes[0].from.as_deref(), Some("translator"));
+    assert!(unit.notes[0].annotates == Some(Annotates::Target));
+    assert_eq!(unit.notes[0].lang.as_deref(), Some("de"));
+    assert!(unit.notes[1] == Note::new("Button title"));
+    assert_eq!(unit.notes[2].priority, None);
+    assert!(unit.notes[2].annotates.is_none());
+
+    assert_eq!(unit.developer_note().unwrap().text, "Button title");
+}
+
+const CONTEXT_SAMPLE: &str = r#"<xliff version="1.2"><file original="messages" source-language="en" datatype="plaintext"><body>
+<group id="dialogs"><context-group name="dialogs" purpose="information"><context context-type="x-screen">Settings</context></context-group>
+<trans-unit id="1"><source>Open</source><target>Öffnen</target>
+    <context-group purpose="location">
+        <context context-type="sourcefile">src/app/menu.ts</context>
+        <context context-type="linenumber">12</context>
+    </context-group>
+    <context-group name="gettext" purpose="match"><context context-type="x-gettext-msgctxt" match-mandatory="yes">menu</context></context-group>
+    <note>File menu item</note>
+</trans-unit>
+</group>
+<trans-unit id="2"><source>Open</source><target>Offen</target>
+    <context-group purpose="location"><context context-type="sourcefile">src/app/status.ts</context></context-group>
+    <context-group><context context-type="x-gettext-msgctxt">status</context></context-group>
+    <context-group purpose="location"/>
+</trans-unit>
+</body></file></xliff>"#;
+
+#[test]
+fn test_reads_context_groups() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(CONTEXT_SAMPLE.as_bytes()).unwrap();
+
+    let file = &sut.groups[0];
+    let dialogs = file.group(&["dialogs"]).unwrap();
+    assert_eq!(dialogs.context_groups.len(), 1);
+    assert_eq!(dialogs.context_groups[0].name.as_deref(), Some("dialogs"));
+    assert!(dialogs.context_groups[0].contexts[0] == Context::new("x-screen", "Settings"));
+
+    let unit = file.units()[0];
+    assert_eq!(unit.context_groups.len(), 2);
+    let location = &unit.context_groups[0];
+    assert_eq!(location.purpose.as_deref(), Some("location"));
+    assert!(location.name.is_none());
+    assert_eq!(location.contexts.len(), 2);
+    assert_eq!(location.contexts[0].context_type, "sourcefile");
+    assert_eq!(location.contexts[0].value, "src/app/menu.ts");
+    assert!(!location.contexts[0].match_mandatory);
+    assert!(unit.context_groups[1].contexts[0].match_mandatory);
+    assert_eq!(unit.notes[0].text, "File menu item");
+
+    assert_eq!(unit.context("linenumber"), Some("12"));
+    assert_eq!(unit.context("x-gettext-msgctxt"), Some("menu"));
+    assert_eq!(unit.context("element"), None);
+
+    let other = file.units()[1];
+    assert_eq!(other.context_groups.len(), 3);
+    assert!(other.context_groups[2].contexts.is_empty());
+}
+
+#[test]
+fn test_reads_count_groups() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><header>
+<count-group name="file"><count count-type="total" unit="word">120</count></count-group>
+</header><body>
+<group id="g"><count-group name="group"><count count-type="x-repeated" unit="segment" phase-name="p1"> 3 </count></count-group>
+<trans-unit id="1"><source>Two words</source><count-group name="unit-1"><count count-type="total" unit="word">2</count><count count-type="total" unit="character">8</count></count-group></trans-unit>
+</group>
+</body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src.as_bytes()).unwrap();
+
+    let file = &sut.groups[0];
+    let header_group = &file.header.as_ref().unwrap().count_groups[0];
+    assert_eq!(header_group.name, "file");
+    assert_eq!(header_group.count("total", "word"), Some(120));
+
+    let group = &file.group(&["g"]).unwrap().count_groups[0];
+    assert_eq!(group.counts[0].count_type.as_deref(), Some("x-repeated"));
+    assert_eq!(group.counts[0].phase_name.as_deref(), Some("p1"));
+    assert_eq!(group.counts[0].value, 3);
+
+    let unit = file.units()[0];
+    assert_eq!(unit.count_groups[0].name, "unit-1");
+    assert_eq!(unit.count_groups[0].count("total", "character"), Some(8));
+    assert_eq!(unit.count_groups[0].count("total", "line"), None);
+}
+
+#[test]
+fn test_invalid_count_is_structure_error() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Text</source><count-group name="c"><count unit="word">many</count></count-group></trans-unit>
+</body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+
+    match sut.load(src.as_bytes()) {
+        Err(XliffError::Structure { position, .. }) => assert_eq!(position.line, 2),
+        _ => panic!("expected a structure error"),
+    }
+}
+
+#[test]
+fn test_computes_counts() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open the <g id="1">file</g> now</source><count-group name="total-1"><count count-type="total" unit="word">99</count></count-group></trans-unit>
+<trans-unit id="2" translate="no"><source>Not counted at all</source></trans-unit>
+<group id="g"><trans-unit id="3"><source>Save  it</source></trans-unit></group>
+</body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src.as_bytes()).unwrap();
+
+    let file = &sut.groups[0];
+    assert_eq!(file.units()[0].word_count(), 4);
+    assert_eq!(file.units()[0].character_count(), 14);
+    assert_eq!(file.word_count(), 6);
+    assert_eq!(file.character_count(), 20);
+
+    sut.compute_counts();
+    let file = &sut.groups[0];
+    let header_group = &file.header.as_ref().unwrap().count_groups[0];
+    assert_eq!(header_group.name, COMPUTED_COUNT_GROUP);
+    assert_eq!(header_group.count("total", "word"), Some(6));
+    assert_eq!(header_group.count("total", "character"), Some(20));
+
+    let units = file.units();
+    assert_eq!(units[0].count_groups.len(), 1);
+    assert_eq!(units[0].count_groups[0].count("total", "word"), Some(4));
+    assert!(units[1].count_groups.is_empty());
+    assert_eq!(units[2].count_groups[0].name, "total-3");
+    assert_eq!(units[2].count_groups[0].count("total", "word"), Some(2));
+}
+
+const PROPS_SAMPLE: &str = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><header>
+<prop-group name="project"><prop prop-type="x-project">Widgets</prop></prop-group>
+</header><body>
+<group id="g"><prop-group><prop prop-type="x-screenshot">settings.png</prop></prop-group>
+<trans-unit id="1"><source>Save</source><prop-group name="tracking"><prop prop-type="x-jira">ABC-123</prop><prop prop-type="x-max-length" xml:lang="de">12</prop></prop-group></trans-unit>
+<trans-unit id="2"><source>Cancel</source><prop-group><prop prop-type="x-jira">ABC-456</prop><prop prop-type="x-empty"/></prop-group></trans-unit>
+</group>
+<trans-unit id="3"><source>Open</source><prop-group><prop prop-type="x-jira">ABC-123</prop></prop-group></trans-unit>
+</body></file></xliff>"#;
+
+#[test]
+fn test_reads_prop_groups() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(PROPS_SAMPLE.as_bytes()).unwrap();
+
+    let file = &sut.groups[0];
+    assert_eq!(file.prop("x-project"), Some("Widgets"));
+    let header_group = &file.header.as_ref().unwrap().prop_groups[0];
+    assert_eq!(header_group.name.as_deref(), Some("project"));
+
+    let group = file.group(&["g"]).unwrap();
+    assert_eq!(group.prop("x-screenshot"), Some("settings.png"));
+    assert_eq!(group.prop("x-jira"), None);
+
+    let units = file.units();
+    assert_eq!(units[0].prop("x-jira"), Some("ABC-123"));
+    assert_eq!(units[0].prop("x-max-length"), Some("12"));
+    assert_eq!(units[0].prop_groups[0].props[1].lang.as_deref(), Some("de"));
+    assert_eq!(units[1].prop("x-empty"), Some(""));
+    assert_eq!(units[2].prop("x-screenshot"), None);
+}
+
+#[test]
+fn test_units_with_prop() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(PROPS_SAMPLE.as_bytes()).unwrap();
+
+    let units = sut.units_with_prop(None, "x-jira", "ABC-123");
+    let ids: Vec<&str> = units.iter().map(|u| u.id.as_str()).collect();
+    assert_eq!(ids, vec!["1", "3"]);
+
+    assert!(sut
+        .units_with_prop(Some("b"), "x-jira", "ABC-123")
+        .is_empty());
+    assert!(sut.units_with_prop(None, "x-jira", "ABC").is_empty());
+}
+
+const BIN_UNIT_SAMPLE: &str = r#"<xliff version="1.2"><file original="app.rc" source-language="en" datatype="winres"><body>
+<trans-unit id="1"><source>Open</source></trans-unit>
+<bin-unit id="icon" mime-type="image/x-icon">
+  <bin-source><internal-file form="base64">AAEC
+    /w==</internal-file></bin-source>
+  <bin-target><external-file href="icons/app.de.ico"/></bin-target>
+  <context-group><context context-type="x-resource">IDI_APP</context></context-group>
+  <prop-group><prop prop-type="x-size">16</prop></prop-group>
+  <note>Application icon</note>
+</bin-unit>
+<group id="g" translate="no"><bin-unit id="text" mime-type="text/plain"><bin-source><internal-file form="text/plain">plain data</internal-file></bin-source></bin-unit></group>
+<trans-unit id="2"><source>Close</source></trans-unit>
+</body></file></xliff>"#;
+
+#[test]
+fn test_reads_bin_units() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(BIN_UNIT_SAMPLE.as_bytes()).unwrap();
+
+    let file = &sut.groups[0];
+    let ids: Vec<&str> = file.units().iter().map(|u| u.id.as_str()).collect();
+    assert_eq!(ids, vec!["1", "2"]);
+    assert!(matches!(file.body[1], BodyItem::BinUnit(_)));
+
+    let bin_units = file.bin_units();
+    assert_eq!(bin_units.len(), 2);
+
+    let icon = bin_units[0];
+    assert_eq!(icon.id, "icon");
+    assert_eq!(icon.mime_type, "image/x-icon");
+    assert!(icon.translate);
+    assert_eq!(icon.source.data(), Some(&[0u8, 1, 2, 255][..]));
+    assert!(
+        icon.target
+            == Some(BinFile::External {
+                href: String::from("icons/app.de.ico")
+            })
+    );
+    assert_eq!(icon.context_groups[0].contexts[0].value, "IDI_APP");
+    assert_eq!(icon.prop_groups[0].prop("x-size"), Some("16"));
+    assert_eq!(icon.notes[0].text, "Application icon");
+
+    let text = bin_units[1];
+    assert!(!text.translate);
+    assert_eq!(text.source.data(), Some("plain data".as_bytes()));
+    assert!(text.target.is_none());
+    assert_eq!(file.group(&["g"]).unwrap().bin_units().len(), 1);
+}
+
+#[test]
+fn test_invalid_base64_is_structure_error() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<bin-unit id="1" mime-type="image/png"><bin-source><internal-file form="base64">not base64!</internal-file></bin-source></bin-unit>
+</body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+
+    match sut.load(src.as_bytes()) {
+        Err(XliffError::Structure { position, .. }) => assert_eq!(position.line, 2),
+        _ => panic!("expected a structure error"),
+    }
+}
+
+const PHASES_SAMPLE: &str = r#"<xliff version="1.2"><file original="a" source-language="en" target-language="de" datatype="plaintext"><header>
+<phase-group>
+  <phase phase-name="tr1" process-name="translation" contact-name="Anna" date="2020-05-01T10:00:00Z" tool-id="cat"><note>First pass</note></phase>
+  <phase phase-name="rv1" process-name="review" company-name="Vendor" contact-email="review@example.com"/>
+</phase-group>
+<tool tool-id="cat" tool-name="CAT"/>
+</header><body>
+<trans-unit id="1"><source>Open</source><target phase-name="rv1" state="signed-off">Öffnen</target></trans-unit>
+<trans-unit id="2"><source>Close</source><target phase-name="unknown">Schließen</target></trans-unit>
+</body></file></xliff>"#;
+
+#[test]
+fn test_reads_phases() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(PHASES_SAMPLE.as_bytes()).unwrap();
+
+    let file = &sut.groups[0];
+    let header = file.header.as_ref().unwrap();
+    assert_eq!(header.phases.len(), 2);
+    assert_eq!(header.tools.len(), 1);
+
+    let translation = file.phase("tr1").unwrap();
+    assert_eq!(translation.process_name, "translation");
+    assert_eq!(translation.contact_name.as_deref(), Some("Anna"));
+    assert_eq!(translation.date.as_deref(), Some("2020-05-01T10:00:00Z"));
+    assert_eq!(translation.tool_id.as_deref(), Some("cat"));
+    assert_eq!(translation.notes[0].text, "First pass");
+
+    let units = file.units();
+    assert_eq!(units[0].phase_name.as_deref(), Some("rv1"));
+    let review = file.target_phase(units[0]).unwrap();
+    assert_eq!(review.company_name.as_deref(), Some("Vendor"));
+    assert_eq!(review.contact_email.as_deref(), Some("review@example.com"));
+    assert!(file.target_phase(units[1]).is_none());
+}
+
+#[test]
+fn test_reads_glossaries_and_references() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><header>
+<glossary><internal-file form="base64">b3BlbjvDtmZmbmVu</internal-file><note>Product terms</note></glossary>
+<glossary><external-file href="https://example.com/terms.tbx"/></glossary>
+<reference><internal-file form="text/plain">Use formal address.</internal-file></reference>
+</header><body><trans-unit id="1"><source>Open</source></trans-unit></body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src.as_bytes()).unwrap();
+
+    let header = sut.groups[0].header.as_ref().unwrap();
+    assert_eq!(header.glossaries.len(), 2);
+    assert_eq!(
+        header.glossaries[0].file.data(),
+        Some("open;öffnen".as_bytes())
+    );
+    assert_eq!(
+        header.glossaries[0].note.as_ref().unwrap().text,
+        "Product terms"
+    );
+    assert!(header.glossaries[1] == ReferenceFile::external("https://example.com/terms.tbx"));
+    assert_eq!(header.references.len(), 1);
+    assert_eq!(
+        header.references[0].file.data(),
+        Some("Use formal address.".as_bytes())
+    );
+    assert!(header.references[0].note.is_none());
+}
+
+const WIDTH_SAMPLE: &str = r#"<xliff version="1.2"><file original="strings.xml" source-language="en" target-language="de" datatype="x-android"><body>
+<trans-unit id="1" resname="app_name" restype="string" datatype="plaintext" maxwidth="6" minwidth="2" size-unit="char" charclass="latin"><source>Notes</source><target>Notizen</target></trans-unit>
+<trans-unit id="2" resname="save" maxwidth="6" size-unit="byte"><source>Save</source><target>Größe</target></trans-unit>
+<trans-unit id="3" maxwidth="2"><source>OK</source><target>Okay</target></trans-unit>
+<trans-unit id="4" maxwidth="10" size-unit="char"><source>Cancel</source><target>Abbrechen</target></trans-unit>
+</body></file></xliff>"#;
+
+#[test]
+fn test_reads_unit_metadata() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(WIDTH_SAMPLE.as_bytes()).unwrap();
+
+    let unit = sut.groups[0].units()[0];
+    assert_eq!(unit.resname.as_deref(), Some("app_name"));
+    assert_eq!(unit.restype.as_deref(), Some("string"));
+    assert_eq!(unit.data_type.as_deref(), Some("plaintext"));
+    assert_eq!(unit.max_width, Some(6));
+    assert_eq!(unit.min_width, Some(2));
+    assert_eq!(unit.size_unit.as_deref(), Some("char"));
+    assert_eq!(unit.char_class.as_deref(), Some("latin"));
+}
+
+#[test]
+fn test_units_exceeding_max_width() {
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(WIDTH_SAMPLE.as_bytes()).unwrap();
+
+    let units = sut.groups[0].units();
+    assert_eq!(units[0].target_width(), Some(7));
+    assert_eq!(units[1].target_width(), Some(7));
+    assert_eq!(units[2].target_width(), None);
+
+    let ids: Vec<&str> = sut
+        .units_exceeding_max_width(None)
+        .iter()
+        .map(|u| u.id.as_str())
+        .collect();
+    assert_eq!(ids, vec!["1", "2"]);
+}
+
+#[test]
+fn test_units_with_mismatched_target_language() {
+    let src: &[u8] = include_bytes!("simplev1_2.xliff");
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src).unwrap();
+
+    let ids: Vec<&str> = sut
+        .units_with_mismatched_target_language(None)
+        .iter()
+        .map(|u| u.id.as_str())
+        .collect();
+    assert_eq!(ids, vec!["Some text2", "9"]);
+    assert!(sut
+        .units_with_mismatched_target_language(Some("file without header"))
+        .is_empty());
+}
+
+#[test]
+fn test_invalid_max_width_is_structure_error() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1" maxwidth="wide"><source>Text</source></trans-unit>
+</body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+
+    match sut.load(src.as_bytes()) {
+        Err(XliffError::Structure { message, position }) => {
+            assert_eq!(message, "the maxwidth attribute must be a whole number");
+            assert_eq!(position.line, 2);
+        }
+        _ => panic!("expected a structure error"),
+    }
+}
+
+#[test]
+fn test_reads_unknown_content() {
+    let src = r#"<xliff version="1.2" xmlns:mq="MQXliff"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1" mq:status="Confirmed"><source>Open</source><target xml:lang="fr">Ouvrir</target><!-- checked --><mq:history><mq:entry/></mq:history></trans-unit>
+</body></file></xliff>"#;
+    let mut store: xliff::store::Store = Store::new();
+    store.load(src.as_bytes()).unwrap();
+    assert_eq!(
+        store.extensions.attributes,
+        vec![(String::from("xmlns:mq"), String::from("MQXliff"))]
+    );
+
+    let unit = &store.groups[0].units()[0];
+    assert_eq!(
+        unit.extensions.attributes,
+        vec![(String::from("mq:status"), String::from("Confirmed"))]
+    );
+    assert!(unit.target_extensions.attributes.is_empty());
+    assert_eq!(unit.target_locale.as_ref().unwrap().identifier, "fr");
+    assert_eq!(
+        unit.extensions.nodes,
+        vec![
+            (2, String::from("<!-- checked -->")),
+            (2, String::from("<mq:history><mq:entry/></mq:history>"))
+        ]
+    );
+    assert_eq!(unit.target_text().unwrap(), "Ouvrir");
+}
+
+#[test]
+fn test_reads_order_of_children() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<group id="g"><note>Group</note><context-group><context context-type="x">a</context></context-group><!-- item --><trans-unit id="1"><source>Open</source><note>A note</note><target>Ouvrir</target></trans-unit></group>
+</body></file></xliff>"#;
+    let mut store: xliff::store::Store = Store::new();
+    store.load(src.as_bytes()).unwrap();
+
+    let group = match &store.groups[0].body[0] {
+        BodyItem::Group(group) => group,
+        _ => panic!("expected a group"),
+    };
+    assert_eq!(
+        group.extensions.order,
+        vec!["note", "context-group", "trans-unit"]
+    );
+    assert_eq!(
+        group.extensions.nodes,
+        vec![(2, String::from("<!-- item -->"))]
+    );
+    let unit = &store.groups[0].units()[0];
+    assert_eq!(unit.extensions.order, vec!["source", "note", "target"]);
+}
+
+#[test]
+fn test_reads_prefixed_xliff_elements() {
+    let src = r#"<xlf:xliff xmlns:xlf="urn:oasis:names:tc:xliff:document:1.2" version="1.2"><xlf:file original="a" source-language="en" datatype="plaintext"><xlf:body>
+<xlf:trans-unit id="1"><xlf:source>Open <xlf:g id="1">file</xlf:g></xlf:source><xlf:note>Menu item</xlf:note></xlf:trans-unit>
+</xlf:body></xlf:file></xlf:xliff>"#;
+    let mut store: xliff::store::Store = Store::new();
+    store.load(src.as_bytes()).unwrap();
+
+    let unit = &store.groups[0].units()[0];
+    assert_eq!(unit.source_text().unwrap(), "Open file");
+    assert_eq!(unit.notes[0].text, "Menu item");
+    // the prefix may still be used by the markup kept as extensions
+    assert_eq!(
+        store.extensions.attributes,
+        vec![(
+            String::from("xmlns:xlf"),
+            String::from("urn:oasis:names:tc:xliff:document:1.2")
+        )]
+    );
+}
+
+#[test]
+fn test_foreign_elements_are_not_read_as_xliff() {
+    let src = r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:1.2" xmlns:sdl="http://sdl.com/FileTypes/SdlXliff/1.0" version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open</source><sdl:note>Reviewed</sdl:note><note xmlns="urn:example:comments">Internal</note></trans-unit>
+</body></file></xliff>"#;
+    let mut store: xliff::store::Store = Store::new();
+    store.load(src.as_bytes()).unwrap();
+
+    let unit = &store.groups[0].units()[0];
+    assert!(unit.notes.is_empty());
+    assert_eq!(
+        unit.extensions.nodes,
+        vec![
+            (1, String::from("<sdl:note>Reviewed</sdl:note>")),
+            (
+                1,
+                String::from(r#"<note xmlns="urn:example:comments">Internal</note>"#)
+            )
+        ]
+    );
+}
+
+#[test]
+fn test_undeclared_prefix_is_foreign() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open</source><mq:source>Ignored</mq:source></trans-unit>
+</body></file></xliff>"#;
+    let mut store: xliff::store::Store = Store::new();
+    store.load(src.as_bytes()).unwrap();
+
+    let unit = &store.groups[0].units()[0];
+    assert_eq!(unit.source_text().unwrap(), "Open");
+    assert_eq!(unit.extensions.nodes.len(), 1);
+}
+
+#[test]
+fn test_reads_unknown_nodes_within_content() {
+    let src = r#"<xliff version="1.2" xmlns:sdl="urn:example:sdl"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Hello <sdl:cmt>SECRET</sdl:cmt> <g id="1">big<!-- in g --></g> world<?pi data?></source>
+<note>See <sdl:ref id="4"/> for details</note></trans-unit>
+</body></file></xliff>"#;
+    let mut store: xliff::store::Store = Store::new();
+    store.load(src.as_bytes()).unwrap();
+
+    let unit = &store.groups[0].units()[0];
+    assert_eq!(unit.source_text().unwrap(), "Hello  big world");
+    let content = &unit.source.as_ref().unwrap().content;
+    assert!(content[1] == ContentPart::Extension(String::from("<sdl:cmt>SECRET</sdl:cmt>")));
+    match &content[3] {
+        ContentPart::Code(code) => {
+            assert!(code.content[1] == ContentPart::Extension(String::from("<!-- in g -->")))
+        }
+        _ => panic!("expected the <g> element"),
+    }
+    assert!(content[5] == ContentPart::Extension(String::from("<?pi data?>")));
+    assert!(unit.extensions.nodes.is_empty());
+
+    let note = &unit.notes[0];
+    assert_eq!(note.text, "See  for details");
+    assert_eq!(
+        note.extensions.nodes,
+        vec![(4, String::from(r#"<sdl:ref id="4"/>"#))]
+    );
+}
+
+#[test]
+fn test_reads_unknown_nodes_with_their_position() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><header><!-- tools --><tool tool-id="t" tool-name="T"/><note>Header</note><!-- end --></header><body>
+<trans-unit id="1"><!-- first --><source>Open</source><target>Ouvrir</target><!-- before note --><note>Menu</note></trans-unit>
+</body></file></xliff>"#;
+    let mut store: xliff::store::Store = Store::new();
+    store.load(src.as_bytes()).unwrap();
+
+    let header = store.groups[0].header.as_ref().unwrap();
+    assert_eq!(
+        header.extensions.nodes,
+        vec![
+            (0, String::from("<!-- tools -->")),
+            (2, String::from("<!-- end -->"))
+        ]
+    );
+    let unit = &store.groups[0].units()[0];
+    assert_eq!(
+        unit.extensions.nodes,
+        vec![
+            (0, String::from("<!-- first -->")),
+            (2, String::from("<!-- before note -->"))
+        ]
+    );
+}
+
+#[test]
+fn test_reads_alt_trans_content() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" target-language="fr" datatype="plaintext"><body>
+<trans-unit id="1"><source>Hello</source><alt-trans match-quality="80">
+<source xml:lang="en">Hello</source><seg-source><mrk mtype="seg" mid="1">Hello</mrk></seg-source>
+<target xml:lang="de" state="translated" phase-name="p1">Hallo</target>
+<context-group name="c"><context context-type="sourcefile">a.c</context></context-group>
+<prop-group><prop prop-type="x-origin">TM</prop></prop-group>
+<note>From the TM</note></alt-trans></trans-unit>
+</body></file></xliff>"#;
+    let mut store: xliff::store::Store = Store::new();
+    store.load(src.as_bytes()).unwrap();
+
+    let unit = &store.groups[0].units()[0];
+    assert_eq!(unit.target_locale.as_ref().unwrap().identifier, "fr");
+    assert!(unit.state.is_none());
+
+    let alternative = &unit.alternatives[0];
+    assert_eq!(
+        alternative.source_extensions.attributes,
+        vec![(String::from("xml:lang"), String::from("en"))]
+    );
+    assert_eq!(
+        alternative.seg_source.as_ref().unwrap().plain_text(),
+        "Hello"
+    );
+    assert_eq!(alternative.target.plain_text(), "Hallo");
+    assert_eq!(alternative.target_locale.as_ref().unwrap().identifier, "de");
+    assert!(alternative.state == Some(TargetState::Translated));
+    assert_eq!(
+        alternative.target_extensions.attributes,
+        vec![(String::from("phase-name"), String::from("p1"))]
+    );
+    assert_eq!(alternative.context_groups[0].contexts[0].value, "a.c");
+    assert_eq!(alternative.prop_groups[0].props[0].value, "TM");
+    assert_eq!(alternative.notes[0].text, "From the TM");
+    assert!(unit.notes.is_empty());
+}
+
+#[test]
+fn test_reads_empty_header() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><header/><body/></file></xliff>"#;
+    let mut store: xliff::store::Store = Store::new();
+    store.load(src.as_bytes()).unwrap();
+
+    assert!(store.groups[0].header.is_some());
+}
+
+#[test]
+fn test_foreign_elements_within_content_are_kept_as_extensions() {
+    let src = r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:1.2" xmlns:sdl="urn:example:sdl" version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open <sdl:g id="1">file</sdl:g><x id="2" xmlns="urn:example:other"/></source><target>Ouvrir <sdl:source>SECRET</sdl:source></target></trans-unit>
+</body></file></xliff>"#;
+    let mut store: xliff::store::Store = Store::new();
+    store.load(src.as_bytes()).unwrap();
+
+    let unit = &store.groups[0].units()[0];
+    assert!(
+        unit.source.as_ref().unwrap().content
+            == vec![
+                ContentPart::Text(String::from("Open ")),
+                ContentPart::Extension(String::from(r#"<sdl:g id="1">file</sdl:g>"#)),
+                ContentPart::Extension(String::from(r#"<x id="2" xmlns="urn:example:other"/>"#)),
+            ]
+    );
+    assert_eq!(unit.source_text().unwrap(), "Open ");
+    assert_eq!(unit.target_text().unwrap(), "Ouvrir ");
+    assert!(unit.extensions.nodes.is_empty());
+}
+
+#[test]
+fn test_reads_empty_context() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Text</source><context-group purpose="location"><context context-type="x-empty" match-mandatory="yes"/><context context-type="linenumber">3</context></context-group></trans-unit>
+</body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src.as_bytes()).unwrap();
+
+    let group = &sut.groups[0].units()[0].context_groups[0];
+    assert_eq!(group.contexts.len(), 2);
+    assert_eq!(group.contexts[0].context_type, "x-empty");
+    assert_eq!(group.contexts[0].value, "");
+    assert!(group.contexts[0].match_mandatory);
+    assert_eq!(group.contexts[1].value, "3");
+}
+
+#[test]
+fn test_empty_count_is_structure_error() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Text</source><count-group name="c"><count count-type="total" unit="word"/></count-group></trans-unit>
+</body></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+
+    match sut.load(src.as_bytes()) {
+        Err(XliffError::Structure { message, position }) => {
+            assert_eq!(message, "<count> must contain a whole number");
+            assert_eq!(position.line, 2);
+        }
+        _ => panic!("expected a structure error"),
+    }
+}
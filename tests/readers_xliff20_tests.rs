@@ -0,0 +1,231 @@
+extern crate xliff;
+
+use xliff::store::*;
+use xliff::t::T;
+
+#[test]
+fn test_sample_reads_file_tags() {
+    let src: &[u8] = include_bytes!("simplev2_0.xliff");
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src).unwrap();
+
+    assert_eq!(sut.groups.len(), 2);
+}
+
+#[test]
+fn test_sample_reads_file_attributes() {
+    let src: &[u8] = include_bytes!("simplev2_0.xliff");
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src).unwrap();
+
+    assert_eq!(sut.groups[0].id.as_deref(), Some("f1"));
+    assert_eq!(
+        sut.groups[0].address,
+        "SampleApp/Base.lproj/Main.storyboard"
+    );
+    assert_eq!(sut.groups[1].id.as_deref(), Some("f2"));
+    assert_eq!(sut.groups[1].address, "f2");
+
+    for file in &sut.groups {
+        assert_eq!(file.source_locale.as_ref().unwrap().identifier, "en");
+        assert_eq!(file.target_locale.as_ref().unwrap().identifier, "bg");
+    }
+}
+
+#[test]
+fn test_sample_reads_translation_units() {
+    let src: &[u8] = include_bytes!("simplev2_0.xliff");
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src).unwrap();
+
+    assert_eq!(sut.groups[0].units().len(), 1);
+    assert_eq!(sut.groups[0].units()[0].id, "fIC-hX-uRv.text");
+    assert!(sut.groups[0].units()[0].translate);
+    assert_eq!(
+        sut.groups[0].units()[0].source_text().unwrap(),
+        "Pet projects are awesome"
+    );
+    assert_eq!(
+        sut.groups[0].units()[0].target_text().unwrap(),
+        "Странични проекти"
+    );
+    assert_eq!(
+        sut.groups[0].units()[0]
+            .target_locale
+            .as_ref()
+            .unwrap()
+            .identifier,
+        "bg"
+    );
+
+    assert_eq!(sut.groups[1].units().len(), 3);
+    assert!(sut.groups[1].units()[0].target.is_none());
+    assert!(!sut.groups[1].units()[1].translate);
+}
+
+#[test]
+fn test_sample_joins_segments() {
+    let src: &[u8] = include_bytes!("simplev2_0.xliff");
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src).unwrap();
+
+    let unit = &sut.groups[1].units()[2];
+    assert_eq!(
+        unit.source_text().unwrap(),
+        "First sentence. Second sentence."
+    );
+    assert_eq!(
+        unit.target_text().unwrap(),
+        "Първо изречение. Второ изречение."
+    );
+}
+
+#[test]
+fn test_sample_reads_notes() {
+    let src: &[u8] = include_bytes!("simplev2_0.xliff");
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src).unwrap();
+
+    assert_eq!(
+        sut.groups[0].units()[0].notes[0].text,
+        r#"Class = "UILabel"; text = "Pet projects are awesome"; ObjectID = "fIC-hX-uRv";"#
+    );
+    assert_eq!(sut.groups[0].header.as_ref().unwrap().notes.len(), 1);
+    assert_eq!(
+        sut.groups[0].header.as_ref().unwrap().notes[0].text,
+        "This is a file note"
+    );
+    assert!(sut.groups[1].header.is_none());
+}
+
+#[test]
+fn test_t_lookup_in_xliff20() {
+    let sut = T::load_str(include_str!("simplev2_0.xliff")).unwrap();
+
+    let result = sut.t(Some("f2"), "CFBundleName");
+    assert!(result.is_some());
+    assert_eq!(result.unwrap().source_text().unwrap(), "SampleApp");
+
+    let result = sut.t_source(None, "Pet projects are awesome");
+    assert!(result.is_some());
+    assert_eq!(result.unwrap().id, "fIC-hX-uRv.text");
+}
+
+#[test]
+fn test_detects_version_from_namespace() {
+    let src = r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" srcLang="en"><file id="f"><unit id="u"><segment><source>a</source></segment></unit></file></xliff>"#;
+    let mut sut: xliff::store::Store = Store::new();
+    sut.load(src.as_bytes()).unwrap();
+
+    assert_eq!(sut.groups[0].units()[0].id, "u");
+    assert_eq!(sut.groups[0].units()[0].source_text().unwrap(), "a");
+}
+
+#[test]
+fn test_reads_prefixed_elements() {
+    let src = r#"<x:xliff xmlns:x="urn:oasis:names:tc:xliff:document:2.0" xmlns:meta="urn:oasis:names:tc:xliff:metadata:2.0" version="2.0" srcLang="en" trgLang="fr">
+<x:file id="f1"><meta:metadata><meta:metaGroup category="datatype"><meta:meta type="datatype">plaintext</meta:meta></meta:metaGroup></meta:metadata>
+<x:unit id="u1"><x:segment><x:source>Open</x:source><x:target>Ouvrir</x:target></x:segment></x:unit>
+</x:file></x:xliff>"#;
+    let t = T::load_str(src).unwrap();
+
+    let file = &t.store.groups[0];
+    assert_eq!(file.data_type, "plaintext");
+    let unit = &file.units()[0];
+    assert_eq!(unit.source_text().unwrap(), "Open");
+    assert_eq!(unit.target_text().unwrap(), "Ouvrir");
+}
+
+#[test]
+fn test_skips_foreign_elements_within_content() {
+    let src = r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" xmlns:sdl="urn:example:sdl" version="2.0" srcLang="en" trgLang="fr">
+<file id="f1"><unit id="u1"><segment><source>Hello <sdl:cmt>SECRET</sdl:cmt>world</source><target>Bonjour <sdl:source>SECRET</sdl:source>monde</target></segment>
+<notes><note>Greeting<sdl:by>QA</sdl:by></note></notes></unit>
+</file></xliff>"#;
+    let t = T::load_str(src).unwrap();
+
+    let unit = &t.store.groups[0].units()[0];
+    assert_eq!(unit.source_text().unwrap(), "Hello world");
+    assert_eq!(unit.target_text().unwrap(), "Bonjour monde");
+    assert_eq!(unit.notes[0].text, "Greeting");
+}
+
+fn code(
+    kind: InlineKind,
+    id: Option<&str>,
+    attributes: &[(&str, &str)],
+    content: &str,
+) -> ContentPart {
+    let mut code = InlineCode::new(kind, id);
+    code.attributes = attributes
+        .iter()
+        .map(|(key, value)| (String::from(*key), String::from(*value)))
+        .collect();
+    if !content.is_empty() {
+        code.content.push(ContentPart::Text(String::from(content)));
+    }
+    ContentPart::Code(code)
+}
+
+#[test]
+fn test_reads_inline_codes() {
+    let src = r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" version="2.0" srcLang="en" trgLang="fr">
+<file id="f1"><unit id="u1"><originalData><data id="d1">&lt;br/&gt;</data><data id="d2">&lt;b&gt;</data><data id="d3">&lt;/b&gt;</data></originalData>
+<segment><source>A<ph id="1" dataRef="d1"/>B<ph id="2" equiv="{0}"/><pc id="3" type="fmt" subType="xlf:i">C</pc></source><target>A<ph id="1" dataRef="d1"/>B<ph id="2" equiv="{0}"></ph><pc id="3" type="fmt" subType="xlf:i">C</pc></target></segment>
+<segment><source> <sc id="4" dataRef="d2"/>D<ec startRef="4" dataRef="d3"/><sc id="5"/>E<ec startRef="5"/> <mrk id="m1" type="term">F</mrk><cp hex="0001"/></source></segment>
+</unit></file></xliff>"#;
+    let t = T::load_str(src).unwrap();
+
+    let unit = &t.store.groups[0].units()[0];
+    let source = &unit.source.as_ref().unwrap().content;
+    let expected = vec![
+        ContentPart::Text(String::from("A")),
+        code(InlineKind::Ph, Some("1"), &[], "<br/>"),
+        ContentPart::Text(String::from("B")),
+        code(InlineKind::X, Some("2"), &[("equiv-text", "{0}")], ""),
+        code(InlineKind::G, Some("3"), &[("ctype", "italic")], "C"),
+        ContentPart::Text(String::from(" ")),
+        code(InlineKind::Bpt, Some("4"), &[], "<b>"),
+        ContentPart::Text(String::from("D")),
+        code(InlineKind::Ept, Some("4"), &[], "</b>"),
+        code(InlineKind::Bx, Some("5"), &[], ""),
+        ContentPart::Text(String::from("E")),
+        code(InlineKind::Ex, Some("5"), &[], ""),
+        ContentPart::Text(String::from(" ")),
+        code(
+            InlineKind::Mrk,
+            None,
+            &[("mtype", "term"), ("mid", "m1")],
+            "F",
+        ),
+        ContentPart::Text(String::from("\u{1}")),
+    ];
+    assert!(*source == expected);
+    assert_eq!(unit.source_text().unwrap(), "ABC DE F\u{1}");
+    // the second segment has not been translated
+    assert!(unit.target.as_ref().unwrap().content[..5] == expected[..5]);
+    assert!(unit.target.as_ref().unwrap().content.len() == 5);
+}
+
+#[test]
+fn test_reads_isolated_codes() {
+    let src = r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" version="2.0" srcLang="en">
+<file id="f1"><unit id="u1"><originalData><data id="d1">&lt;i&gt;</data></originalData>
+<segment><source><sc id="1" isolated="yes" dataRef="d1"/>A<ec id="2" isolated="yes"/></source></segment>
+</unit></file></xliff>"#;
+    let t = T::load_str(src).unwrap();
+
+    let source = &t.store.groups[0].units()[0]
+        .source
+        .as_ref()
+        .unwrap()
+        .content;
+    assert!(
+        *source
+            == vec![
+                code(InlineKind::It, Some("1"), &[("pos", "open")], "<i>"),
+                ContentPart::Text(String::from("A")),
+                code(InlineKind::Ex, Some("2"), &[], ""),
+            ]
+    );
+}
@@ -1,6 +1,7 @@
 extern crate xliff;
 
 use std::path::PathBuf;
+use xliff::error::XliffError;
 use xliff::t::T;
 
 #[test]
@@ -8,7 +9,7 @@ fn test_t_get_translation() {
     let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "simplev1_2.xliff"]
         .iter()
         .collect();
-    let sut = T::load(&path);
+    let sut = T::load(&path).unwrap();
 
     let result = sut.t(None, "fIC-hX-uRv.text");
 
@@ -22,7 +23,7 @@ fn test_t_get_translation() {
     );
     assert_eq!(translation.target_text().unwrap(), "Странични проекти");
     assert_eq!(
-        translation.note.as_ref().unwrap().text,
+        translation.notes[0].text,
         r#"Class = "UILabel"; text = "Pet projects are awesome"; ObjectID = "fIC-hX-uRv";"#
     );
 }
@@ -32,7 +33,7 @@ fn test_t_get_translation_twice() {
     let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "simplev1_2.xliff"]
         .iter()
         .collect();
-    let sut = T::load(&path);
+    let sut = T::load(&path).unwrap();
 
     let result1 = sut.t(None, "fIC-hX-uRv.text");
     assert!(result1.is_some());
@@ -46,7 +47,7 @@ fn test_t_load_using_path() {
     let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     d.push("tests/simplev1_2.xliff");
 
-    let sut = T::load(&d.to_str().unwrap());
+    let sut = T::load(d.to_str().unwrap()).unwrap();
 
     let result1 = sut.t(None, "fIC-hX-uRv.text");
     assert!(result1.is_some());
@@ -60,7 +61,7 @@ fn test_t_get_translation_with_domain() {
     let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     d.push("tests/simplev1_2.xliff");
 
-    let sut = T::load(&d.to_str().unwrap());
+    let sut = T::load(d.to_str().unwrap()).unwrap();
 
     let result1 = sut.t(Some("SampleApp/en.lproj/InfoPlist.strings"), "More text");
     assert!(result1.is_some());
@@ -74,7 +75,7 @@ fn test_t_get_translation_with_source() {
     let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     d.push("tests/simplev1_2.xliff");
 
-    let sut = T::load(&d.to_str().unwrap());
+    let sut = T::load(d.to_str().unwrap()).unwrap();
 
     let result1 = sut.t_source(None, "Some text");
     assert!(result1.is_some());
@@ -86,7 +87,7 @@ fn test_t_get_translation_with_source_and_domain() {
     let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
     d.push("tests/simplev1_2.xliff");
 
-    let sut = T::load(&d.to_str().unwrap());
+    let sut = T::load(d.to_str().unwrap()).unwrap();
 
     let result1 = sut.t_source(Some("SampleApp/en.lproj/InfoPlist.strings"), "More text");
     assert!(result1.is_some());
@@ -127,7 +128,7 @@ fn test_t_load_translation_from_string() {
   </file>
 </xliff>"#;
 
-    let sut = T::load_str(xliff_string);
+    let sut = T::load_str(xliff_string).unwrap();
 
     let result = sut.t(None, "CFBundleName");
 
@@ -140,5 +141,69 @@ fn test_t_load_translation_from_string() {
         translation.target_text().unwrap(),
         "HelloWidgets Translated"
     );
-    assert_eq!(translation.note.as_ref().unwrap().text, r#"Bundle name"#);
+    assert_eq!(translation.notes[0].text, r#"Bundle name"#);
+}
+
+#[test]
+fn test_t_load_missing_file_returns_error() {
+    let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    d.push("tests/does-not-exist.xliff");
+
+    match T::load(d) {
+        Err(XliffError::Io(_)) => (),
+        _ => panic!("expected an I/O error"),
+    }
+}
+
+#[test]
+fn test_t_finds_units_inside_groups() {
+    let sut = T::load_str(
+        r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<group id="outer"><group id="inner"><trans-unit id="deep"><source>Deep</source><target>Tief</target></trans-unit></group></group>
+</body></file></xliff>"#,
+    )
+    .unwrap();
+
+    assert_eq!(sut.t(None, "deep").unwrap().target_text().unwrap(), "Tief");
+    assert_eq!(sut.t(Some("a"), "deep").unwrap().id, "deep");
+    assert_eq!(sut.t_source(None, "Deep").unwrap().id, "deep");
+}
+
+#[test]
+fn test_t_source_context_disambiguates_identical_sources() {
+    let sut = T::load_str(
+        r#"<xliff version="1.2"><file original="messages" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open</source><target>Öffnen</target>
+    <context-group><context context-type="x-gettext-msgctxt">menu</context></context-group></trans-unit>
+<trans-unit id="2"><source>Open</source><target>Offen</target>
+    <context-group><context context-type="x-gettext-msgctxt">status</context></context-group></trans-unit>
+</body></file></xliff>"#,
+    )
+    .unwrap();
+
+    let status = sut.t_source_context(None, "x-gettext-msgctxt", "status", "Open");
+    assert_eq!(status.unwrap().target_text().unwrap(), "Offen");
+    let menu = sut.t_source_context(Some("messages"), "x-gettext-msgctxt", "menu", "Open");
+    assert_eq!(menu.unwrap().id, "1");
+    assert!(sut
+        .t_source_context(None, "x-gettext-msgctxt", "toolbar", "Open")
+        .is_none());
+    assert!(sut
+        .t_source_context(Some("other"), "x-gettext-msgctxt", "menu", "Open")
+        .is_none());
+}
+
+#[test]
+fn test_t_resname() {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "simplev1_2.xliff"]
+        .iter()
+        .collect();
+    let sut = T::load(&path).unwrap();
+
+    let translation = sut.t_resname(None, "love").unwrap();
+    assert_eq!(translation.id, "9");
+    assert_eq!(translation.target_text().unwrap(), "❤");
+
+    assert!(sut.t_resname(None, "9").is_none());
+    assert!(sut.t_resname(Some("file without header"), "love").is_none());
 }
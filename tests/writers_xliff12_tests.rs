@@ -1,7 +1,10 @@
 extern crate xliff;
 
-use xliff::store::Store;
+use std::path::PathBuf;
+use xliff::store::{Annotates, Store, UntranslatedUnits};
 use xliff::t::T;
+use xliff::writers::options::{Newline, Quote, WriterOptions};
+use xliff::writers::validation::{validate, Rule, ValidationError};
 use xliff::writers::xliff12::*;
 
 #[test]
@@ -22,14 +25,14 @@ fn test_writes_file_for_each_group_in_order() {
     // load the sample xliff
     let sample_file: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut store: xliff::store::Store = Store::new();
-    store.load(sample_file);
+    store.load(sample_file).unwrap();
 
     // export the contents to a string
     let result = WriterXliff12::write(&store);
     let result_string = String::from_utf8(result.unwrap()).unwrap();
 
     // load the string to a new store, so we can assert
-    let t = T::load_str(result_string.as_str());
+    let t = T::load_str(result_string.as_str()).unwrap();
 
     assert_eq!(t.store.groups.len(), 4);
 
@@ -44,19 +47,39 @@ fn test_writes_file_for_each_group_in_order() {
     }
 }
 
+#[test]
+fn test_writes_target_language_differing_from_file() {
+    let sample_file: &[u8] = include_bytes!("simplev1_2.xliff");
+    let mut store: xliff::store::Store = Store::new();
+    store.load(sample_file).unwrap();
+
+    let result = WriterXliff12::write(&store);
+    let result_string = String::from_utf8(result.unwrap()).unwrap();
+    assert!(result_string.contains(r#"<target xml:lang="fr">"#));
+    assert!(result_string.contains(r#"<target xml:lang="de-at">"#));
+    assert!(!result_string.contains(r#"<target xml:lang="bg">"#));
+
+    let t = T::load_str(result_string.as_str()).unwrap();
+    for (file, written) in store.groups.iter().zip(t.store.groups.iter()) {
+        for (unit, written) in file.units().iter().zip(written.units()) {
+            assert!(written.target_locale == unit.target_locale);
+        }
+    }
+}
+
 #[test]
 fn test_writes_header_tools() {
     // load the sample xliff
     let sample_file: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut store: xliff::store::Store = Store::new();
-    store.load(sample_file);
+    store.load(sample_file).unwrap();
 
     // export the contents to a string
     let result = WriterXliff12::write(&store);
     let result_string = String::from_utf8(result.unwrap()).unwrap();
 
     // load the string to a new store, so we can assert
-    let t = T::load_str(result_string.as_str());
+    let t = T::load_str(result_string.as_str()).unwrap();
 
     assert_eq!(t.store.groups.len(), 4);
 
@@ -88,14 +111,14 @@ fn test_writes_header_notes() {
     // load the sample xliff
     let sample_file: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut store: xliff::store::Store = Store::new();
-    store.load(sample_file);
+    store.load(sample_file).unwrap();
 
     // export the contents to a string
     let result = WriterXliff12::write(&store);
     let result_string = String::from_utf8(result.unwrap()).unwrap();
 
     // load the string to a new store, so we can assert
-    let t = T::load_str(result_string.as_str());
+    let t = T::load_str(result_string.as_str()).unwrap();
 
     assert_eq!(t.store.groups.len(), 4);
     let mut at_least_one_note = false;
@@ -128,7 +151,7 @@ fn test_writes_body() {
     // load the sample xliff
     let sample_file: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut store: xliff::store::Store = Store::new();
-    store.load(sample_file);
+    store.load(sample_file).unwrap();
 
     // export the contents to a string
     let result = WriterXliff12::write(&store);
@@ -143,32 +166,32 @@ fn test_writes_body_units() {
     // load the sample xliff
     let sample_file: &[u8] = include_bytes!("simplev1_2.xliff");
     let mut store: xliff::store::Store = Store::new();
-    store.load(sample_file);
+    store.load(sample_file).unwrap();
 
     // export the contents to a string
     let result = WriterXliff12::write(&store);
     let result_string = String::from_utf8(result.unwrap()).unwrap();
 
     // load the string to a new store, so we can assert
-    let t = T::load_str(result_string.as_str());
+    let t = T::load_str(result_string.as_str()).unwrap();
 
     let mut at_least_one_note = false;
     for (g_ix, group) in store.groups.iter().enumerate() {
         let written_group = t.store.groups.get(g_ix);
         assert!(&written_group.is_some());
 
-        for (u_ix, unit) in group.units.iter().enumerate() {
+        for (u_ix, unit) in group.units().iter().enumerate() {
             at_least_one_note = true;
 
-            let written_unit = written_group.unwrap().units.get(u_ix);
+            let written_unit = written_group.unwrap().units().get(u_ix).copied();
             assert!(&written_unit.is_some());
             assert!(
                 &written_unit.unwrap().source.is_some(),
                 "All units must have a source tag."
             );
-            assert!(&written_unit.unwrap().source == &unit.source);
-            assert!(&written_unit.unwrap().target == &unit.target);
-            assert!(&written_unit.unwrap().note == &unit.note);
+            assert!(written_unit.unwrap().source == unit.source);
+            assert!(written_unit.unwrap().target == unit.target);
+            assert!(written_unit.unwrap().notes == unit.notes);
             assert_eq!(&written_unit.unwrap().translate, &unit.translate);
         }
     }
@@ -178,3 +201,742 @@ fn test_writes_body_units() {
         "Test data is missing at least one translation unit."
     );
 }
+
+#[test]
+fn test_writes_inline_codes() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body><trans-unit id="1"><source>Click <g id="1" ctype="bold">here</g> to <x id="2"/>continue<ph id="3">&lt;br/&gt;</ph></source><target><bpt id="4">&lt;b&gt;</bpt>Ici<ept id="4">&lt;/b&gt;</ept></target></trans-unit></body></file></xliff>"#;
+    let mut store: xliff::store::Store = Store::new();
+    store.load(src.as_bytes()).unwrap();
+
+    let result = WriterXliff12::write(&store);
+    let result_string = String::from_utf8(result.unwrap()).unwrap();
+
+    assert!(result_string.contains(r#"<source>Click <g id="1" ctype="bold">here</g> to <x id="2"></x>continue<ph id="3">&lt;br/&gt;</ph></source>"#));
+    assert!(result_string.contains(
+        r#"<target><bpt id="4">&lt;b&gt;</bpt>Ici<ept id="4">&lt;/b&gt;</ept></target>"#
+    ));
+
+    let t = T::load_str(result_string.as_str()).unwrap();
+    assert!(t.store.groups[0].units()[0].source == store.groups[0].units()[0].source);
+    assert!(t.store.groups[0].units()[0].target == store.groups[0].units()[0].target);
+}
+
+#[test]
+fn test_writes_empty_inline_codes_as_configured() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body><trans-unit id="1"><source>Press <bx id="1"/>Enter<ex id="1"/> or <x id="2"/></source></trans-unit></body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let output = String::from_utf8(WriterXliff12::write(&store).unwrap()).unwrap();
+    assert!(output.contains(
+        r#"<source>Press <bx id="1"></bx>Enter<ex id="1"></ex> or <x id="2"></x></source>"#
+    ));
+
+    let options = WriterOptions {
+        self_closing: true,
+        ..WriterOptions::default()
+    };
+    let mut written = Vec::new();
+    WriterXliff12::new(options)
+        .write_to(&store, &mut written)
+        .unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(
+        output.contains(r#"<source>Press <bx id="1"/>Enter<ex id="1"/> or <x id="2"/></source>"#)
+    );
+}
+
+#[test]
+fn test_writes_nested_groups() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="top"><source>Top</source></trans-unit>
+<group id="menu" resname="MainMenu" restype="x-menu" translate="no"><note>Menu strings</note>
+<group resname="EditMenu"><trans-unit id="copy"><source>Copy</source></trans-unit></group>
+</group>
+<trans-unit id="bottom"><source>Bottom</source></trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        r#"<group id="menu" resname="MainMenu" restype="x-menu" translate="no"><note>Menu strings</note><group resname="EditMenu" translate="no"><trans-unit id="copy""#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    let file = &t.store.groups[0];
+    assert!(file.body == store.groups[0].body);
+    assert_eq!(
+        file.group(&["menu", "EditMenu"]).unwrap().units()[0].id,
+        "copy"
+    );
+}
+
+#[test]
+fn test_writes_alternate_translations() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open</source><target>Öffnen</target><note>A note</note>
+<alt-trans match-quality="87%" origin="tm" tool-id="tms" alttranstype="proposal"><source>Open it</source><target>Öffne <x id="1"/></target></alt-trans>
+<alt-trans><target>Aufmachen</target></alt-trans>
+</trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        r#"<note>A note</note><alt-trans match-quality="87%" origin="tm" tool-id="tms" alttranstype="proposal"><source>Open it</source><target>Öffne <x id="1"></x></target></alt-trans><alt-trans><target>Aufmachen</target></alt-trans></trans-unit>"#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    let unit = t.store.groups[0].units()[0];
+    assert!(unit.alternatives == store.groups[0].units()[0].alternatives);
+}
+
+#[test]
+fn test_writes_segmented_source() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1" xml:space="preserve"><source>One. Two.</source><seg-source><mrk mtype="seg" mid="1">One.</mrk> <mrk mtype="seg" mid="2">Two.</mrk></seg-source><target><mrk mtype="seg" mid="1">Eins.</mrk> <mrk mtype="seg" mid="2">Zwei.</mrk></target></trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        r#"<source>One. Two.</source><seg-source><mrk mtype="seg" mid="1">One.</mrk> <mrk mtype="seg" mid="2">Two.</mrk></seg-source><target><mrk mtype="seg" mid="1">Eins.</mrk> <mrk mtype="seg" mid="2">Zwei.</mrk></target>"#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    assert!(t.store.groups[0].units()[0].segments() == store.groups[0].units()[0].segments());
+}
+
+#[test]
+fn test_writes_workflow_state() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1" approved="yes"><source>One</source><target state="needs-review-translation" state-qualifier="x-custom">Eins</target></trans-unit>
+<trans-unit id="2"><source>Two</source><target>Zwei</target></trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        r#"<trans-unit id="1" translate="true" xml:space="preserve" approved="yes"><source>One</source><target state="needs-review-translation" state-qualifier="x-custom">Eins</target>"#
+    ));
+    assert!(output.contains(
+        r#"<trans-unit id="2" translate="true" xml:space="preserve"><source>Two</source><target>Zwei</target>"#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    let units = t.store.groups[0].units();
+    assert!(units[0].approved);
+    assert!(units[0].state == store.groups[0].units()[0].state);
+    assert!(units[0].state_qualifier == store.groups[0].units()[0].state_qualifier);
+    assert!(units[1].state.is_none());
+}
+
+#[test]
+fn test_writes_multiple_notes() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Save</source><note>Button title</note><note from="translator" priority="3" annotates="target" xml:lang="de">Kurz halten</note></trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        r#"<note>Button title</note><note xml:lang="de" from="translator" priority="3" annotates="target">Kurz halten</note>"#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    assert!(t.store.groups[0].units()[0].notes == store.groups[0].units()[0].notes);
+}
+
+#[test]
+fn test_writes_context_groups() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<group id="g"><context-group purpose="information"><context context-type="x-screen">Settings</context></context-group>
+<trans-unit id="1"><source>Open</source><target>Öffnen</target><context-group name="loc" purpose="location"><context context-type="sourcefile">menu.ts</context><context context-type="linenumber" match-mandatory="yes">12</context></context-group><note>A note</note></trans-unit>
+</group>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        r#"<group id="g" translate="yes"><context-group purpose="information"><context context-type="x-screen">Settings</context></context-group><trans-unit"#
+    ));
+    assert!(output.contains(
+        r#"<target>Öffnen</target><context-group name="loc" purpose="location"><context context-type="sourcefile">menu.ts</context><context context-type="linenumber" match-mandatory="yes">12</context></context-group><note>A note</note>"#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    let file = &t.store.groups[0];
+    assert!(file.body == store.groups[0].body);
+}
+
+#[test]
+fn test_writes_count_groups() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<group id="g"><count-group name="group"><count count-type="x-repeated" phase-name="p1" unit="segment">3</count></count-group>
+<trans-unit id="1"><source>Two words</source><note>A note</note></trans-unit>
+</group>
+</body></file></xliff>"#;
+    let mut store = T::load_str(src).unwrap().store;
+    store.compute_counts();
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        r#"<header><count-group name="total"><count count-type="total" unit="word">2</count><count count-type="total" unit="character">8</count></count-group></header>"#
+    ));
+    assert!(output.contains(
+        r#"<group id="g" translate="yes"><count-group name="group"><count count-type="x-repeated" phase-name="p1" unit="segment">3</count></count-group>"#
+    ));
+    assert!(output.contains(
+        r#"<source>Two words</source><count-group name="total-1"><count count-type="total" unit="word">2</count><count count-type="total" unit="character">8</count></count-group><note>A note</note>"#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    let file = &t.store.groups[0];
+    assert!(file.body == store.groups[0].body);
+    assert!(
+        file.header.as_ref().unwrap().count_groups
+            == store.groups[0].header.as_ref().unwrap().count_groups
+    );
+}
+
+#[test]
+fn test_writes_prop_groups() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><header>
+<prop-group name="project"><prop prop-type="x-project">Widgets</prop></prop-group><note>Header note</note>
+</header><body>
+<group id="g"><prop-group><prop prop-type="x-screenshot">settings.png</prop></prop-group>
+<trans-unit id="1"><source>Save</source><prop-group name="tracking"><prop prop-type="x-jira">ABC-123</prop><prop prop-type="x-max-length" xml:lang="de">12</prop></prop-group></trans-unit>
+</group>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        r#"<header><prop-group name="project"><prop prop-type="x-project">Widgets</prop></prop-group><note>Header note</note></header>"#
+    ));
+    assert!(output.contains(
+        r#"<group id="g" translate="yes"><prop-group><prop prop-type="x-screenshot">settings.png</prop></prop-group>"#
+    ));
+    assert!(output.contains(
+        r#"<source>Save</source><prop-group name="tracking"><prop prop-type="x-jira">ABC-123</prop><prop prop-type="x-max-length" xml:lang="de">12</prop></prop-group>"#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    let file = &t.store.groups[0];
+    assert!(file.body == store.groups[0].body);
+    assert!(
+        file.header.as_ref().unwrap().prop_groups
+            == store.groups[0].header.as_ref().unwrap().prop_groups
+    );
+}
+
+#[test]
+fn test_writes_bin_units() {
+    let src = r#"<xliff version="1.2"><file original="app.rc" source-language="en" datatype="winres"><body>
+<trans-unit id="1"><source>Open</source></trans-unit>
+<bin-unit id="icon" mime-type="image/x-icon"><bin-source><internal-file form="base64">AAEC/w==</internal-file></bin-source><bin-target><external-file href="icons/app.de.ico"/></bin-target><note>Application icon</note></bin-unit>
+<trans-unit id="2"><source>Close</source></trans-unit>
+</body></file></xliff>"#;
+    let mut store = T::load_str(src).unwrap().store;
+    if let xliff::store::BodyItem::BinUnit(bin_unit) = &mut store.groups[0].body[1] {
+        bin_unit.target = Some(xliff::store::BinFile::base64(vec![0, 1, 2, 3]));
+    }
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        r#"</trans-unit><bin-unit id="icon" mime-type="image/x-icon" translate="yes"><bin-source><internal-file form="base64">AAEC/w==</internal-file></bin-source><bin-target><internal-file form="base64">AAECAw==</internal-file></bin-target><note>Application icon</note></bin-unit><trans-unit id="2""#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    assert!(t.store.groups[0].body == store.groups[0].body);
+}
+
+#[test]
+fn test_writes_binary_internal_file_as_base64() {
+    let src = r#"<xliff version="1.2"><file original="app.rc" source-language="en" datatype="winres"><body>
+<bin-unit id="icon" mime-type="image/x-icon"><bin-source><internal-file form="text">icon</internal-file></bin-source></bin-unit>
+</body></file></xliff>"#;
+    let mut store = T::load_str(src).unwrap().store;
+    if let xliff::store::BodyItem::BinUnit(bin_unit) = &mut store.groups[0].body[0] {
+        bin_unit.target = Some(xliff::store::BinFile::Internal {
+            form: Some(String::from("text")),
+            data: vec![0xff, 0xfe, 0],
+        });
+    }
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        r#"<bin-source><internal-file form="text">icon</internal-file></bin-source><bin-target><internal-file form="base64">//4A</internal-file></bin-target>"#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    let bin_units = t.store.groups[0].bin_units();
+    assert_eq!(
+        bin_units[0].target.as_ref().unwrap().data(),
+        Some(&[0xff, 0xfe, 0][..])
+    );
+}
+
+#[test]
+fn test_writes_skeleton() {
+    let src = r#"<xliff version="1.2"><file original="readme.txt" source-language="en" datatype="plaintext"><header>
+<skl><internal-file form="base64">VGl0bGU6ICUlJXRpdGxlJSUl</internal-file></skl><tool tool-id="t" tool-name="Tool"/>
+</header><body><trans-unit id="title"><source>Hello</source></trans-unit></body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        r#"<header><skl><internal-file form="base64">VGl0bGU6ICUlJXRpdGxlJSUl</internal-file></skl><tool "#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    let header = t.store.groups[0].header.as_ref().unwrap();
+    assert!(header.skeleton == store.groups[0].header.as_ref().unwrap().skeleton);
+    assert_eq!(
+        header.skeleton.as_ref().unwrap().data(),
+        Some("Title: %%%title%%%".as_bytes())
+    );
+}
+
+#[test]
+fn test_writes_phases() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" target-language="de" datatype="plaintext"><header>
+<phase-group><phase phase-name="tr1" process-name="translation" contact-name="Anna"><note>First pass</note></phase></phase-group>
+</header><body>
+<trans-unit id="1"><source>Open</source><target phase-name="tr1">Öffnen</target></trans-unit>
+</body></file></xliff>"#;
+    let mut store = T::load_str(src).unwrap().store;
+    let file = &mut store.groups[0];
+    let mut review = xliff::store::Phase::new("rv1", "review");
+    review.date = Some(String::from("2020-05-02T09:00:00Z"));
+    file.add_phase(review);
+    file.units_mut()[0].phase_name = Some(String::from("rv1"));
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        r#"<header><phase-group><phase phase-name="tr1" process-name="translation" contact-name="Anna"><note>First pass</note></phase><phase phase-name="rv1" process-name="review" date="2020-05-02T09:00:00Z"></phase></phase-group></header>"#
+    ));
+    assert!(output.contains(r#"<target phase-name="rv1">Öffnen</target>"#));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    let file = &t.store.groups[0];
+    assert!(
+        file.header.as_ref().unwrap().phases == store.groups[0].header.as_ref().unwrap().phases
+    );
+    assert_eq!(
+        file.target_phase(file.units()[0]).unwrap().process_name,
+        "review"
+    );
+}
+
+#[test]
+fn test_add_phase_creates_header() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open</source></trans-unit>
+</body></file></xliff>"#;
+    let mut store = T::load_str(src).unwrap().store;
+    let file = &mut store.groups[0];
+
+    file.add_phase(xliff::store::Phase::new("p1", "translation"));
+    file.add_phase(xliff::store::Phase::new("p1", "review"));
+
+    let phases = &file.header.as_ref().unwrap().phases;
+    assert_eq!(phases.len(), 1);
+    assert_eq!(phases[0].process_name, "review");
+}
+
+#[test]
+fn test_writes_glossaries_and_references() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open</source></trans-unit>
+</body></file></xliff>"#;
+    let mut store = T::load_str(src).unwrap().store;
+    let glossary_path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "simplev1_2.xliff"]
+        .iter()
+        .collect();
+    let file = &mut store.groups[0];
+    file.attach_glossary(&glossary_path).unwrap();
+    let mut reference = xliff::store::ReferenceFile::external("style-guide.pdf");
+    reference.note = Some(xliff::store::Note::new("Style guide"));
+    file.add_reference(reference);
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(r#"<header><glossary><internal-file form="base64">"#));
+    assert!(output.contains(
+        r#"</glossary><reference><external-file href="style-guide.pdf"></external-file><note>Style guide</note></reference></header>"#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    let header = t.store.groups[0].header.as_ref().unwrap();
+    assert_eq!(
+        header.glossaries[0].file.data(),
+        Some(std::fs::read(&glossary_path).unwrap().as_slice())
+    );
+    assert!(header.references == store.groups[0].header.as_ref().unwrap().references);
+}
+
+#[test]
+fn test_writes_unit_metadata() {
+    let src = r#"<xliff version="1.2"><file original="strings.xml" source-language="en" datatype="x-android"><body>
+<trans-unit id="1" resname="app_name" restype="string" datatype="plaintext" maxwidth="6" minwidth="2" size-unit="char" charclass="latin"><source>Notes</source></trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        r#"<trans-unit id="1" translate="true" xml:space="preserve" resname="app_name" restype="string" datatype="plaintext" maxwidth="6" minwidth="2" size-unit="char" charclass="latin">"#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    assert!(t.store.groups[0].body == store.groups[0].body);
+}
+
+#[test]
+fn test_writes_unknown_content_back() {
+    let src = r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:1.2" xmlns:sdl="http://sdl.com/FileTypes/SdlXliff/1.0" version="1.2"><file original="a" source-language="en" datatype="plaintext"><header><tool tool-id="com.apple.dt.xcode" tool-name="Xcode" tool-version="12.0" build-num="12A6159"/></header><body>
+<!-- generated strings -->
+<group id="g" sdl:origin="tm"><sdl:seg-defs><sdl:seg id="1" conf="Translated"/></sdl:seg-defs><trans-unit id="1"><source>Open</source><target xml:lang="fr">Ouvrir</target><sdl:cmt id="c1"/></trans-unit></group>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let written = WriterXliff12::write(&store).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(r#"xmlns:sdl="http://sdl.com/FileTypes/SdlXliff/1.0">"#));
+    assert!(output.contains(r#"tool-version="12.0" build-num="12A6159">"#));
+    assert!(output.contains(r#"<body><!-- generated strings --><group id="g" translate="yes" sdl:origin="tm"><sdl:seg-defs><sdl:seg id="1" conf="Translated"/></sdl:seg-defs>"#));
+    assert!(
+        output.contains(r#"<target xml:lang="fr">Ouvrir</target><sdl:cmt id="c1"/></trans-unit>"#)
+    );
+
+    let t = T::load_str(output.as_str()).unwrap();
+    assert!(t.store.extensions == store.extensions);
+    assert!(t.store.groups[0].header == store.groups[0].header);
+    assert!(t.store.groups[0].body == store.groups[0].body);
+}
+
+#[test]
+fn test_writes_indented_document() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><header><tool tool-id="x" tool-name="X"/></header><body>
+<trans-unit id="1"><source>Open <g id="1">file</g></source><note>Menu</note></trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+    let writer = WriterXliff12::new(WriterOptions {
+        indent: Some(2),
+        newline: Newline::CrLf,
+        xml_declaration: false,
+        quote: Quote::Single,
+        self_closing: true,
+        escape_quotes: true,
+    });
+
+    let mut written = Vec::new();
+    writer.write_to(&store, &mut written).unwrap();
+    let expected = [
+        "<xliff xmlns='urn:oasis:names:tc:xliff:document:1.2' xmlns:xsi='http://www.w3.org/2001/XMLSchema-instance' version='1.2' xsi:schemaLocation='urn:oasis:names:tc:xliff:document:1.2 http://docs.oasis-open.org/xliff/v1.2/os/xliff-core-1.2-strict.xsd'>",
+        "  <file original='a' datatype='plaintext' source-language='en'>",
+        "    <header>",
+        "      <tool tool-id='x' tool-name='X'/>",
+        "    </header>",
+        "    <body>",
+        "      <trans-unit id='1' translate='true' xml:space='preserve'>",
+        "        <source>Open <g id='1'>file</g></source>",
+        "        <note>Menu</note>",
+        "      </trans-unit>",
+        "    </body>",
+        "  </file>",
+        "</xliff>",
+        "",
+    ]
+    .join("\r\n");
+    assert_eq!(String::from_utf8(written).unwrap(), expected);
+}
+
+#[test]
+fn test_writes_to_file() {
+    let sample_file: &[u8] = include_bytes!("simplev1_2.xliff");
+    let mut store: xliff::store::Store = Store::new();
+    store.load(sample_file).unwrap();
+
+    let path = std::env::temp_dir().join("xliff_test_writes_to_file.xliff");
+    let file = std::fs::File::create(&path).unwrap();
+    let writer = WriterXliff12::new(WriterOptions {
+        indent: Some(4),
+        ..WriterOptions::default()
+    });
+    writer
+        .write_to(&store, std::io::BufWriter::new(file))
+        .unwrap();
+
+    let t = T::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert!(t.store.groups[0].body == store.groups[0].body);
+    assert_eq!(
+        t.store.groups[0].header.as_ref().unwrap().tools.len(),
+        store.groups[0].header.as_ref().unwrap().tools.len()
+    );
+}
+
+#[test]
+fn test_xcode_profile_round_trips_export_unchanged() {
+    let export: &[u8] = include_bytes!("xcode_export.xliff");
+    let mut store: xliff::store::Store = Store::new();
+    store.load(export).unwrap();
+
+    let mut written = Vec::new();
+    WriterXliff12::xcode()
+        .write_to(&store, &mut written)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(written).unwrap(),
+        String::from_utf8(export.to_vec()).unwrap()
+    );
+}
+
+#[test]
+fn test_xcode_profile_writes_translate_when_disabled() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1" translate="no"><source>Open</source></trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let mut written = Vec::new();
+    WriterXliff12::xcode()
+        .write_to(&store, &mut written)
+        .unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(r#"<trans-unit id="1" xml:space="preserve" translate="no">"#));
+}
+
+fn invalid_store() -> Store {
+    let src = r#"<xliff version="1.2"><file original="strings.xml" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open</source></trans-unit>
+<group id="g"><trans-unit id="1"><source>Close</source></trans-unit></group>
+</body></file><file original="" datatype="plaintext"><body>
+<trans-unit id="2"><source>Save</source></trans-unit>
+</body></file></xliff>"#;
+    let mut store = T::load_str(src).unwrap().store;
+    let mut unit = xliff::store::Unit::new();
+    unit.id = String::from("3");
+    store.groups[1]
+        .body
+        .push(xliff::store::BodyItem::Unit(unit));
+    store
+}
+
+#[test]
+fn test_validation_lists_every_violation() {
+    let store = invalid_store();
+
+    let violations = validate(&store);
+    let found: Vec<_> = violations
+        .iter()
+        .map(|v| (v.file_index, v.unit.as_deref(), v.rule.clone()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (0, Some("1"), Rule::DuplicateUnitId),
+            (1, None, Rule::EmptyOriginal),
+            (1, None, Rule::MissingSourceLanguage),
+            (1, Some("3"), Rule::MissingSource),
+        ]
+    );
+    assert_eq!(violations[0].file, "strings.xml");
+    assert_eq!(
+        violations[0].to_string(),
+        r#"file 0 ("strings.xml"), unit "1": the unit id is used more than once"#
+    );
+}
+
+#[test]
+fn test_validation_rejects_bin_unit_sharing_unit_id() {
+    let src = r#"<xliff version="1.2"><file original="app" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open</source></trans-unit>
+<bin-unit id="1" mime-type="image/png"><bin-source><external-file href="open.png"/></bin-source></bin-unit>
+<bin-unit id="2" mime-type="image/png"><bin-source><external-file href="close.png"/></bin-source></bin-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let violations = validate(&store);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].unit.as_deref(), Some("1"));
+    assert_eq!(violations[0].rule, Rule::DuplicateUnitId);
+}
+
+#[test]
+fn test_validation_lists_bin_unit_violations_in_document_order() {
+    let src = r#"<xliff version="1.2"><file original="app" source-language="en" datatype="plaintext"><body>
+<bin-unit id="1" mime-type="image/png"><bin-source><external-file href="open.png"/></bin-source></bin-unit>
+<group id="g"><trans-unit id="1"><source>Open</source></trans-unit></group>
+<trans-unit id="2"><source>Close</source></trans-unit>
+<bin-unit id="2" mime-type="image/png"><bin-source><external-file href="close.png"/></bin-source></bin-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let violations = validate(&store);
+    let units: Vec<_> = violations.iter().map(|v| v.unit.as_deref()).collect();
+    assert_eq!(units, vec![Some("1"), Some("2")]);
+    assert!(violations.iter().all(|v| v.rule == Rule::DuplicateUnitId));
+}
+
+#[test]
+fn test_invalid_store_is_not_written() {
+    let store = invalid_store();
+
+    let mut written = Vec::new();
+    let error = WriterXliff12::default()
+        .write_checked(&store, &mut written)
+        .err()
+        .unwrap();
+    assert!(written.is_empty());
+    let error = error.downcast_ref::<ValidationError>().unwrap();
+    assert_eq!(error.violations.len(), 4);
+    assert!(WriterXliff12::write(&store).is_err());
+}
+
+#[test]
+fn test_lenient_writer_returns_warnings() {
+    let store = invalid_store();
+    let writer = WriterXliff12 {
+        lenient: true,
+        ..WriterXliff12::default()
+    };
+
+    let mut written = Vec::new();
+    let warnings = writer.write_checked(&store, &mut written).unwrap();
+    assert_eq!(warnings.len(), 4);
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(r#"<file original="" datatype="plaintext">"#));
+    assert!(!output.contains(r#"<trans-unit id="3""#));
+}
+
+const REVERSE_SAMPLE: &str = r#"<xliff version="1.2"><file original="app" source-language="en" target-language="fr" datatype="plaintext"><body>
+<trans-unit id="1"><source xml:lang="en">Open <g id="b">now</g></source><target state="translated">Ouvrir <g id="b">maintenant</g></target><note annotates="source">verb</note></trans-unit>
+<group id="menu"><trans-unit id="2"><source>Close</source></trans-unit></group>
+</body></file></xliff>"#;
+
+#[test]
+fn test_writes_reversed_store() {
+    let t = T::load_str(REVERSE_SAMPLE).unwrap();
+
+    let reversed = t.store.reversed(UntranslatedUnits::Drop);
+    let result = WriterXliff12::write(&reversed).unwrap();
+    let written = T::load_str(String::from_utf8(result).unwrap().as_str()).unwrap();
+
+    let file = &written.store.groups[0];
+    assert_eq!(file.source_locale.as_ref().unwrap().identifier, "fr");
+    assert_eq!(file.target_locale.as_ref().unwrap().identifier, "en");
+    let units = file.units();
+    assert_eq!(units.len(), 1);
+    assert!(units[0].source == t.store.groups[0].units()[0].target);
+    assert!(units[0].target == t.store.groups[0].units()[0].source);
+    assert!(units[0].state.is_none());
+    assert!(units[0].target_extensions.is_empty());
+    assert!(units[0].notes[0].annotates == Some(Annotates::Target));
+    assert!(written
+        .store
+        .units_with_mismatched_target_language(None)
+        .is_empty());
+}
+
+#[test]
+fn test_reversed_store_keeps_untranslated_units_empty() {
+    let t = T::load_str(REVERSE_SAMPLE).unwrap();
+
+    let reversed = t.store.reversed(UntranslatedUnits::KeepEmpty);
+    assert!(validate(&reversed).is_empty());
+
+    let units = reversed.groups[0].units();
+    assert_eq!(units.len(), 2);
+    assert_eq!(units[1].source_text().as_deref(), Some(""));
+    assert_eq!(units[1].target_text().as_deref(), Some("Close"));
+
+    let result = String::from_utf8(WriterXliff12::write(&reversed).unwrap()).unwrap();
+    assert!(result.contains(r#"<source></source><target>Close</target>"#));
+}
+
+#[test]
+fn test_reversed_store_rebuilds_segments_and_drops_counts() {
+    let src = r#"<xliff version="1.2"><file original="app" source-language="en" target-language="fr" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open. Close.</source><seg-source><mrk mtype="seg" mid="1">Open.</mrk> <mrk mtype="seg" mid="2">Close.</mrk></seg-source><target><mrk mtype="seg" mid="1">Ouvrir.</mrk> <mrk mtype="seg" mid="2">Fermer.</mrk></target></trans-unit>
+</body></file></xliff>"#;
+    let mut store = T::load_str(src).unwrap().store;
+    store.compute_counts();
+
+    let reversed = store.reversed(UntranslatedUnits::Drop);
+    let result = WriterXliff12::write(&reversed).unwrap();
+    let written = T::load_str(String::from_utf8(result).unwrap().as_str()).unwrap();
+
+    let file = &written.store.groups[0];
+    assert!(file
+        .header
+        .as_ref()
+        .map_or(true, |header| header.count_groups.is_empty()));
+    let unit = &file.units()[0];
+    assert!(unit.count_groups.is_empty());
+    assert_eq!(unit.source_text().as_deref(), Some("Ouvrir. Fermer."));
+    let segments = unit.segments();
+    assert_eq!(segments.len(), 2);
+    assert_eq!(segments[1].mid, "2");
+    assert_eq!(segments[1].source.plain_text(), "Fermer.");
+    assert_eq!(
+        segments[1].target.as_ref().map(|t| t.plain_text()),
+        Some(String::from("Close."))
+    );
+}
+
+#[test]
+fn test_round_trips_unknown_nodes_in_place() {
+    let src = r#"<?xml version="1.0" encoding="UTF-8"?><xliff xmlns="urn:oasis:names:tc:xliff:document:1.2" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" version="1.2" xsi:schemaLocation="urn:oasis:names:tc:xliff:document:1.2 http://docs.oasis-open.org/xliff/v1.2/os/xliff-core-1.2-strict.xsd" xmlns:sdl="urn:example:sdl"><!-- before file --><file original="a" datatype="plaintext" source-language="en" target-language="fr"><header><?sdl start?><tool tool-id="t" tool-name="T"><sdl:build>12</sdl:build></tool><!-- before note --><note>Header</note></header><body><trans-unit id="1" translate="true" xml:space="preserve"><!-- first --><source>Hello <sdl:cmt>SECRET</sdl:cmt> <g id="1">big<!-- in g --></g> world<?pi data?></source><sdl:seg/><target>Bonjour <sdl:cmt>SECRET</sdl:cmt> monde</target><!-- before note --><note>See <sdl:ref id="4"/> for details</note><alt-trans match-quality="80"><source xml:lang="en">Hello</source><seg-source><mrk mtype="seg" mid="1">Hello</mrk></seg-source><target xml:lang="de" state="translated" phase-name="p1">Hallo</target><sdl:origin/><context-group name="c"><context context-type="sourcefile">a.c</context></context-group><prop-group><prop prop-type="x-origin">TM</prop></prop-group><note>From the TM</note></alt-trans></trans-unit></body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let output = String::from_utf8(WriterXliff12::write(&store).unwrap()).unwrap();
+    assert_eq!(output, src);
+}
+
+#[test]
+fn test_round_trips_children_in_document_order() {
+    let src = r#"<?xml version="1.0" encoding="UTF-8"?><xliff xmlns="urn:oasis:names:tc:xliff:document:1.2" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" version="1.2" xsi:schemaLocation="urn:oasis:names:tc:xliff:document:1.2 http://docs.oasis-open.org/xliff/v1.2/os/xliff-core-1.2-strict.xsd" xmlns:xlf="urn:oasis:names:tc:xliff:document:1.2" xmlns:sdl="urn:example:sdl"><file original="a" datatype="plaintext" source-language="en"><header><note>Header</note><!-- after note --><tool tool-id="t" tool-name="T"></tool></header><body><group id="g" translate="yes"><note>Group</note><sdl:info><xlf:note>Kept</xlf:note></sdl:info><context-group name="c"><context context-type="sourcefile">a.c</context></context-group><trans-unit id="1" translate="true" xml:space="preserve"><source>Hello</source></trans-unit><!-- after unit --></group><trans-unit id="2" translate="true" xml:space="preserve"><source>Open</source><note>Before the target</note><target>Ouvrir</target><alt-trans><target>Ouvre</target></alt-trans><!-- between --><note>After the alternative</note><prop-group><prop prop-type="a">1</prop></prop-group><count-group name="c1"><count count-type="total" unit="word">1</count></count-group><prop-group><prop prop-type="b">2</prop></prop-group></trans-unit></body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let output = String::from_utf8(WriterXliff12::write(&store).unwrap()).unwrap();
+    assert_eq!(output, src);
+}
+
+#[test]
+fn test_writes_added_children_in_schema_order() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open</source><note>First</note><target>Ouvrir</target></trans-unit>
+</body></file></xliff>"#;
+    let mut store = T::load_str(src).unwrap().store;
+    let unit = &mut store.groups[0].units_mut()[0];
+    unit.notes.push(xliff::store::Note::new("Second"));
+    unit.seg_source = Some(xliff::store::UnitValue::new("Open"));
+
+    let output = String::from_utf8(WriterXliff12::write(&store).unwrap()).unwrap();
+    assert!(output.contains(
+        "<source>Open</source><seg-source>Open</seg-source><note>First</note><note>Second</note><target>Ouvrir</target>"
+    ));
+}
+
+#[test]
+fn test_writes_empty_header() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><header/><body/></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let output = String::from_utf8(WriterXliff12::write(&store).unwrap()).unwrap();
+    assert!(output.contains("<header></header><body></body>"));
+}
@@ -0,0 +1,388 @@
+extern crate xliff;
+
+use xliff::store::Store;
+use xliff::t::T;
+use xliff::writers::conversion;
+use xliff::writers::options::WriterOptions;
+use xliff::writers::xliff20::*;
+
+/// Loads the sample document, with the targets which declare their own language moved to the
+/// language of their file as XLIFF 2.0 documents hold a single target language.
+fn sample_store() -> Store {
+    let sample_file: &[u8] = include_bytes!("simplev1_2.xliff");
+    let mut store: xliff::store::Store = Store::new();
+    store.load(sample_file).unwrap();
+    for file in store.groups.iter_mut() {
+        let target_locale = file.target_locale.clone();
+        for unit in file.units_mut() {
+            unit.target_locale = target_locale.clone();
+        }
+    }
+    store
+}
+
+#[test]
+fn test_print_store_with_source_language() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body></body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+    let expected = r#"<?xml version="1.0" encoding="UTF-8"?><xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" xmlns:mda="urn:oasis:names:tc:xliff:metadata:2.0" version="2.0" srcLang="en"><file id="f1" original="a"><mda:metadata><mda:metaGroup category="datatype"><mda:meta type="datatype">plaintext</mda:meta></mda:metaGroup></mda:metadata></file></xliff>"#;
+
+    let result = WriterXliff20::write(&store);
+    assert_eq!(
+        String::from_utf8(result.unwrap()).unwrap(),
+        expected.to_string()
+    );
+}
+
+#[test]
+fn test_rejects_store_without_source_language() {
+    assert!(WriterXliff20::write(&Store::new()).is_err());
+
+    let src = r#"<xliff version="1.2"><file original="a" datatype="plaintext"><body></body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+    let error = WriterXliff20::write(&store).err().unwrap();
+    assert!(error.to_string().contains("srcLang"));
+}
+
+#[test]
+fn test_rejects_target_in_other_language() {
+    let sample_file: &[u8] = include_bytes!("simplev1_2.xliff");
+    let mut store: xliff::store::Store = Store::new();
+    store.load(sample_file).unwrap();
+
+    let error = WriterXliff20::write(&store).err().unwrap();
+    assert!(error.to_string().contains("`fr` in unit `Some text2`"));
+}
+
+#[test]
+fn test_writes_languages_on_root() {
+    let store = sample_store();
+
+    let result = WriterXliff20::write(&store);
+    let result_string = String::from_utf8(result.unwrap()).unwrap();
+
+    assert!(result_string.contains(r#"version="2.0" srcLang="en" trgLang="bg">"#));
+}
+
+#[test]
+fn test_rejects_mixed_languages() {
+    let mut store = sample_store();
+    store.groups[1].target_locale = store.groups[0].source_locale.clone();
+
+    assert!(WriterXliff20::write(&store).is_err());
+}
+
+#[test]
+fn test_writes_file_for_each_group_in_order() {
+    let store = sample_store();
+
+    let result = WriterXliff20::write(&store);
+    let result_string = String::from_utf8(result.unwrap()).unwrap();
+
+    let t = T::load_str(result_string.as_str()).unwrap();
+
+    assert_eq!(t.store.groups.len(), 4);
+    for (ix, group) in store.groups.iter().enumerate() {
+        let written = t.store.groups.get(ix).unwrap();
+        assert_eq!(written.id, Some(format!("f{}", ix + 1)));
+        assert!(written.address == group.address);
+        assert!(written.source_locale == group.source_locale);
+        assert!(written.target_locale == group.target_locale);
+        assert!(written.data_type == group.data_type);
+    }
+}
+
+#[test]
+fn test_writes_header_as_metadata_and_notes() {
+    let store = sample_store();
+
+    let result = WriterXliff20::write(&store);
+    let result_string = String::from_utf8(result.unwrap()).unwrap();
+
+    assert!(result_string.contains(
+        r#"<mda:metaGroup category="tool"><mda:meta type="tool-id">com.apple.dt.xcode</mda:meta>"#
+    ));
+
+    let t = T::load_str(result_string.as_str()).unwrap();
+
+    let header = store.groups[0].header.as_ref().unwrap();
+    let written = t.store.groups[0].header.as_ref().unwrap();
+    assert_eq!(written.tools.len(), header.tools.len());
+    assert!(written.tools[0] == header.tools[0]);
+    assert_eq!(written.notes.len(), header.notes.len());
+    for (ix, note) in header.notes.iter().enumerate() {
+        assert_eq!(written.notes[ix].text, note.text);
+    }
+}
+
+#[test]
+fn test_writes_units() {
+    let store = sample_store();
+
+    let result = WriterXliff20::write(&store);
+    let result_string = String::from_utf8(result.unwrap()).unwrap();
+
+    assert!(result_string.contains(r#"<unit id="2" translate="no">"#));
+
+    let t = T::load_str(result_string.as_str()).unwrap();
+
+    let mut at_least_one_unit = false;
+    for (g_ix, group) in store.groups.iter().enumerate() {
+        let written_group = t.store.groups.get(g_ix).unwrap();
+
+        for (u_ix, unit) in group.units().iter().enumerate() {
+            at_least_one_unit = true;
+
+            let written_unit = written_group.units()[u_ix];
+            assert_eq!(written_unit.id, unit.id);
+            assert!(written_unit.source == unit.source);
+            assert!(written_unit.target == unit.target);
+            assert!(written_unit.notes == unit.notes);
+            assert_eq!(written_unit.translate, unit.translate);
+        }
+    }
+
+    assert!(
+        at_least_one_unit,
+        "Test data is missing at least one translation unit."
+    );
+}
+
+#[test]
+fn test_writes_note_priority_and_applies_to() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source>Save</source><note>Button title</note><note from="translator" priority="3" annotates="target">Kurz halten</note><note annotates="general">General</note></trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    // XLIFF 2.0 has no equivalent of `from`
+    assert!(WriterXliff20::write(&store).is_err());
+    let mut writer = WriterXliff20::new(WriterOptions::default());
+    writer.lenient = true;
+    let mut output = vec![];
+    let losses = writer.write_checked(&store, &mut output).unwrap();
+    assert_eq!(losses.len(), 1);
+    assert_eq!(
+        losses[0].to_string(),
+        r#"file 0 ("a"), unit "1": the from attribute of <note> has no XLIFF 2.0 equivalent"#
+    );
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains(
+        r#"<notes><note>Button title</note><note priority="3" appliesTo="target">Kurz halten</note><note>General</note></notes>"#
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    let notes = &t.store.groups[0].units()[0].notes;
+    assert_eq!(notes.len(), 3);
+    assert_eq!(notes[1].priority, Some(3));
+    assert!(notes[1].annotates == Some(xliff::store::Annotates::Target));
+}
+
+#[test]
+fn test_writes_indented_document() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" target-language="fr" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open</source><target>Ouvrir</target></trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+    let writer = WriterXliff20::new(WriterOptions {
+        indent: Some(1),
+        ..WriterOptions::default()
+    });
+
+    let mut written = Vec::new();
+    writer.write_to(&store, &mut written).unwrap();
+    let output = String::from_utf8(written).unwrap();
+    assert!(output.contains(
+        "\n <file id=\"f1\" original=\"a\">\n  <mda:metadata>\n   <mda:metaGroup category=\"datatype\">\n    <mda:meta type=\"datatype\">plaintext</mda:meta>\n"
+    ));
+    assert!(output.contains(
+        "\n   <segment>\n    <source>Open</source>\n    <target>Ouvrir</target>\n   </segment>\n"
+    ));
+
+    let t = T::load_str(output.as_str()).unwrap();
+    assert_eq!(
+        t.store.groups[0].units()[0].target_text().unwrap(),
+        "Ouvrir"
+    );
+}
+
+const MAPPING_SAMPLE: &str = r#"<xliff version="1.2"><file original="a" source-language="en" target-language="fr" datatype="plaintext"><header>
+<prop-group name="project"><prop prop-type="x-ticket">L10N-1</prop></prop-group>
+</header><body>
+<group resname="menu" restype="x-menu" translate="no"><note>Menu strings</note>
+<trans-unit id="1" resname="open" restype="string" maxwidth="10" size-unit="char" approved="yes"><source>Open</source><target state="signed-off">Ouvrir</target>
+<context-group name="location" purpose="location"><context context-type="sourcefile">menu.c</context><context context-type="linenumber">12</context></context-group>
+<count-group name="words"><count count-type="total" unit="word">1</count></count-group>
+</trans-unit>
+<group id="edit"><trans-unit id="2"><source>Copy</source><target state="needs-review-translation" state-qualifier="fuzzy-match">Copier</target></trans-unit></group>
+</group>
+<trans-unit id="3"><source>Close</source><target state="x-pending">Fermer</target></trans-unit>
+</body></file></xliff>"#;
+
+#[test]
+fn test_writes_groups_names_and_states() {
+    let store = T::load_str(MAPPING_SAMPLE).unwrap().store;
+
+    let output = String::from_utf8(WriterXliff20::write(&store).unwrap()).unwrap();
+    assert!(output.contains(
+        r#"<group id="g1" name="menu" translate="no"><mda:metadata><mda:metaGroup category="group"><mda:meta type="restype">x-menu</mda:meta></mda:metaGroup></mda:metadata><notes><note>Menu strings</note></notes><unit id="1" name="open" translate="no">"#
+    ));
+    assert!(output.contains(r#"<segment state="reviewed"><source>Open</source>"#));
+    assert!(output.contains(r#"<group id="edit" translate="no"><unit id="2" translate="no">"#));
+    assert!(output.contains(
+        r#"<mda:metaGroup category="target"><mda:meta type="state">needs-review-translation</mda:meta><mda:meta type="state-qualifier">fuzzy-match</mda:meta></mda:metaGroup>"#
+    ));
+    assert!(output.contains(r#"<unit id="3"><mda:metadata><mda:metaGroup category="target"><mda:meta type="state">x-pending</mda:meta>"#));
+
+    let written = T::load_str(output.as_str()).unwrap().store;
+    let file = &written.groups[0];
+    let menu = file.group(&["g1"]).unwrap();
+    assert_eq!(menu.resname.as_deref(), Some("menu"));
+    assert_eq!(menu.restype.as_deref(), Some("x-menu"));
+    assert!(!menu.translate);
+    assert_eq!(menu.notes[0].text, "Menu strings");
+    assert!(file.group(&["g1", "edit"]).is_some());
+
+    for (unit, written) in store.groups[0].units().iter().zip(file.units()) {
+        assert_eq!(written.id, unit.id);
+        assert!(written.state == unit.state);
+        assert!(written.state_qualifier == unit.state_qualifier);
+        assert_eq!(written.translate, unit.translate);
+    }
+}
+
+#[test]
+fn test_writes_unmapped_data_as_metadata() {
+    let store = T::load_str(MAPPING_SAMPLE).unwrap().store;
+
+    let output = String::from_utf8(WriterXliff20::write(&store).unwrap()).unwrap();
+    assert!(output.contains(
+        r#"<mda:metaGroup category="prop-group"><mda:meta type="name">project</mda:meta><mda:meta type="x-ticket">L10N-1</mda:meta></mda:metaGroup>"#
+    ));
+    assert!(output.contains(
+        r#"<mda:metaGroup category="count-group"><mda:meta type="name">words</mda:meta><mda:meta type="total:word">1</mda:meta></mda:metaGroup>"#
+    ));
+
+    let written = T::load_str(output.as_str()).unwrap().store;
+    let file = &written.groups[0];
+    assert_eq!(file.prop("x-ticket"), Some("L10N-1"));
+
+    let unit = file.units()[0];
+    let original = store.groups[0].units()[0];
+    assert_eq!(unit.resname.as_deref(), Some("open"));
+    assert_eq!(unit.restype.as_deref(), Some("string"));
+    assert_eq!(unit.max_width, Some(10));
+    assert_eq!(unit.size_unit.as_deref(), Some("char"));
+    assert!(unit.approved);
+    assert!(unit.context_groups == original.context_groups);
+    assert!(unit.count_groups == original.count_groups);
+    assert_eq!(unit.context("linenumber"), Some("12"));
+}
+
+#[test]
+fn test_writes_inline_codes() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" target-language="fr" datatype="html"><body>
+<trans-unit id="1"><source>Click <g id="1" ctype="bold">here</g><x id="2" equiv-text="{0}"/> to <bpt id="3">&lt;i&gt;</bpt>open<ept id="3">&lt;/i&gt;</ept> a <mrk mtype="term" mid="m1">file</mrk><ph id="4">&lt;br/&gt;</ph><it id="5" pos="open">&lt;u&gt;</it></source><target>Cliquer <g id="1" ctype="bold">ici</g><x id="2" equiv-text="{0}"/> pour <bpt id="3">&lt;i&gt;</bpt>ouvrir<ept id="3">&lt;/i&gt;</ept> un <mrk mtype="term" mid="m1">fichier</mrk><ph id="4">&lt;br/&gt;</ph><it id="5" pos="open">&lt;u&gt;</it></target></trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let output = String::from_utf8(WriterXliff20::write(&store).unwrap()).unwrap();
+    assert!(output.contains(
+        r#"<originalData><data id="d1">&lt;i&gt;</data><data id="d2">&lt;/i&gt;</data><data id="d3">&lt;br/&gt;</data><data id="d4">&lt;u&gt;</data></originalData>"#
+    ));
+    assert!(output.contains(
+        r#"<source>Click <pc id="1" type="fmt" subType="xlf:b">here</pc><ph id="2" equiv="{0}"></ph> to <sc id="3" dataRef="d1"></sc>open<ec startRef="3" dataRef="d2"></ec> a <mrk id="m1" type="term">file</mrk><ph id="4" dataRef="d3"></ph><sc id="5" isolated="yes" dataRef="d4"></sc></source>"#
+    ));
+    assert!(output.contains(r#"<mrk id="m1" type="term">fichier</mrk>"#));
+
+    let written = T::load_str(output.as_str()).unwrap().store;
+    let unit = written.groups[0].units()[0];
+    let original = store.groups[0].units()[0];
+    assert!(unit.source == original.source);
+    assert!(unit.target == original.target);
+}
+
+#[test]
+fn test_writes_unpaired_codes_as_isolated_and_identifies_markers() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<trans-unit id="1"><source><bx id="1"/>a<ex id="2"/><bx id="3" rid="r"/>b<ex id="4" rid="r"/><mrk mtype="x-generic">c</mrk></source></trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let output = String::from_utf8(WriterXliff20::write(&store).unwrap()).unwrap();
+    assert!(output.contains(
+        r#"<source><sc id="1" isolated="yes"></sc>a<ec id="2" isolated="yes"></ec><sc id="3"></sc>b<ec startRef="3"></ec><mrk id="5">c</mrk></source>"#
+    ));
+}
+
+#[test]
+fn test_reports_data_without_equivalent() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" target-language="fr" datatype="plaintext"><body>
+<trans-unit id="1"><source>Open</source><target>Ouvrir</target><alt-trans><target>Ouvre</target></alt-trans></trans-unit>
+<bin-unit id="2" mime-type="image/png"><bin-source><external-file href="a.png"/></bin-source></bin-unit>
+<trans-unit id="3"><source><x id="1" ctype="x-custom"/></source></trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let expected = [
+        r#"file 0 ("a"), unit "1": <alt-trans> has no XLIFF 2.0 equivalent"#,
+        r#"file 0 ("a"), unit "2": <bin-unit> has no XLIFF 2.0 equivalent"#,
+        r#"file 0 ("a"), unit "3": the ctype attribute of <x> has no XLIFF 2.0 equivalent"#,
+    ];
+    let losses: Vec<String> = conversion::losses(&store)
+        .iter()
+        .map(|loss| loss.to_string())
+        .collect();
+    assert_eq!(losses, expected);
+
+    let mut output = vec![];
+    let writer = WriterXliff20::new(WriterOptions::default());
+    let error = writer.write_checked(&store, &mut output).err().unwrap();
+    let error = error.downcast::<conversion::ConversionError>().unwrap();
+    assert_eq!(error.losses.len(), 3);
+    assert!(output.is_empty());
+
+    let mut writer = writer;
+    writer.lenient = true;
+    let losses = writer.write_checked(&store, &mut output).unwrap();
+    assert_eq!(losses.len(), 3);
+    let output = String::from_utf8(output).unwrap();
+    assert!(!output.contains("bin-unit"));
+    assert!(output.contains(r#"<source><ph id="1"></ph></source>"#));
+}
+
+#[test]
+fn test_generates_group_ids_not_used_in_file() {
+    let src = r#"<xliff version="1.2"><file original="a" source-language="en" datatype="plaintext"><body>
+<group id="g1"><trans-unit id="g2"><source>a</source></trans-unit></group>
+<group><trans-unit id="1"><source>b</source></trans-unit></group>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let output = String::from_utf8(WriterXliff20::write(&store).unwrap()).unwrap();
+    assert!(output.contains(r#"<group id="g1">"#));
+    assert!(output.contains(r#"<group id="g3">"#));
+}
+
+#[test]
+fn test_writes_unrecognised_attributes_as_metadata() {
+    let src = r#"<xliff version="1.2" xmlns:okp="okapi-framework:xliff-extensions"><file original="a" source-language="en" datatype="plaintext" okp:build="12"><body>
+<trans-unit id="1" okp:engine="mt"><source>Open</source></trans-unit>
+</body></file></xliff>"#;
+    let store = T::load_str(src).unwrap().store;
+
+    let output = String::from_utf8(WriterXliff20::write(&store).unwrap()).unwrap();
+    assert!(output.contains(r#" xmlns:okp="okapi-framework:xliff-extensions""#));
+    assert!(output.contains(
+        r#"<mda:metaGroup category="attributes"><mda:meta type="okp:engine">mt</mda:meta></mda:metaGroup>"#
+    ));
+
+    let written = T::load_str(output.as_str()).unwrap().store;
+    assert!(written.groups[0].extensions == store.groups[0].extensions);
+    assert!(written.groups[0].units()[0].extensions == store.groups[0].units()[0].extensions);
+    assert!(written.extensions.attributes.contains(&(
+        String::from("xmlns:okp"),
+        String::from("okapi-framework:xliff-extensions")
+    )));
+}